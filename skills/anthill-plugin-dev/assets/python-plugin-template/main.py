@@ -11,6 +11,7 @@ def main() -> None:
         ANTHILL_PLUGIN_PARAMS: JSON string containing user parameters
         ANTHILL_PHASE: Either "prepare" (preview) or "apply" (execute)
         ANTHILL_PREVIEW_PLAN: JSON string with preview data (apply phase only)
+        ANTHILL_PLUGIN_STATE_DIR: Directory for data that persists between runs
     """
     # Get environment variables
     raw_params = os.getenv("ANTHILL_PLUGIN_PARAMS")
@@ -0,0 +1,47 @@
+use super::format_timestamp;
+use crate::models::AuditEntry;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Deserialize)]
+pub struct ListAuditQuery {
+    /// Inclusive lower bound, epoch millis.
+    pub since: Option<i64>,
+    /// Inclusive upper bound, epoch millis.
+    pub until: Option<i64>,
+    pub plugin_id: Option<String>,
+    pub action: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntryResponse {
+    pub id: String,
+    pub action: String,
+    pub plugin_id: String,
+    pub actor: Option<String>,
+    pub timestamp: i64,
+    pub timestamp_rfc3339: String,
+    pub detail: Option<Value>,
+}
+
+impl From<AuditEntry> for AuditEntryResponse {
+    fn from(entry: AuditEntry) -> Self {
+        Self {
+            id: entry.id,
+            action: entry.action,
+            plugin_id: entry.plugin_id,
+            actor: entry.actor,
+            timestamp: entry.timestamp,
+            timestamp_rfc3339: format_timestamp(entry.timestamp),
+            detail: entry
+                .detail
+                .as_deref()
+                .and_then(|raw| serde_json::from_str(raw).ok()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditListResponse {
+    pub data: Vec<AuditEntryResponse>,
+}
@@ -1,4 +1,5 @@
-use crate::models::Execution;
+use super::format_timestamp;
+use crate::models::{Execution, Plugin};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -6,12 +7,21 @@ use std::collections::HashMap;
 #[derive(Debug, Deserialize)]
 pub struct ExecutePluginRequest {
     pub params: Option<HashMap<String, Value>>,
+    pub timeout_ms: Option<u64>,
+    pub execution_id: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Name of a `metadata.commands` entry to run instead of the entry point.
+    pub command: Option<String>,
+    /// Receives a POST of the final execution once it finishes.
+    pub callback_url: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ApplyExecutionRequest {
     pub confirm_token: String,
     pub params: Option<HashMap<String, Value>>,
+    pub timeout_ms: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -22,6 +32,10 @@ pub struct ExecutionResponse {
     pub status: String,
     pub pid: Option<i32>,
     pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_signal: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_signal_name: Option<String>,
     pub stdout: Option<String>,
     pub stderr: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -32,10 +46,60 @@ pub struct ExecutionResponse {
     pub expires_at: Option<i64>,
     pub started_at: i64,
     pub finished_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_at: Option<i64>,
+    pub duration_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prepare_duration_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview_stdout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview_stderr: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    pub dry_run: bool,
+    /// 1-based place among executions waiting for a concurrency slot.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at_rfc3339: Option<String>,
+    pub started_at_rfc3339: String,
+    pub finished_at_rfc3339: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_at_rfc3339: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<ExecutionPluginSummary>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionPluginSummary {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub plugin_type: String,
+}
+
+impl From<Plugin> for ExecutionPluginSummary {
+    fn from(plugin: Plugin) -> Self {
+        Self {
+            id: plugin.plugin_id,
+            name: plugin.name,
+            version: plugin.version,
+            plugin_type: format!("{:?}", plugin.plugin_type),
+        }
+    }
 }
 
 impl From<Execution> for ExecutionResponse {
     fn from(execution: Execution) -> Self {
+        let duration_ms = execution.duration_ms();
+        let exit_signal_name = execution.exit_signal_name().map(str::to_string);
         Self {
             id: execution.id,
             plugin_id: execution.plugin_id,
@@ -43,6 +107,8 @@ impl From<Execution> for ExecutionResponse {
             status: format!("{:?}", execution.status),
             pid: execution.pid,
             exit_code: execution.exit_code,
+            exit_signal: execution.exit_signal,
+            exit_signal_name,
             stdout: execution.stdout,
             stderr: execution.stderr,
             preview_payload: execution.preview_payload,
@@ -50,6 +116,25 @@ impl From<Execution> for ExecutionResponse {
             expires_at: execution.expires_at,
             started_at: execution.started_at,
             finished_at: execution.finished_at,
+            applied_at: execution.applied_at,
+            duration_ms,
+            prepare_duration_ms: execution.prepare_duration_ms,
+            preview_stdout: execution.preview_stdout,
+            preview_stderr: execution.preview_stderr,
+            idempotency_key: execution.idempotency_key,
+            dry_run: execution.dry_run,
+            queue_position: None,
+            command: execution.command,
+            callback_url: execution.callback_url,
+            params: execution
+                .params
+                .as_deref()
+                .and_then(|raw| serde_json::from_str(raw).ok()),
+            expires_at_rfc3339: execution.expires_at.map(format_timestamp),
+            started_at_rfc3339: format_timestamp(execution.started_at),
+            finished_at_rfc3339: execution.finished_at.map(format_timestamp),
+            applied_at_rfc3339: execution.applied_at.map(format_timestamp),
+            plugin: None,
         }
     }
 }
@@ -58,3 +143,61 @@ impl From<Execution> for ExecutionResponse {
 pub struct ExecutionsListResponse {
     pub data: Vec<ExecutionResponse>,
 }
+
+#[derive(Debug, Serialize)]
+pub struct PrunePreviewsResponse {
+    pub pruned: usize,
+    pub execution_ids: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ExecutionPhase, ExecutionStatus};
+
+    fn execution(finished_at: Option<i64>) -> Execution {
+        Execution {
+            id: "e1".to_string(),
+            plugin_id: "demo".to_string(),
+            phase: ExecutionPhase::Apply,
+            status: ExecutionStatus::Running,
+            pid: None,
+            exit_code: None,
+            stdout: None,
+            stderr: None,
+            preview_payload: None,
+            confirm_token: None,
+            expires_at: None,
+            started_at: 1_700_000_000_000,
+            finished_at,
+            applied_at: None,
+            prepare_duration_ms: None,
+            preview_stdout: None,
+            preview_stderr: None,
+            idempotency_key: None,
+            dry_run: false,
+            params: None,
+            exit_signal: None,
+            command: None,
+            callback_url: None,
+        }
+    }
+
+    #[test]
+    fn rfc3339_companions_mirror_the_millisecond_fields() {
+        let body =
+            serde_json::to_value(ExecutionResponse::from(execution(Some(1_700_000_001_500))))
+                .unwrap();
+        assert_eq!(body["started_at"], 1_700_000_000_000i64);
+        assert_eq!(body["started_at_rfc3339"], "2023-11-14T22:13:20.000Z");
+        assert_eq!(body["finished_at_rfc3339"], "2023-11-14T22:13:21.500Z");
+    }
+
+    #[test]
+    fn unset_timestamps_have_no_rfc3339_value() {
+        let body = serde_json::to_value(ExecutionResponse::from(execution(None))).unwrap();
+        assert!(body["finished_at_rfc3339"].is_null());
+        assert!(body.get("expires_at_rfc3339").is_none());
+        assert!(body.get("applied_at_rfc3339").is_none());
+    }
+}
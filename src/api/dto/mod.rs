@@ -1,3 +1,28 @@
+pub mod audit;
 pub mod execution;
 pub mod plugin;
 pub mod update;
+
+use chrono::{DateTime, SecondsFormat};
+
+pub(crate) fn format_timestamp(millis: i64) -> String {
+    DateTime::from_timestamp_millis(millis)
+        .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_timestamp_is_utc_rfc3339_with_millis() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            format_timestamp(1_700_000_000_123),
+            "2023-11-14T22:13:20.123Z"
+        );
+        let parsed = DateTime::parse_from_rfc3339(&format_timestamp(1_700_000_000_123)).unwrap();
+        assert_eq!(parsed.timestamp_millis(), 1_700_000_000_123);
+    }
+}
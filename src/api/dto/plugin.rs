@@ -1,16 +1,86 @@
+use super::format_timestamp;
 use crate::error::AppError;
-use crate::models::{Plugin, PluginParameter, PluginParameterGroup, PythonDependencies};
-use serde_json::Value;
+use crate::models::{
+    LastExecution, Plugin, PluginParameter, PluginParameterGroup, PluginVersion, PythonDependencies,
+};
+use crate::services::{BatchInstallFailure, PackageValidation, PythonEnvStatus};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Deserialize)]
 pub struct InstallPluginRequest {
     pub package_url: String,
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InstallFromMetadataRequest {
+    pub metadata_url: String,
+    #[serde(default)]
+    pub continue_on_error: bool,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdatePluginRequest {
     pub package_url: String,
+    #[serde(default)]
+    pub allow_downgrade: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListPluginsQuery {
+    pub tag: Option<String>,
+    /// Comma-separated extras; `last_execution` adds each plugin's latest run.
+    pub include: Option<String>,
+    /// `order` sorts by `metadata.order` (unset last); default is newest first.
+    pub sort: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidatePluginRequest {
+    pub package_url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidatePluginResponse {
+    pub valid: bool,
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub min_anthill_version: Option<String>,
+    pub plugin_type: String,
+    pub description: String,
+    pub author: String,
+    pub entry_point: String,
+    pub parameters: Option<Vec<PluginParameter>>,
+    pub groups: Option<Vec<PluginParameterGroup>>,
+    pub metadata: Option<Value>,
+    pub tags: Vec<String>,
+    pub python_dependencies: Option<PythonDependencies>,
+    pub warnings: Vec<String>,
+}
+
+impl From<PackageValidation> for ValidatePluginResponse {
+    fn from(validation: PackageValidation) -> Self {
+        Self {
+            valid: true,
+            id: validation.plugin_id,
+            name: validation.name,
+            version: validation.version,
+            min_anthill_version: validation.min_anthill_version,
+            plugin_type: format!("{:?}", validation.plugin_type),
+            description: validation.description,
+            author: validation.author,
+            entry_point: validation.entry_point,
+            parameters: validation.parameters,
+            groups: validation.groups,
+            metadata: validation.metadata,
+            tags: validation.tags,
+            python_dependencies: validation.python_dependencies,
+            warnings: validation.warnings,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -24,12 +94,55 @@ pub struct PluginResponse {
     pub author: String,
     pub entry_point: String,
     pub enabled: bool,
+    pub disabled_reason: Option<String>,
     pub created_at: i64,
     pub updated_at: i64,
+    pub created_at_rfc3339: String,
+    pub updated_at_rfc3339: String,
     pub parameters: Option<Vec<PluginParameter>>,
     pub groups: Option<Vec<PluginParameterGroup>>,
     pub metadata: Option<Value>,
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<i64>,
     pub python_dependencies: Option<PythonDependencies>,
+    pub resolved_dependencies: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub python_version: Option<String>,
+    /// Venv health check; only filled in by `GET /api/plugins/{id}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub python_env: Option<PythonEnvStatus>,
+    pub default_timeout_ms: Option<i64>,
+    pub max_timeout_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_execution: Option<LastExecutionResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LastExecutionResponse {
+    pub id: String,
+    pub phase: String,
+    pub status: String,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub started_at_rfc3339: String,
+    pub finished_at_rfc3339: Option<String>,
+}
+
+impl From<LastExecution> for LastExecutionResponse {
+    fn from(execution: LastExecution) -> Self {
+        Self {
+            id: execution.id,
+            phase: format!("{:?}", execution.phase),
+            status: format!("{:?}", execution.status),
+            started_at: execution.started_at,
+            finished_at: execution.finished_at,
+            started_at_rfc3339: format_timestamp(execution.started_at),
+            finished_at_rfc3339: execution.finished_at.map(format_timestamp),
+        }
+    }
 }
 
 impl TryFrom<Plugin> for PluginResponse {
@@ -40,6 +153,12 @@ impl TryFrom<Plugin> for PluginResponse {
         let groups = parse_groups(&plugin.parameter_groups)?;
         let metadata = parse_metadata(&plugin.metadata)?;
         let python_dependencies = parse_python_dependencies(&plugin.python_dependencies)?;
+        let resolved_dependencies = parse_resolved_dependencies(&plugin.resolved_dependencies)?;
+        let tags = plugin.tags();
+        // Display hints only, so a bad value hides them instead of failing the listing
+        let settings = plugin.settings().ok();
+        let category = settings.and_then(|settings| settings.category.clone());
+        let order = settings.and_then(|settings| settings.order);
         Ok(Self {
             id: plugin.plugin_id,
             name: plugin.name,
@@ -50,12 +169,24 @@ impl TryFrom<Plugin> for PluginResponse {
             author: plugin.author,
             entry_point: plugin.entry_point,
             enabled: plugin.enabled,
+            disabled_reason: plugin.disabled_reason,
             created_at: plugin.created_at,
             updated_at: plugin.updated_at,
+            created_at_rfc3339: format_timestamp(plugin.created_at),
+            updated_at_rfc3339: format_timestamp(plugin.updated_at),
             parameters,
             groups,
             metadata,
+            tags,
+            category,
+            order,
             python_dependencies,
+            resolved_dependencies,
+            python_version: plugin.python_version,
+            python_env: None,
+            default_timeout_ms: plugin.default_timeout_ms,
+            max_timeout_ms: plugin.max_timeout_ms,
+            last_execution: None,
         })
     }
 }
@@ -86,6 +217,19 @@ fn parse_python_dependencies(raw: &Option<String>) -> Result<Option<PythonDepend
     Ok(Some(dependencies))
 }
 
+fn parse_resolved_dependencies(raw: &Option<String>) -> Result<Option<Vec<String>>, AppError> {
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    let dependencies = serde_json::from_str(trimmed)
+        .map_err(|e| AppError::Execution(format!("Invalid resolved dependencies: {}", e)))?;
+    Ok(Some(dependencies))
+}
+
 fn parse_groups(raw: &Option<String>) -> Result<Option<Vec<PluginParameterGroup>>, AppError> {
     let Some(raw) = raw else {
         return Ok(None);
@@ -112,7 +256,55 @@ fn parse_metadata(raw: &Option<String>) -> Result<Option<Value>, AppError> {
     Ok(Some(metadata))
 }
 
+#[derive(Debug, Serialize)]
+pub struct InstallFailureResponse {
+    pub package_url: String,
+    pub error: String,
+    pub code: String,
+}
+
+impl From<BatchInstallFailure> for InstallFailureResponse {
+    fn from(failure: BatchInstallFailure) -> Self {
+        Self {
+            package_url: failure.package_url,
+            code: failure.error.code().to_string(),
+            error: failure.error.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstallBatchResponse {
+    pub installed: Vec<PluginResponse>,
+    pub failed: Vec<InstallFailureResponse>,
+    pub rolled_back: Vec<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct PluginsListResponse {
     pub data: Vec<PluginResponse>,
 }
+
+#[derive(Debug, Serialize)]
+pub struct PluginVersionResponse {
+    pub version: String,
+    pub installed_at: i64,
+    pub installed_at_rfc3339: String,
+    pub package_sha256: String,
+}
+
+impl From<PluginVersion> for PluginVersionResponse {
+    fn from(version: PluginVersion) -> Self {
+        Self {
+            version: version.version,
+            installed_at: version.installed_at,
+            installed_at_rfc3339: format_timestamp(version.installed_at),
+            package_sha256: version.package_sha256,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PluginHistoryResponse {
+    pub data: Vec<PluginVersionResponse>,
+}
@@ -1,7 +1,18 @@
+use super::format_timestamp;
+use crate::services::PendingStatus;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateRequest {
+    pub package_url: Option<String>,
+    pub repo: Option<String>,
+    pub tag: Option<String>,
+    pub signature: Option<String>,
+    pub signature_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateImpactQuery {
     pub package_url: String,
 }
 
@@ -12,3 +23,31 @@ pub struct UpdateResponse {
     pub current_version: String,
     pub package_version: String,
 }
+
+#[derive(Debug, Serialize)]
+pub struct RestartResponse {
+    pub status: String,
+    pub update_applied: bool,
+    pub current_version: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateStatusResponse {
+    pub current_version: String,
+    pub pending: bool,
+    pub staged_version: Option<String>,
+    pub staged_at: Option<i64>,
+    pub staged_at_rfc3339: Option<String>,
+}
+
+impl From<PendingStatus> for UpdateStatusResponse {
+    fn from(status: PendingStatus) -> Self {
+        Self {
+            current_version: status.current_version,
+            pending: status.pending,
+            staged_version: status.staged_version,
+            staged_at: status.staged_at,
+            staged_at_rfc3339: status.staged_at.map(format_timestamp),
+        }
+    }
+}
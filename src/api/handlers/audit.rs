@@ -0,0 +1,24 @@
+use crate::api::dto::audit::{AuditEntryResponse, AuditListResponse, ListAuditQuery};
+use crate::api::routes::AppState;
+use crate::error::Result;
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+
+pub async fn list_audit(
+    State(state): State<AppState>,
+    Query(query): Query<ListAuditQuery>,
+) -> Result<Json<AuditListResponse>> {
+    let entries = state
+        .audit_service
+        .list(
+            query.since,
+            query.until,
+            query.plugin_id.as_deref(),
+            query.action.as_deref(),
+        )
+        .await?;
+    let data = entries.into_iter().map(AuditEntryResponse::from).collect();
+    Ok(Json(AuditListResponse { data }))
+}
@@ -1,28 +1,73 @@
 use crate::api::dto::execution::{
-    ApplyExecutionRequest, ExecutePluginRequest, ExecutionResponse, ExecutionsListResponse,
+    ApplyExecutionRequest, ExecutePluginRequest, ExecutionPluginSummary, ExecutionResponse,
+    ExecutionsListResponse, PrunePreviewsResponse,
 };
 use crate::api::routes::AppState;
-use crate::error::Result;
-use crate::models::ExecutionStatus;
+use crate::error::{AppError, Result};
+use crate::models::{Execution, ExecutionStatus};
+use crate::services::{ExecutionOptions, HealthCheckResult, WarmResult};
 use axum::{
     Json,
-    extract::{Path, Query, State},
+    extract::{
+        Path, Query, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    http::{HeaderMap, header},
+    response::{IntoResponse, Response},
 };
+use tokio::sync::broadcast;
 
 pub async fn execute_plugin(
     State(state): State<AppState>,
     Path(plugin_id): Path<String>,
+    headers: HeaderMap,
     Json(req): Json<ExecutePluginRequest>,
 ) -> Result<Json<ExecutionResponse>> {
     let params = req.params.unwrap_or_default();
+    let idempotency_key = headers
+        .get("idempotency-key")
+        .map(|value| {
+            value
+                .to_str()
+                .map(str::to_string)
+                .map_err(|_| AppError::InvalidRequest("Invalid Idempotency-Key header".to_string()))
+        })
+        .transpose()?;
 
     let execution = state
         .execution_service
-        .execute_plugin(&plugin_id, params)
+        .execute_plugin(
+            &plugin_id,
+            ExecutionOptions {
+                params,
+                timeout_ms: req.timeout_ms,
+                execution_id: req.execution_id,
+                idempotency_key,
+                dry_run: req.dry_run,
+                command: req.command,
+                callback_url: req.callback_url,
+            },
+        )
         .await?;
     Ok(Json(ExecutionResponse::from(execution)))
 }
 
+pub async fn healthcheck_plugin(
+    State(state): State<AppState>,
+    Path(plugin_id): Path<String>,
+) -> Result<Json<HealthCheckResult>> {
+    let result = state.execution_service.health_check(&plugin_id).await?;
+    Ok(Json(result))
+}
+
+pub async fn warm_plugin(
+    State(state): State<AppState>,
+    Path(plugin_id): Path<String>,
+) -> Result<Json<WarmResult>> {
+    let result = state.execution_service.warm_plugin(&plugin_id).await?;
+    Ok(Json(result))
+}
+
 pub async fn prepare_plugin(
     State(state): State<AppState>,
     Path(plugin_id): Path<String>,
@@ -31,7 +76,18 @@ pub async fn prepare_plugin(
     let params = req.params.unwrap_or_default();
     let execution = state
         .execution_service
-        .prepare_plugin(&plugin_id, params)
+        .prepare_plugin(
+            &plugin_id,
+            ExecutionOptions {
+                params,
+                timeout_ms: req.timeout_ms,
+                execution_id: req.execution_id,
+                dry_run: req.dry_run,
+                command: req.command,
+                callback_url: req.callback_url,
+                ..Default::default()
+            },
+        )
         .await?;
     // 等待预览完成或失败，最多 15s
     let execution = state
@@ -53,17 +109,61 @@ pub async fn apply_execution(
     let params = req.params.unwrap_or_default();
     let execution = state
         .execution_service
-        .apply_execution(&id, &req.confirm_token, params)
+        .apply_execution(&id, &req.confirm_token, params, req.timeout_ms)
         .await?;
     Ok(Json(ExecutionResponse::from(execution)))
 }
 
+pub async fn discard_execution(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ExecutionResponse>> {
+    let execution = state.execution_service.discard_preview(&id).await?;
+    Ok(Json(ExecutionResponse::from(execution)))
+}
+
 pub async fn get_execution(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
 ) -> Result<Json<ExecutionResponse>> {
+    let include_plugin = params
+        .get("include")
+        .is_some_and(|include| include.split(',').any(|item| item.trim() == "plugin"));
+
     let execution = state.execution_service.get_execution(&id).await?;
-    Ok(Json(ExecutionResponse::from(execution)))
+    let queue_position = state.execution_service.queue_position(&execution.id);
+    let plugin = if include_plugin {
+        match state.plugin_service.get_plugin(&execution.plugin_id).await {
+            Ok(plugin) => Some(ExecutionPluginSummary::from(plugin)),
+            Err(AppError::PluginNotFound(_)) => None,
+            Err(err) => return Err(err),
+        }
+    } else {
+        None
+    };
+
+    let mut response = ExecutionResponse::from(execution);
+    response.queue_position = queue_position;
+    response.plugin = plugin;
+    Ok(Json(response))
+}
+
+pub async fn execution_queue(
+    State(state): State<AppState>,
+) -> Result<Json<ExecutionsListResponse>> {
+    let data = state
+        .execution_service
+        .execution_queue()
+        .await?
+        .into_iter()
+        .map(|(execution, queue_position)| {
+            let mut response = ExecutionResponse::from(execution);
+            response.queue_position = queue_position;
+            response
+        })
+        .collect();
+    Ok(Json(ExecutionsListResponse { data }))
 }
 
 pub async fn list_executions(
@@ -71,8 +171,18 @@ pub async fn list_executions(
     Query(params): Query<std::collections::HashMap<String, String>>,
 ) -> Result<Json<ExecutionsListResponse>> {
     let plugin_id = params.get("plugin_id").cloned();
+    let status = params
+        .get("status")
+        .map(|name| {
+            ExecutionStatus::from_name(name)
+                .ok_or_else(|| AppError::Execution(format!("Unknown execution status: {}", name)))
+        })
+        .transpose()?;
 
-    let executions = state.execution_service.list_executions(plugin_id).await?;
+    let executions = state
+        .execution_service
+        .list_executions(plugin_id, status)
+        .await?;
     let response = ExecutionsListResponse {
         data: executions
             .into_iter()
@@ -82,6 +192,72 @@ pub async fn list_executions(
     Ok(Json(response))
 }
 
+pub async fn execution_ws(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Result<Response> {
+    let receiver = state.execution_service.subscribe_status_changes();
+    let execution = state.execution_service.get_execution(&id).await?;
+    Ok(ws.on_upgrade(move |socket| stream_execution_status(socket, state, execution, receiver)))
+}
+
+async fn stream_execution_status(
+    mut socket: WebSocket,
+    state: AppState,
+    mut last: Execution,
+    mut receiver: broadcast::Receiver<String>,
+) {
+    if send_execution(&mut socket, &last).await.is_err() || last.status.is_terminal() {
+        let _ = socket.send(Message::Close(None)).await;
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            event = receiver.recv() => match event {
+                Ok(changed_id) if changed_id != last.id => continue,
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+        }
+
+        let current = match state.execution_service.get_execution(&last.id).await {
+            Ok(execution) => execution,
+            Err(err) => {
+                tracing::warn!("Failed to load execution {}: {}", last.id, err);
+                break;
+            }
+        };
+        if current.status == last.status && current.phase == last.phase {
+            continue;
+        }
+        if send_execution(&mut socket, &current).await.is_err() {
+            return;
+        }
+        let finished = current.status.is_terminal();
+        last = current;
+        if finished {
+            break;
+        }
+    }
+
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+async fn send_execution(socket: &mut WebSocket, execution: &Execution) -> Result<()> {
+    let payload = serde_json::to_string(&ExecutionResponse::from(execution.clone()))
+        .map_err(|e| AppError::Execution(format!("Failed to serialize execution: {}", e)))?;
+    socket
+        .send(Message::Text(payload.into()))
+        .await
+        .map_err(|e| AppError::Execution(format!("Failed to send execution update: {}", e)))
+}
+
 pub async fn stop_execution(
     State(state): State<AppState>,
     Path(id): Path<String>,
@@ -91,3 +267,85 @@ pub async fn stop_execution(
         "message": "Execution stopped"
     })))
 }
+
+pub async fn execution_logs(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> Result<impl IntoResponse> {
+    let stream = params.get("stream").map(String::as_str).unwrap_or("stdout");
+    let execution = state.execution_service.get_execution(&id).await?;
+    let output = match stream {
+        "stdout" => execution.stdout,
+        "stderr" => execution.stderr,
+        other => {
+            return Err(AppError::InvalidRequest(format!(
+                "Unknown log stream: {} (expected stdout or stderr)",
+                other
+            )));
+        }
+    };
+    let disposition = format!("attachment; filename=\"{}-{}.log\"", execution.id, stream);
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "text/plain; charset=utf-8".to_string(),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        output.unwrap_or_default(),
+    ))
+}
+
+pub async fn retry_execution(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ExecutionResponse>> {
+    let execution = state.execution_service.retry_execution(&id).await?;
+    Ok(Json(ExecutionResponse::from(execution)))
+}
+
+pub async fn list_previews(State(state): State<AppState>) -> Result<Json<ExecutionsListResponse>> {
+    let previews = state.execution_service.list_previews().await?;
+    let plugins: std::collections::HashMap<String, ExecutionPluginSummary> = state
+        .plugin_service
+        .list_plugins()
+        .await?
+        .into_iter()
+        .map(|plugin| {
+            (
+                plugin.plugin_id.clone(),
+                ExecutionPluginSummary::from(plugin),
+            )
+        })
+        .collect();
+
+    let response = ExecutionsListResponse {
+        data: previews
+            .into_iter()
+            .map(|execution| {
+                let plugin = plugins.get(&execution.plugin_id).cloned();
+                let mut response = ExecutionResponse::from(execution);
+                response.plugin = plugin;
+                response
+            })
+            .collect(),
+    };
+    Ok(Json(response))
+}
+
+pub async fn prune_previews(
+    State(state): State<AppState>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<PrunePreviewsResponse>> {
+    let all = params
+        .get("all")
+        .is_some_and(|value| value == "true" || value == "1");
+
+    let execution_ids = state.execution_service.prune_previews(all).await?;
+    Ok(Json(PrunePreviewsResponse {
+        pruned: execution_ids.len(),
+        execution_ids,
+    }))
+}
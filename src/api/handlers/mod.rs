@@ -1,4 +1,6 @@
+pub mod audit;
 pub mod execution;
 pub mod health;
 pub mod plugin;
+pub mod system;
 pub mod update;
@@ -1,19 +1,64 @@
 use crate::api::dto::plugin::{
-    InstallPluginRequest, PluginResponse, PluginsListResponse, UpdatePluginRequest,
+    InstallBatchResponse, InstallFailureResponse, InstallFromMetadataRequest, InstallPluginRequest,
+    LastExecutionResponse, ListPluginsQuery, PluginHistoryResponse, PluginResponse,
+    PluginVersionResponse, PluginsListResponse, UpdatePluginRequest, ValidatePluginRequest,
+    ValidatePluginResponse,
 };
 use crate::api::routes::AppState;
-use crate::error::Result;
+use crate::error::{AppError, Result};
+use crate::models::PluginParameter;
+use crate::services::{PluginFiles, PluginUsage};
 use axum::{
     Json,
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Multipart, Path, Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
 };
+use tokio::io::AsyncWriteExt;
 
-pub async fn list_plugins(State(state): State<AppState>) -> Result<Json<PluginsListResponse>> {
-    let plugins = state.plugin_service.list_plugins().await?;
+/// Blocks scripts and same-origin access for files that come from plugin packages.
+const UNTRUSTED_CONTENT_CSP: &str = "default-src 'none'; style-src 'unsafe-inline'; sandbox";
+
+pub async fn list_plugins(
+    State(state): State<AppState>,
+    Query(query): Query<ListPluginsQuery>,
+) -> Result<Json<PluginsListResponse>> {
+    let mut plugins = match query.tag.as_deref() {
+        Some(tag) => state.plugin_service.list_plugins_with_tag(tag).await?,
+        None => state.plugin_service.list_plugins().await?,
+    };
+    match query.sort.as_deref() {
+        None => {}
+        // Stable, so plugins sharing an order keep the newest-first default
+        Some("order") => plugins.sort_by_key(|plugin| {
+            let order = plugin.settings().ok().and_then(|settings| settings.order);
+            (order.is_none(), order)
+        }),
+        Some(other) => {
+            return Err(AppError::InvalidRequest(format!(
+                "Unsupported sort '{}', expected order",
+                other
+            )));
+        }
+    }
+    let include_last_execution = query.include.as_deref().is_some_and(|include| {
+        include
+            .split(',')
+            .any(|item| item.trim() == "last_execution")
+    });
+    let mut latest = if include_last_execution {
+        state.plugin_service.latest_executions().await?
+    } else {
+        Default::default()
+    };
     let data = plugins
         .into_iter()
-        .map(PluginResponse::try_from)
+        .map(|plugin| {
+            let last_execution = latest.remove(&plugin.plugin_id);
+            let mut response = PluginResponse::try_from(plugin)?;
+            response.last_execution = last_execution.map(LastExecutionResponse::from);
+            Ok(response)
+        })
         .collect::<Result<Vec<_>>>()?;
     let response = PluginsListResponse { data };
     Ok(Json(response))
@@ -24,26 +69,167 @@ pub async fn get_plugin(
     Path(id): Path<String>,
 ) -> Result<Json<PluginResponse>> {
     let plugin = state.plugin_service.get_plugin(&id).await?;
-    Ok(Json(PluginResponse::try_from(plugin)?))
+    let python_env = state.plugin_service.python_env_status(&plugin).await;
+    let mut response = PluginResponse::try_from(plugin)?;
+    response.python_env = python_env;
+    Ok(Json(response))
 }
 
 pub async fn install_plugin(
     State(state): State<AppState>,
     Json(req): Json<InstallPluginRequest>,
 ) -> Result<(StatusCode, Json<PluginResponse>)> {
-    let plugin = state.plugin_service.install_plugin(req.package_url).await?;
+    let plugin = state
+        .plugin_service
+        .install_plugin(req.package_url, req.force)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(PluginResponse::try_from(plugin)?)))
+}
+
+/// Responds 201 when every plugin installed and 207 when any entry failed.
+pub async fn install_plugins_from_metadata(
+    State(state): State<AppState>,
+    Json(req): Json<InstallFromMetadataRequest>,
+) -> Result<(StatusCode, Json<InstallBatchResponse>)> {
+    let report = state
+        .plugin_service
+        .install_from_metadata_url(req.metadata_url, req.continue_on_error)
+        .await?;
+    let status = if report.failed.is_empty() {
+        StatusCode::CREATED
+    } else {
+        StatusCode::MULTI_STATUS
+    };
+    let installed = report
+        .installed
+        .into_iter()
+        .map(PluginResponse::try_from)
+        .collect::<Result<Vec<_>>>()?;
+    let failed = report
+        .failed
+        .into_iter()
+        .map(InstallFailureResponse::from)
+        .collect();
+    Ok((
+        status,
+        Json(InstallBatchResponse {
+            installed,
+            failed,
+            rolled_back: report.rolled_back,
+        }),
+    ))
+}
+
+pub async fn upload_plugin(
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<PluginResponse>)> {
+    let max_bytes = state.plugin_service.max_upload_bytes();
+    let temp_file = tempfile::Builder::new()
+        .prefix("plugin_upload_")
+        .suffix(".zip")
+        .tempfile()?;
 
+    let mut received = None;
+    while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.name() != Some("package") {
+            continue;
+        }
+
+        // Stream straight to disk so large packages never sit in memory
+        let mut output = tokio::fs::File::from_std(temp_file.reopen()?);
+        let mut written: u64 = 0;
+        while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+            written += chunk.len() as u64;
+            if written > max_bytes {
+                return Err(AppError::PayloadTooLarge(format!(
+                    "Package exceeds maximum upload size of {} bytes",
+                    max_bytes
+                )));
+            }
+            output.write_all(&chunk).await?;
+        }
+        output.flush().await?;
+        received = Some(written);
+        break;
+    }
+
+    let Some(size) = received else {
+        return Err(AppError::Execution(
+            "Missing 'package' field in upload".to_string(),
+        ));
+    };
+    tracing::info!("Received plugin upload of {} bytes", size);
+
+    let plugin = state
+        .plugin_service
+        .install_plugin_from_file(temp_file.path())
+        .await?;
     Ok((StatusCode::CREATED, Json(PluginResponse::try_from(plugin)?)))
 }
 
+fn multipart_error(err: axum::extract::multipart::MultipartError) -> AppError {
+    // The request body limit surfaces here once a streamed upload crosses it
+    if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        return AppError::PayloadTooLarge(format!("Invalid upload: {}", err.body_text()));
+    }
+    AppError::Execution(format!("Invalid upload: {}", err))
+}
+
+pub async fn validate_plugin(
+    State(state): State<AppState>,
+    Json(req): Json<ValidatePluginRequest>,
+) -> Result<Json<ValidatePluginResponse>> {
+    let validation = state
+        .plugin_service
+        .validate_package(req.package_url)
+        .await?;
+    Ok(Json(ValidatePluginResponse::from(validation)))
+}
+
+pub async fn plugin_history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<PluginHistoryResponse>> {
+    let versions = state.plugin_service.plugin_history(&id).await?;
+    let data = versions
+        .into_iter()
+        .map(PluginVersionResponse::from)
+        .collect();
+    Ok(Json(PluginHistoryResponse { data }))
+}
+
 pub async fn uninstall_plugin(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
 ) -> Result<StatusCode> {
-    state.plugin_service.uninstall_plugin(&id).await?;
+    let soft = params
+        .get("soft")
+        .is_some_and(|value| value == "true" || value == "1");
+    let keep_history = params
+        .get("keep_history")
+        .is_some_and(|value| value == "true" || value == "1");
+    if soft {
+        state.plugin_service.soft_delete_plugin(&id).await?;
+    } else {
+        state
+            .plugin_service
+            .uninstall_plugin(&id, keep_history)
+            .await?;
+    }
     Ok(StatusCode::NO_CONTENT)
 }
 
+pub async fn restore_plugin(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<PluginResponse>> {
+    let plugin = state.plugin_service.restore_plugin(&id).await?;
+    Ok(Json(PluginResponse::try_from(plugin)?))
+}
+
 pub async fn update_plugin(
     State(state): State<AppState>,
     Path(id): Path<String>,
@@ -51,11 +237,36 @@ pub async fn update_plugin(
 ) -> Result<(StatusCode, Json<PluginResponse>)> {
     let plugin = state
         .plugin_service
-        .update_plugin(&id, req.package_url)
+        .update_plugin(&id, req.package_url, req.allow_downgrade)
         .await?;
     Ok((StatusCode::OK, Json(PluginResponse::try_from(plugin)?)))
 }
 
+pub async fn rebuild_plugin_env(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<PluginResponse>> {
+    let plugin = state.plugin_service.rebuild_python_env(&id).await?;
+    Ok(Json(PluginResponse::try_from(plugin)?))
+}
+
+pub async fn plugin_icon(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let (icon_path, content_type) = state.plugin_service.plugin_icon(&id).await?;
+    let bytes = tokio::fs::read(&icon_path).await?;
+    // An SVG icon opened directly must not run scripts on the API's origin
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+            (header::CONTENT_SECURITY_POLICY, UNTRUSTED_CONTENT_CSP),
+        ],
+        bytes,
+    ))
+}
+
 pub async fn enable_plugin(
     State(state): State<AppState>,
     Path(id): Path<String>,
@@ -71,3 +282,42 @@ pub async fn disable_plugin(
     state.plugin_service.disable_plugin(&id).await?;
     Ok(StatusCode::OK)
 }
+
+pub async fn plugin_files(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<PluginFiles>> {
+    let files = state.plugin_service.plugin_files(&id).await?;
+    Ok(Json(files))
+}
+
+pub async fn plugin_file(
+    State(state): State<AppState>,
+    Path((id, path)): Path<(String, String)>,
+) -> Result<impl IntoResponse> {
+    let (content, content_type) = state.plugin_service.read_plugin_file(&id, &path).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+            (header::CONTENT_SECURITY_POLICY, UNTRUSTED_CONTENT_CSP),
+        ],
+        content,
+    ))
+}
+
+pub async fn plugin_parameters(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<PluginParameter>>> {
+    let parameters = state.plugin_service.plugin_parameters(&id).await?;
+    Ok(Json(parameters))
+}
+
+pub async fn plugin_usage(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<PluginUsage>> {
+    let usage = state.plugin_service.plugin_usage(&id).await?;
+    Ok(Json(usage))
+}
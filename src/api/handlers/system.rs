@@ -0,0 +1,46 @@
+use crate::api::routes::AppState;
+use crate::error::{AppError, Result};
+use crate::services::system_service::SystemUsage;
+use crate::services::{EffectiveConfig, WorkDirCleanup};
+use axum::{
+    Json,
+    extract::{ConnectInfo, Query, Request, State},
+};
+use std::net::SocketAddr;
+
+pub async fn usage(State(state): State<AppState>) -> Result<Json<SystemUsage>> {
+    let usage = state.system_service.usage().await?;
+    Ok(Json(usage))
+}
+
+/// Only answers local callers, since there is no auth layer yet. Unix socket
+/// connections carry no peer address and are local by construction.
+pub async fn effective_config(
+    State(state): State<AppState>,
+    request: Request,
+) -> Result<Json<EffectiveConfig>> {
+    if let Some(ConnectInfo(peer)) = request.extensions().get::<ConnectInfo<SocketAddr>>()
+        && !peer.ip().to_canonical().is_loopback()
+    {
+        return Err(AppError::Forbidden(
+            "The system config is only available from the local machine".to_string(),
+        ));
+    }
+    let config = state.system_service.effective_config()?;
+    Ok(Json(config))
+}
+
+pub async fn cleanup(
+    State(state): State<AppState>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> Result<Json<WorkDirCleanup>> {
+    let include_terminal = params
+        .get("include_terminal")
+        .is_some_and(|value| value == "true" || value == "1");
+
+    let cleanup = state
+        .execution_service
+        .cleanup_work_dirs(include_terminal)
+        .await?;
+    Ok(Json(cleanup))
+}
@@ -1,13 +1,37 @@
-use crate::api::dto::update::{UpdateRequest, UpdateResponse};
+use crate::api::dto::update::{
+    RestartResponse, UpdateImpactQuery, UpdateRequest, UpdateResponse, UpdateStatusResponse,
+};
 use crate::api::routes::AppState;
-use crate::error::Result;
-use axum::{Json, extract::State, http::StatusCode};
+use crate::error::{AppError, Result};
+use crate::services::UpdateImpact;
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+};
 
 pub async fn stage_update(
     State(state): State<AppState>,
     Json(req): Json<UpdateRequest>,
 ) -> Result<(StatusCode, Json<UpdateResponse>)> {
-    let status = state.update_service.stage_update(req.package_url).await?;
+    let package_url = match (req.package_url, req.repo) {
+        (Some(package_url), None) => package_url,
+        (None, Some(repo)) => {
+            state
+                .update_service
+                .resolve_release_url(&repo, req.tag.as_deref())
+                .await?
+        }
+        _ => {
+            return Err(AppError::Execution(
+                "Specify either package_url or repo".to_string(),
+            ));
+        }
+    };
+    let status = state
+        .update_service
+        .stage_update(package_url, req.signature, req.signature_url)
+        .await?;
 
     let response = UpdateResponse {
         status: "staged".to_string(),
@@ -18,3 +42,37 @@ pub async fn stage_update(
 
     Ok((StatusCode::ACCEPTED, Json(response)))
 }
+
+pub async fn update_status(State(state): State<AppState>) -> Result<Json<UpdateStatusResponse>> {
+    let status = state.update_service.pending_status()?;
+    Ok(Json(UpdateStatusResponse::from(status)))
+}
+
+pub async fn update_impact(
+    State(state): State<AppState>,
+    Query(query): Query<UpdateImpactQuery>,
+) -> Result<Json<UpdateImpact>> {
+    let plugins = state.plugin_service.list_plugins().await?;
+    let impact = state
+        .update_service
+        .check_impact(query.package_url, &plugins)
+        .await?;
+    Ok(Json(impact))
+}
+
+pub async fn cancel_pending(State(state): State<AppState>) -> Result<StatusCode> {
+    state.update_service.cancel_pending()?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn restart(State(state): State<AppState>) -> Result<(StatusCode, Json<RestartResponse>)> {
+    let status = state.update_service.restart()?;
+
+    let response = RestartResponse {
+        status: "restarting".to_string(),
+        update_applied: status.update_applied,
+        current_version: status.current_version,
+    };
+
+    Ok((StatusCode::ACCEPTED, Json(response)))
+}
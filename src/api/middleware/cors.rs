@@ -11,7 +11,8 @@ pub fn add_cors(router: Router) -> Router {
             axum::http::Method::DELETE,
             axum::http::Method::PATCH,
         ])
-        .allow_headers(Any);
+        .allow_headers(Any)
+        .expose_headers([axum::http::HeaderName::from_static("x-request-id")]);
 
     router.layer(cors)
 }
@@ -0,0 +1,80 @@
+use crate::error::InternalErrorDetails;
+use axum::{
+    Router,
+    response::{IntoResponse, Response},
+};
+
+/// When `expose` is set, internal errors report their real code and message
+/// instead of the generic "Internal error" body.
+pub fn add_error_details(router: Router, expose: bool) -> Router {
+    if !expose {
+        return router;
+    }
+    router.layer(axum::middleware::map_response(
+        |mut response: Response| async move {
+            match response.extensions_mut().remove::<InternalErrorDetails>() {
+                Some(details) => details.into_response(),
+                None => response,
+            }
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use axum::{body::Body, http::Request, routing::get};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    fn app(expose: bool) -> Router {
+        let router = Router::new()
+            .route(
+                "/internal",
+                get(|| async { Err::<(), _>(AppError::Io(std::io::Error::other("disk on fire"))) }),
+            )
+            .route(
+                "/missing",
+                get(|| async { Err::<(), _>(AppError::PluginNotFound("demo".to_string())) }),
+            );
+        add_error_details(router, expose)
+    }
+
+    async fn get_json(app: Router, uri: &str) -> (u16, Value) {
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status().as_u16();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn internal_errors_are_hidden_unless_exposed() {
+        let (status, body) = get_json(app(false), "/internal").await;
+        assert_eq!(status, 500);
+        assert_eq!(body["code"], "internal_error");
+        assert_eq!(body["error"], "Internal error");
+        assert!(body["correlation_id"].is_string());
+
+        let (status, body) = get_json(app(true), "/internal").await;
+        assert_eq!(status, 500);
+        assert_eq!(body["code"], "io_error");
+        assert_eq!(body["error"], "disk on fire");
+        assert!(body["correlation_id"].is_string());
+    }
+
+    #[tokio::test]
+    async fn client_errors_carry_a_correlation_id() {
+        for expose in [false, true] {
+            let (status, body) = get_json(app(expose), "/missing").await;
+            assert_eq!(status, 404);
+            assert_eq!(body["code"], "plugin_not_found");
+            assert!(body["correlation_id"].is_string());
+        }
+    }
+}
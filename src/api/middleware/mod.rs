@@ -1 +1,3 @@
 pub mod cors;
+pub mod errors;
+pub mod request_id;
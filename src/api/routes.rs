@@ -1,49 +1,405 @@
-use super::handlers::{execution, health, plugin, update};
+use super::handlers::{audit, execution, health, plugin, system, update};
 use super::middleware::cors::add_cors;
-use crate::services::{ExecutionService, PluginService, UpdateService};
+use super::middleware::errors::add_error_details;
+use super::middleware::request_id::add_request_id;
+use crate::services::{
+    AuditService, ExecutionService, PluginService, SystemService, UpdateService,
+};
 use axum::{
     Router,
+    extract::DefaultBodyLimit,
     routing::{delete, get, post, put},
 };
+use tower_http::limit::RequestBodyLimitLayer;
 
 #[derive(Clone)]
 pub struct AppState {
     pub plugin_service: PluginService,
     pub execution_service: ExecutionService,
     pub update_service: UpdateService,
+    pub system_service: SystemService,
+    pub audit_service: AuditService,
 }
 
-pub fn create_router(plugin_service: PluginService, execution_service: ExecutionService) -> Router {
+pub fn create_router(
+    plugin_service: PluginService,
+    execution_service: ExecutionService,
+    update_service: UpdateService,
+    system_service: SystemService,
+    audit_service: AuditService,
+    max_request_body_bytes: usize,
+    expose_internal_errors: bool,
+) -> Router {
+    let max_upload_bytes = usize::try_from(plugin_service.max_upload_bytes()).unwrap_or(usize::MAX);
     let state = AppState {
         plugin_service,
         execution_service,
-        update_service: UpdateService::new(),
+        update_service,
+        system_service,
+        audit_service,
     };
 
+    // Uploads get their own, larger limit; it is also enforced while streaming
+    let upload_routes = Router::new()
+        .route("/api/plugins/upload", post(plugin::upload_plugin))
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(max_upload_bytes));
+
     let api_routes = Router::new()
         // Health check
         .route("/health", get(health::health_check))
         // Plugin management
         .route("/api/plugins", get(plugin::list_plugins))
         .route("/api/plugins", post(plugin::install_plugin))
+        .route("/api/plugins/validate", post(plugin::validate_plugin))
+        .route(
+            "/api/plugins/install-from-metadata",
+            post(plugin::install_plugins_from_metadata),
+        )
         .route("/api/plugins/{id}", get(plugin::get_plugin))
         .route("/api/plugins/{id}", delete(plugin::uninstall_plugin))
         .route("/api/plugins/{id}", put(plugin::update_plugin))
         .route("/api/plugins/{id}/enable", put(plugin::enable_plugin))
         .route("/api/plugins/{id}/disable", put(plugin::disable_plugin))
+        .route("/api/plugins/{id}/restore", post(plugin::restore_plugin))
+        .route(
+            "/api/plugins/{id}/parameters",
+            get(plugin::plugin_parameters),
+        )
+        .route("/api/plugins/{id}/usage", get(plugin::plugin_usage))
+        .route("/api/plugins/{id}/files", get(plugin::plugin_files))
+        .route("/api/plugins/{id}/files/{*path}", get(plugin::plugin_file))
+        .route(
+            "/api/plugins/{id}/rebuild-env",
+            post(plugin::rebuild_plugin_env),
+        )
+        .route("/api/plugins/{id}/history", get(plugin::plugin_history))
+        .route("/api/plugins/{id}/icon", get(plugin::plugin_icon))
         // Execution
         .route("/api/plugins/{id}/prepare", post(execution::prepare_plugin))
         .route("/api/plugins/{id}/execute", post(execution::execute_plugin))
+        .route(
+            "/api/plugins/{id}/healthcheck",
+            post(execution::healthcheck_plugin),
+        )
+        .route("/api/plugins/{id}/warm", post(execution::warm_plugin))
         .route(
             "/api/executions/{id}/apply",
             post(execution::apply_execution),
         )
+        .route(
+            "/api/executions/{id}/discard",
+            post(execution::discard_execution),
+        )
         .route("/api/executions", get(execution::list_executions))
+        .route("/api/executions/queue", get(execution::execution_queue))
         .route("/api/executions/{id}", get(execution::get_execution))
         .route("/api/executions/{id}/stop", put(execution::stop_execution))
+        .route("/api/executions/{id}/logs", get(execution::execution_logs))
+        .route(
+            "/api/executions/{id}/retry",
+            post(execution::retry_execution),
+        )
+        .route("/api/executions/{id}/ws", get(execution::execution_ws))
+        .route("/api/previews", get(execution::list_previews))
+        .route("/api/previews/prune", post(execution::prune_previews))
+        // System
+        .route("/api/system/usage", get(system::usage))
+        .route("/api/system/config", get(system::effective_config))
+        .route("/api/system/cleanup", post(system::cleanup))
+        // Audit
+        .route("/api/audit", get(audit::list_audit))
         // Update
         .route("/api/update", post(update::stage_update))
+        .route("/api/update/status", get(update::update_status))
+        .route("/api/update/impact", get(update::update_impact))
+        .route("/api/update/pending", delete(update::cancel_pending))
+        .route("/api/update/restart", post(update::restart))
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(max_request_body_bytes))
+        .merge(upload_routes)
         .with_state(state);
 
-    add_cors(api_routes)
+    add_request_id(add_cors(add_error_details(
+        api_routes,
+        expose_internal_errors,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{
+        AuditRepository, DbPool, ExecutionRepository, PluginRepository, PoolSettings,
+        establish_connection,
+    };
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    /// The full API over an in-memory database holding one shell plugin, `demo`.
+    async fn test_app() -> (Router, DbPool, tempfile::TempDir) {
+        test_app_with(|plugin_service| plugin_service).await
+    }
+
+    /// Like [`test_app`], with `configure` applied to the plugin service.
+    async fn test_app_with(
+        configure: impl FnOnce(PluginService) -> PluginService,
+    ) -> (Router, DbPool, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let dirs = crate::paths::Dirs::under(dir.path());
+        let pool = establish_connection("sqlite::memory:", PoolSettings::default())
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO plugins (id, plugin_id, name, version, plugin_type, plugin_path, entry_point, created_at, updated_at) VALUES ('p1', 'demo', 'Demo', '1.2.0', 2, '/tmp/demo', 'main.sh', 0, 0)",
+        )
+        .execute(sqlite(&pool))
+        .await
+        .unwrap();
+        let plugin_repo = PluginRepository::new(pool.clone());
+        let app = create_router(
+            configure(PluginService::new(plugin_repo.clone(), None).with_dirs(dirs.clone())),
+            ExecutionService::new(ExecutionRepository::new(pool.clone()), plugin_repo)
+                .with_dirs(dirs.clone()),
+            UpdateService::new(),
+            SystemService::new(Default::default()).with_dirs(dirs),
+            AuditService::new(AuditRepository::new(pool.clone())),
+            1024 * 1024,
+            false,
+        );
+        (app, pool, dir)
+    }
+
+    async fn send(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder().method(method).uri(uri);
+        let request = match body {
+            Some(body) => request
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        (status, body)
+    }
+
+    async fn insert_execution(pool: &DbPool, id: &str, status: i32) {
+        sqlx::query(
+            "INSERT INTO executions (id, plugin_id, status, started_at) VALUES ($1, 'demo', $2, 0)",
+        )
+        .bind(id)
+        .bind(status)
+        .execute(sqlite(pool))
+        .await
+        .unwrap();
+    }
+
+    fn sqlite(pool: &DbPool) -> &sqlx::SqlitePool {
+        match pool {
+            DbPool::Sqlite(pool) => pool,
+            DbPool::Postgres(_) => unreachable!("test_app uses sqlite"),
+        }
+    }
+
+    #[tokio::test]
+    async fn get_execution_embeds_the_plugin_only_when_asked() {
+        let (app, pool, _dir) = test_app().await;
+        insert_execution(&pool, "e1", 4).await;
+
+        let (status, body) = send(&app, "GET", "/api/executions/e1", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.get("plugin").is_none());
+
+        let (status, body) = send(&app, "GET", "/api/executions/e1?include=plugin", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["plugin"]["id"], "demo");
+        assert_eq!(body["plugin"]["name"], "Demo");
+        assert_eq!(body["plugin"]["version"], "1.2.0");
+        assert_eq!(body["plugin"]["plugin_type"], "Shell");
+    }
+
+    #[tokio::test]
+    async fn invalid_idempotency_key_is_an_invalid_request() {
+        let (app, _pool, _dir) = test_app().await;
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/plugins/demo/execute")
+            .header("content-type", "application/json")
+            .header(
+                "idempotency-key",
+                axum::http::HeaderValue::from_bytes(b"caf\xe9").unwrap(),
+            )
+            .body(Body::from("{}"))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["code"], "invalid_request");
+    }
+
+    #[tokio::test]
+    async fn unknown_log_stream_is_an_invalid_request() {
+        let (app, pool, _dir) = test_app().await;
+        insert_execution(&pool, "e1", 4).await;
+
+        let (status, body) = send(&app, "GET", "/api/executions/e1/logs?stream=stdin", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_request");
+    }
+
+    #[tokio::test]
+    async fn unsupported_plugin_sort_is_an_invalid_request() {
+        let (app, _pool, _dir) = test_app().await;
+
+        let (status, body) = send(&app, "GET", "/api/plugins?sort=name", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_request");
+    }
+
+    const BOUNDARY: &str = "anthill-test-boundary";
+
+    /// A multipart upload of `package` that reads the file as the body is sent.
+    /// Each chunk arrives on a later poll, as from a socket, since the multipart
+    /// parser buffers whatever the body has ready.
+    fn streamed_upload(package: &std::path::Path) -> Request<Body> {
+        use futures_util::StreamExt;
+        use std::io::Read;
+
+        let head = format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"package\"; filename=\"plugin.zip\"\r\nContent-Type: application/zip\r\n\r\n"
+        );
+        let tail = format!("\r\n--{BOUNDARY}--\r\n");
+        let mut file = std::fs::File::open(package).unwrap();
+        let file_chunks = std::iter::from_fn(move || {
+            let mut chunk = vec![0; 64 * 1024];
+            match file.read(&mut chunk) {
+                Ok(0) => None,
+                Ok(read) => {
+                    chunk.truncate(read);
+                    Some(Ok(axum::body::Bytes::from(chunk)))
+                }
+                Err(err) => Some(Err(err)),
+            }
+        });
+        let chunks = std::iter::once(Ok(axum::body::Bytes::from(head)))
+            .chain(file_chunks)
+            .chain(std::iter::once(Ok(axum::body::Bytes::from(tail))));
+        Request::builder()
+            .method("POST")
+            .uri("/api/plugins/upload")
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from_stream(futures_util::stream::iter(chunks).then(
+                |chunk| async move {
+                    tokio::task::yield_now().await;
+                    chunk
+                },
+            )))
+            .unwrap()
+    }
+
+    /// Writes a shell plugin package padded with `padding` bytes of stored data.
+    fn write_padded_package(path: &std::path::Path, padding: usize) {
+        use std::io::Write;
+
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let stored = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored)
+            .large_file(padding > u32::MAX as usize);
+        writer.start_file("metadata.json", stored).unwrap();
+        writer
+            .write_all(
+                serde_json::json!({
+                    "plugin_id": "big",
+                    "name": "big",
+                    "version": "1.0.0",
+                    "plugin_type": "shell",
+                    "description": "",
+                    "author": "",
+                    "entry_point": "main.sh",
+                })
+                .to_string()
+                .as_bytes(),
+            )
+            .unwrap();
+        writer.start_file("main.sh", stored).unwrap();
+        writer.write_all(b"echo hi\n").unwrap();
+        writer.start_file("padding.bin", stored).unwrap();
+        let chunk = vec![0; 64 * 1024];
+        for _ in 0..padding / chunk.len() {
+            writer.write_all(&chunk).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    /// Peak resident memory of this process, in KiB.
+    #[cfg(target_os = "linux")]
+    fn peak_rss_kib() -> u64 {
+        std::fs::read_to_string("/proc/self/status")
+            .unwrap()
+            .lines()
+            .find_map(|line| line.strip_prefix("VmHWM:"))
+            .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+            .unwrap()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn large_upload_streams_to_disk() {
+        const PADDING: usize = 96 * 1024 * 1024;
+        let (app, _pool, dir) = test_app().await;
+        let package = dir.path().join("big.zip");
+        write_padded_package(&package, PADDING);
+
+        // Reset the peak so it only covers the upload
+        std::fs::write("/proc/self/clear_refs", "5").unwrap();
+        let before = peak_rss_kib();
+        let response = app.oneshot(streamed_upload(&package)).await.unwrap();
+        let grown_kib = peak_rss_kib().saturating_sub(before);
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let installed = dir.path().join("plugins/big/padding.bin");
+        assert_eq!(std::fs::metadata(installed).unwrap().len(), PADDING as u64);
+        assert!(
+            grown_kib < 32 * 1024,
+            "peak memory grew by {} KiB for a {} byte upload",
+            grown_kib,
+            PADDING
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_upload_is_payload_too_large() {
+        let (app, _pool, dir) =
+            test_app_with(|plugin_service| plugin_service.with_max_upload_bytes(1024 * 1024)).await;
+        let package = dir.path().join("big.zip");
+        write_padded_package(&package, 2 * 1024 * 1024);
+
+        let response = app.oneshot(streamed_upload(&package)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["code"], "payload_too_large");
+    }
 }
@@ -8,11 +8,65 @@ pub struct Config {
     pub host: String,
     pub port: u16,
     pub uv_path: Option<PathBuf>,
+    pub recycle_retention_hours: u64,
+    pub output_flush_ms: u64,
+    pub strict_metadata: bool,
+    pub container_runtime: Option<String>,
+    pub container_python_image: String,
+    pub container_node_image: String,
+    pub log_format: LogFormat,
+    pub expose_internal_errors: bool,
+    pub max_upload_mb: u64,
+    pub auto_disable_missing_runtime: bool,
+    pub update_public_key: Option<String>,
+    pub update_asset_pattern: String,
+    pub update_preserve_dirs: Vec<String>,
+    pub max_request_body_bytes: usize,
+    pub plugin_env_passthrough: Vec<String>,
+    pub inherit_plugin_env: bool,
+    pub offline: bool,
+    pub shell_path: Option<String>,
+    pub max_plugins: u64,
+    /// Plugin processes allowed to run at once; 0 means unlimited.
+    pub max_concurrent_executions: usize,
+    pub keep_work_dir_on_failure: bool,
+    pub work_dir_retention_hours: u64,
+    pub plugin_max_memory_mb: Option<u64>,
+    pub plugin_max_cpu_secs: Option<u64>,
+    pub strict_entry_point: bool,
+    pub db_max_connections: u32,
+    pub db_busy_timeout_ms: u64,
+    pub db_wal: bool,
+    /// Hosts package downloads and execution callbacks may reach; empty allows
+    /// any. Callbacks to private or loopback addresses need an explicit entry.
+    pub allowed_hosts: Vec<String>,
+    /// Dirs put in front of PATH for plugin processes; relative ones are under the install root.
+    pub executor_path_prepend: Vec<PathBuf>,
+    /// Seconds between sweeps that expire unapplied previews; 0 disables the sweeper.
+    pub preview_sweep_interval_secs: u64,
+    /// Directory overrides; relative paths are under the install root.
+    /// `data_dir` also moves the recycle bin, plugin state and, unless set separately,
+    /// the python envs and the default database.
+    pub plugins_dir: Option<PathBuf>,
+    pub work_dir: Option<PathBuf>,
+    pub data_dir: Option<PathBuf>,
+    pub python_envs_dir: Option<PathBuf>,
+    /// Plugin types (`python`, `javascript`, `shell`) allowed to install and run; empty allows all.
+    pub enabled_plugin_types: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        let database_url = crate::paths::data_dir()
+        let database_url = crate::paths::Dirs::default()
+            .data_dir()
             .map(|dir| format!("sqlite:{}", dir.join("anthill.db").display()))
             .unwrap_or_else(|_| "sqlite:anthill.db".to_string());
         Self {
@@ -20,6 +74,43 @@ impl Default for Config {
             host: "127.0.0.1".to_string(),
             port: 6701,
             uv_path: None,
+            recycle_retention_hours: 7 * 24,
+            output_flush_ms: 500,
+            strict_metadata: false,
+            container_runtime: None,
+            container_python_image: "python:3.12-slim".to_string(),
+            container_node_image: "node:22-slim".to_string(),
+            log_format: LogFormat::default(),
+            expose_internal_errors: cfg!(debug_assertions),
+            max_upload_mb: 256,
+            auto_disable_missing_runtime: false,
+            update_public_key: None,
+            update_asset_pattern: crate::services::update_service::DEFAULT_ASSET_PATTERN
+                .to_string(),
+            update_preserve_dirs: Vec::new(),
+            max_request_body_bytes: 2 * 1024 * 1024,
+            plugin_env_passthrough: Vec::new(),
+            inherit_plugin_env: false,
+            offline: false,
+            shell_path: None,
+            max_plugins: 0,
+            max_concurrent_executions: 0,
+            keep_work_dir_on_failure: false,
+            work_dir_retention_hours: 24,
+            plugin_max_memory_mb: None,
+            plugin_max_cpu_secs: None,
+            strict_entry_point: false,
+            db_max_connections: 10,
+            db_busy_timeout_ms: 5000,
+            db_wal: true,
+            allowed_hosts: Vec::new(),
+            executor_path_prepend: Vec::new(),
+            preview_sweep_interval_secs: 5 * 60,
+            plugins_dir: None,
+            work_dir: None,
+            data_dir: None,
+            python_envs_dir: None,
+            enabled_plugin_types: Vec::new(),
         }
     }
 }
@@ -27,6 +118,7 @@ impl Default for Config {
 impl Config {
     pub fn from_env() -> Result<Self> {
         let mut config = Self::default();
+        let default_database_url = config.database_url.clone();
 
         if let Some(file_config) = Self::from_conf_file()? {
             config.apply_file(file_config);
@@ -41,11 +133,37 @@ impl Config {
         }
 
         if let Ok(port) = std::env::var("PORT") {
-            config.port = port.parse().unwrap_or(6701);
+            config.port = port
+                .trim()
+                .parse()
+                .ok()
+                .filter(|port| *port != 0)
+                .with_context(|| format!("PORT must be 1-65535, got '{}'", port))?;
+        }
+
+        if let Ok(log_format) = std::env::var("LOG_FORMAT") {
+            config.log_format = match log_format.trim().to_ascii_lowercase().as_str() {
+                "json" => LogFormat::Json,
+                "text" => LogFormat::Text,
+                other => anyhow::bail!("Unsupported LOG_FORMAT '{}', expected text or json", other),
+            };
         }
 
+        config.normalize_dir_overrides()?;
+        if let Some(data_dir) = &config.data_dir
+            && config.database_url == default_database_url
+        {
+            config.database_url = format!("sqlite:{}", data_dir.join("anthill.db").display());
+        }
         config.normalize_database_url()?;
         config.normalize_uv_path()?;
+        config.normalize_executor_path_prepend()?;
+        if config.db_max_connections == 0 {
+            anyhow::bail!("db_max_connections must be greater than 0");
+        }
+        config.validate_update_preserve_dirs()?;
+        config.validate_listen_addr()?;
+        config.plugin_types()?;
         Ok(config)
     }
 
@@ -75,12 +193,123 @@ impl Config {
         if let Some(uv_path) = file_config.uv_path {
             self.uv_path = Some(PathBuf::from(uv_path));
         }
+        if let Some(hours) = file_config.recycle_retention_hours {
+            self.recycle_retention_hours = hours;
+        }
+        if let Some(output_flush_ms) = file_config.output_flush_ms {
+            self.output_flush_ms = output_flush_ms;
+        }
+        if let Some(strict_metadata) = file_config.strict_metadata {
+            self.strict_metadata = strict_metadata;
+        }
+        if let Some(runtime) = file_config.container_runtime {
+            self.container_runtime = Some(runtime);
+        }
+        if let Some(image) = file_config.container_python_image {
+            self.container_python_image = image;
+        }
+        if let Some(image) = file_config.container_node_image {
+            self.container_node_image = image;
+        }
+        if let Some(log_format) = file_config.log_format {
+            self.log_format = log_format;
+        }
+        if let Some(expose) = file_config.expose_internal_errors {
+            self.expose_internal_errors = expose;
+        }
+        if let Some(max_upload_mb) = file_config.max_upload_mb {
+            self.max_upload_mb = max_upload_mb;
+        }
+        if let Some(auto_disable) = file_config.auto_disable_missing_runtime {
+            self.auto_disable_missing_runtime = auto_disable;
+        }
+        if let Some(public_key) = file_config.update_public_key {
+            self.update_public_key = Some(public_key);
+        }
+        if let Some(pattern) = file_config.update_asset_pattern {
+            self.update_asset_pattern = pattern;
+        }
+        if let Some(dirs) = file_config.update_preserve_dirs {
+            self.update_preserve_dirs = dirs;
+        }
+        if let Some(max_bytes) = file_config.max_request_body_bytes {
+            self.max_request_body_bytes = max_bytes;
+        }
+        if let Some(passthrough) = file_config.plugin_env_passthrough {
+            self.plugin_env_passthrough = passthrough;
+        }
+        if let Some(inherit) = file_config.inherit_plugin_env {
+            self.inherit_plugin_env = inherit;
+        }
+        if let Some(offline) = file_config.offline {
+            self.offline = offline;
+        }
+        if let Some(shell_path) = file_config.shell_path {
+            self.shell_path = Some(shell_path);
+        }
+        if let Some(max_plugins) = file_config.max_plugins {
+            self.max_plugins = max_plugins;
+        }
+        if let Some(max) = file_config.max_concurrent_executions {
+            self.max_concurrent_executions = max;
+        }
+        if let Some(keep) = file_config.keep_work_dir_on_failure {
+            self.keep_work_dir_on_failure = keep;
+        }
+        if let Some(hours) = file_config.work_dir_retention_hours {
+            self.work_dir_retention_hours = hours;
+        }
+        if let Some(mb) = file_config.plugin_max_memory_mb {
+            self.plugin_max_memory_mb = Some(mb);
+        }
+        if let Some(secs) = file_config.plugin_max_cpu_secs {
+            self.plugin_max_cpu_secs = Some(secs);
+        }
+        if let Some(strict_entry_point) = file_config.strict_entry_point {
+            self.strict_entry_point = strict_entry_point;
+        }
+        if let Some(max_connections) = file_config.db_max_connections {
+            self.db_max_connections = max_connections;
+        }
+        if let Some(busy_timeout_ms) = file_config.db_busy_timeout_ms {
+            self.db_busy_timeout_ms = busy_timeout_ms;
+        }
+        if let Some(wal) = file_config.db_wal {
+            self.db_wal = wal;
+        }
+        if let Some(hosts) = file_config.allowed_hosts {
+            self.allowed_hosts = hosts;
+        }
+        if let Some(dirs) = file_config.executor_path_prepend {
+            self.executor_path_prepend = dirs;
+        }
+        if let Some(secs) = file_config.preview_sweep_interval_secs {
+            self.preview_sweep_interval_secs = secs;
+        }
+        if let Some(dir) = file_config.plugins_dir {
+            self.plugins_dir = Some(dir);
+        }
+        if let Some(dir) = file_config.work_dir {
+            self.work_dir = Some(dir);
+        }
+        if let Some(dir) = file_config.data_dir {
+            self.data_dir = Some(dir);
+        }
+        if let Some(dir) = file_config.python_envs_dir {
+            self.python_envs_dir = Some(dir);
+        }
+        if let Some(plugin_types) = file_config.enabled_plugin_types {
+            self.enabled_plugin_types = plugin_types;
+        }
     }
 
     fn normalize_database_url(&mut self) -> Result<()> {
         let Some(path_str) = self.database_url.strip_prefix("sqlite:") else {
             return Ok(());
         };
+        if crate::repository::is_memory_url(&self.database_url) {
+            return Ok(());
+        }
 
         let path = Path::new(path_str);
         let root = crate::paths::install_root()?;
@@ -107,6 +336,166 @@ impl Config {
         Ok(())
     }
 
+    /// Checks `host`/`port` the way `run_server` will bind them.
+    fn validate_listen_addr(&self) -> Result<()> {
+        if let Some(socket_path) = self.host.strip_prefix("unix:") {
+            if socket_path.trim().is_empty() {
+                anyhow::bail!("HOST 'unix:' must be followed by a socket path");
+            }
+            return Ok(());
+        }
+        if self.port == 0 {
+            anyhow::bail!("port must be 1-65535, got 0");
+        }
+        format!("{}:{}", self.host, self.port)
+            .parse::<std::net::SocketAddr>()
+            .with_context(|| {
+                format!(
+                    "HOST must be an IP address or unix:<path>, got '{}'",
+                    self.host
+                )
+            })?;
+        Ok(())
+    }
+
+    /// Parsed `enabled_plugin_types`; empty means every type is enabled.
+    pub fn plugin_types(&self) -> Result<Vec<crate::models::PluginType>> {
+        self.enabled_plugin_types
+            .iter()
+            .map(|name| {
+                crate::models::PluginType::from_name(name.trim()).with_context(|| {
+                    format!(
+                        "enabled_plugin_types entry '{}' must be python, javascript or shell",
+                        name
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Settings reported by `GET /api/system/config`. Fields are listed one by
+    /// one so new options stay private until added here; keys, host allowlists
+    /// and env var names are left out and the database password is masked.
+    pub fn reported(&self) -> serde_json::Map<String, serde_json::Value> {
+        let serde_json::Value::Object(reported) = serde_json::json!({
+            "database_url": crate::repository::redact_database_url(&self.database_url),
+            "host": self.host,
+            "port": self.port,
+            "log_format": self.log_format,
+            "offline": self.offline,
+            "strict_metadata": self.strict_metadata,
+            "strict_entry_point": self.strict_entry_point,
+            "enabled_plugin_types": self.enabled_plugin_types,
+            "max_plugins": self.max_plugins,
+            "max_concurrent_executions": self.max_concurrent_executions,
+            "max_upload_mb": self.max_upload_mb,
+            "max_request_body_bytes": self.max_request_body_bytes,
+            "output_flush_ms": self.output_flush_ms,
+            "recycle_retention_hours": self.recycle_retention_hours,
+            "work_dir_retention_hours": self.work_dir_retention_hours,
+            "keep_work_dir_on_failure": self.keep_work_dir_on_failure,
+            "preview_sweep_interval_secs": self.preview_sweep_interval_secs,
+            "auto_disable_missing_runtime": self.auto_disable_missing_runtime,
+            "inherit_plugin_env": self.inherit_plugin_env,
+            "plugin_max_memory_mb": self.plugin_max_memory_mb,
+            "plugin_max_cpu_secs": self.plugin_max_cpu_secs,
+            "container_runtime": self.container_runtime,
+            "container_python_image": self.container_python_image,
+            "container_node_image": self.container_node_image,
+            "db_max_connections": self.db_max_connections,
+            "db_busy_timeout_ms": self.db_busy_timeout_ms,
+            "db_wal": self.db_wal,
+            "update_signature_required": self.update_public_key.is_some(),
+            "allowed_hosts_restricted": !self.allowed_hosts.is_empty(),
+        }) else {
+            unreachable!("json! object literal");
+        };
+        reported
+    }
+
+    pub fn dirs(&self) -> crate::paths::Dirs {
+        crate::paths::Dirs {
+            plugins: self.plugins_dir.clone(),
+            work: self.work_dir.clone(),
+            data: self.data_dir.clone(),
+            python_envs: self.python_envs_dir.clone(),
+        }
+    }
+
+    /// Resolves directory overrides against the install root, with the same
+    /// rules as the SQLite database path, and keeps them across updates.
+    fn normalize_dir_overrides(&mut self) -> Result<()> {
+        let root = crate::paths::install_root()?;
+        for (name, dir) in [
+            ("plugins_dir", &mut self.plugins_dir),
+            ("work_dir", &mut self.work_dir),
+            ("data_dir", &mut self.data_dir),
+            ("python_envs_dir", &mut self.python_envs_dir),
+        ] {
+            let Some(path) = dir.as_mut() else {
+                continue;
+            };
+            if path.as_os_str().is_empty() {
+                anyhow::bail!("{} cannot be empty", name);
+            }
+            if path
+                .components()
+                .any(|component| matches!(component, std::path::Component::ParentDir))
+            {
+                anyhow::bail!("{} cannot contain '..'", name);
+            }
+            if path.is_absolute() {
+                if !path.starts_with(&root) {
+                    anyhow::bail!("{} must be under install root: {}", name, root.display());
+                }
+            } else {
+                *path = root.join(&*path);
+            }
+            if let Ok(relative) = path.strip_prefix(&root)
+                && let Some(std::path::Component::Normal(top)) = relative.components().next()
+            {
+                let top = top.to_string_lossy().to_string();
+                if !self.update_preserve_dirs.contains(&top) {
+                    self.update_preserve_dirs.push(top);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_update_preserve_dirs(&self) -> Result<()> {
+        for dir in &self.update_preserve_dirs {
+            let mut components = Path::new(dir).components();
+            let is_plain_name = matches!(
+                (components.next(), components.next()),
+                (Some(std::path::Component::Normal(_)), None)
+            );
+            if !is_plain_name {
+                anyhow::bail!(
+                    "update_preserve_dirs entry '{}' must be a directory name in the install root",
+                    dir
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn normalize_executor_path_prepend(&mut self) -> Result<()> {
+        if self.executor_path_prepend.is_empty() {
+            return Ok(());
+        }
+        let root = crate::paths::install_root()?;
+        for dir in &mut self.executor_path_prepend {
+            if dir.as_os_str().is_empty() {
+                anyhow::bail!("executor_path_prepend entries cannot be empty");
+            }
+            if dir.is_relative() {
+                *dir = root.join(&*dir);
+            }
+        }
+        Ok(())
+    }
+
     fn normalize_uv_path(&mut self) -> Result<()> {
         let Some(path) = self.uv_path.as_ref() else {
             return Ok(());
@@ -140,4 +529,40 @@ struct FileConfig {
     host: Option<String>,
     port: Option<u16>,
     uv_path: Option<String>,
+    recycle_retention_hours: Option<u64>,
+    output_flush_ms: Option<u64>,
+    strict_metadata: Option<bool>,
+    container_runtime: Option<String>,
+    container_python_image: Option<String>,
+    container_node_image: Option<String>,
+    log_format: Option<LogFormat>,
+    expose_internal_errors: Option<bool>,
+    max_upload_mb: Option<u64>,
+    auto_disable_missing_runtime: Option<bool>,
+    update_public_key: Option<String>,
+    update_asset_pattern: Option<String>,
+    update_preserve_dirs: Option<Vec<String>>,
+    max_request_body_bytes: Option<usize>,
+    plugin_env_passthrough: Option<Vec<String>>,
+    inherit_plugin_env: Option<bool>,
+    offline: Option<bool>,
+    shell_path: Option<String>,
+    max_plugins: Option<u64>,
+    max_concurrent_executions: Option<usize>,
+    keep_work_dir_on_failure: Option<bool>,
+    work_dir_retention_hours: Option<u64>,
+    plugin_max_memory_mb: Option<u64>,
+    plugin_max_cpu_secs: Option<u64>,
+    strict_entry_point: Option<bool>,
+    db_max_connections: Option<u32>,
+    db_busy_timeout_ms: Option<u64>,
+    db_wal: Option<bool>,
+    allowed_hosts: Option<Vec<String>>,
+    executor_path_prepend: Option<Vec<PathBuf>>,
+    preview_sweep_interval_secs: Option<u64>,
+    plugins_dir: Option<PathBuf>,
+    work_dir: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+    python_envs_dir: Option<PathBuf>,
+    enabled_plugin_types: Option<Vec<String>>,
 }
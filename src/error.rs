@@ -1,7 +1,7 @@
 use axum::{
     Json,
     http::StatusCode,
-    response::{IntoResponse, Response},
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
 };
 use serde_json::json;
 use thiserror::Error;
@@ -31,13 +31,71 @@ pub enum AppError {
 
     #[error("Plugin is disabled")]
     PluginDisabled,
+
+    #[error("Plugin runtime missing: {0}")]
+    RuntimeMissing(String),
+
+    #[error("uv not found: {0}")]
+    UvNotFound(String),
+
+    #[error("Plugin icon not found: {0}")]
+    IconNotFound(String),
+
+    #[error("Plugin file not found: {0}")]
+    PluginFileNotFound(String),
+
+    #[error("Plugin limit reached: {0}")]
+    PluginLimitReached(u64),
+
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+}
+
+impl AppError {
+    /// Stable identifier clients can branch on instead of matching messages.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Database(_) => "database_error",
+            AppError::PluginNotFound(_) => "plugin_not_found",
+            AppError::PluginAlreadyExists(_) => "plugin_already_exists",
+            AppError::ExecutionNotFound(_) => "execution_not_found",
+            AppError::Execution(_) => "execution_error",
+            AppError::Io(_) => "io_error",
+            AppError::InvalidPluginType => "invalid_plugin_type",
+            AppError::PluginDisabled => "plugin_disabled",
+            AppError::RuntimeMissing(_) => "runtime_missing",
+            AppError::UvNotFound(_) => "uv_not_found",
+            AppError::IconNotFound(_) => "icon_not_found",
+            AppError::PluginFileNotFound(_) => "plugin_file_not_found",
+            AppError::PluginLimitReached(_) => "plugin_limit_reached",
+            AppError::RateLimited(_) => "rate_limited",
+            AppError::Conflict(_) => "conflict",
+            AppError::InvalidRequest(_) => "invalid_request",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::PayloadTooLarge(_) => "payload_too_large",
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let correlation_id = uuid::Uuid::new_v4().to_string();
+        let code = self.code();
         let (status, message) = match self {
             AppError::Database(e) => {
-                tracing::error!("Database error: {}", e);
+                tracing::error!(correlation_id, "Database error: {}", e);
                 (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
             }
             AppError::PluginNotFound(id) => {
@@ -53,20 +111,95 @@ impl IntoResponse for AppError {
             ),
             AppError::Execution(e) => (StatusCode::BAD_REQUEST, e),
             AppError::Io(e) => {
-                tracing::error!("IO error: {}", e);
+                tracing::error!(correlation_id, "IO error: {}", e);
                 (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
             }
             AppError::InvalidPluginType => {
                 (StatusCode::BAD_REQUEST, "Invalid plugin type".to_string())
             }
             AppError::PluginDisabled => (StatusCode::FORBIDDEN, "Plugin is disabled".to_string()),
+            AppError::RuntimeMissing(e) => (StatusCode::SERVICE_UNAVAILABLE, e),
+            AppError::UvNotFound(path) => (
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "uv was not found at '{}'. Install uv (https://docs.astral.sh/uv/) or set uv_path in conf/config.json",
+                    path
+                ),
+            ),
+            AppError::IconNotFound(id) => (
+                StatusCode::NOT_FOUND,
+                format!("Plugin '{}' has no icon", id),
+            ),
+            AppError::PluginFileNotFound(path) => (
+                StatusCode::NOT_FOUND,
+                format!("Plugin file '{}' not found", path),
+            ),
+            AppError::PluginLimitReached(max) => (
+                StatusCode::CONFLICT,
+                format!(
+                    "Plugin limit of {} reached; uninstall a plugin or raise max_plugins",
+                    max
+                ),
+            ),
+            AppError::RateLimited(e) => (StatusCode::TOO_MANY_REQUESTS, e),
+            AppError::Conflict(e) => (StatusCode::CONFLICT, e),
+            AppError::InvalidRequest(e) => (StatusCode::BAD_REQUEST, e),
+            AppError::Forbidden(e) => (StatusCode::FORBIDDEN, e),
+            AppError::PayloadTooLarge(e) => (StatusCode::PAYLOAD_TOO_LARGE, e),
         };
 
+        if status != StatusCode::INTERNAL_SERVER_ERROR {
+            let body = json!({
+                "error": message,
+                "code": code,
+                "correlation_id": correlation_id
+            });
+            return (status, Json(body)).into_response();
+        }
+
         let body = json!({
-            "error": message
+            "error": "Internal error",
+            "code": "internal_error",
+            "correlation_id": correlation_id
         });
+        let details = InternalErrorDetails {
+            code,
+            message,
+            correlation_id,
+        };
+        (status, details, Json(body)).into_response()
+    }
+}
+
+/// The real code and message of a 500 response, whose body only says
+/// "Internal error". The error layer puts them back when configured to.
+#[derive(Debug, Clone)]
+pub struct InternalErrorDetails {
+    code: &'static str,
+    message: String,
+    correlation_id: String,
+}
+
+impl IntoResponseParts for InternalErrorDetails {
+    type Error = std::convert::Infallible;
+
+    fn into_response_parts(
+        self,
+        mut res: ResponseParts,
+    ) -> std::result::Result<ResponseParts, Self::Error> {
+        res.extensions_mut().insert(self);
+        Ok(res)
+    }
+}
 
-        (status, Json(body)).into_response()
+impl IntoResponse for InternalErrorDetails {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "error": self.message,
+            "code": self.code,
+            "correlation_id": self.correlation_id
+        });
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
     }
 }
 
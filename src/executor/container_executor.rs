@@ -0,0 +1,386 @@
+use super::PluginExecutor;
+use crate::error::{AppError, Result};
+use crate::models::{Plugin, PluginType, ProcessPriority, PythonDependencies, ResourceLimits};
+use crate::paths;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const PLUGIN_MOUNT: &str = "/plugin";
+const WORK_MOUNT: &str = "/work";
+const STATE_MOUNT: &str = "/state";
+const DEPS_MOUNT: &str = "/deps";
+const STATE_DIR_ENV: &str = "ANTHILL_PLUGIN_STATE_DIR";
+/// Records what a deps dir was installed from, so changes trigger a reinstall.
+const DEPS_MARKER: &str = ".anthill-installed";
+
+#[derive(Clone)]
+pub struct ContainerExecutor {
+    runtime: String,
+    python_image: String,
+    node_image: String,
+    resource_limits: ResourceLimits,
+    dirs: paths::Dirs,
+    /// Serializes dependency installs so concurrent runs do not race on a deps dir.
+    install_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// Removes a run's container once dropped, unless [`ContainerHandle::exited`]
+/// was called. Killing the runtime CLI alone leaves the container running.
+pub struct ContainerHandle {
+    runtime: String,
+    name: String,
+    armed: bool,
+}
+
+impl ContainerHandle {
+    /// The container stopped on its own and `--rm` already removed it.
+    pub fn exited(&mut self) {
+        self.armed = false;
+    }
+
+    /// Force-removes the container now, killing the plugin inside it.
+    pub fn remove(&mut self) {
+        if std::mem::take(&mut self.armed) {
+            remove_container(&self.runtime, &self.name);
+        }
+    }
+}
+
+impl Drop for ContainerHandle {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+/// Runs `<runtime> rm -f <name>` without waiting for it.
+fn remove_container(runtime: &str, name: &str) {
+    match std::process::Command::new(runtime)
+        .args(["rm", "-f", name])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+    {
+        // Reap it off-thread; this also runs from Drop, where blocking is not an option
+        Ok(mut child) => {
+            std::thread::spawn(move || child.wait());
+        }
+        Err(err) => tracing::warn!("Failed to remove container {}: {}", name, err),
+    }
+}
+
+impl ContainerExecutor {
+    pub fn new(runtime: String, python_image: String, node_image: String) -> Self {
+        Self {
+            runtime,
+            python_image,
+            node_image,
+            resource_limits: ResourceLimits::default(),
+            dirs: paths::Dirs::default(),
+            install_lock: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// Container name for a run in `work_dir`, unique per execution.
+    fn container_name(work_dir: &Path) -> String {
+        let id = work_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        format!("anthill-{}", id)
+    }
+
+    /// Handle that removes the container started for `work_dir` when dropped.
+    pub fn handle(&self, work_dir: &Path) -> ContainerHandle {
+        ContainerHandle {
+            runtime: self.runtime.clone(),
+            name: Self::container_name(work_dir),
+            armed: true,
+        }
+    }
+
+    /// Force-removes the container of a recorded execution, e.g. on cancel.
+    pub fn stop(&self, execution_id: &str) {
+        remove_container(&self.runtime, &format!("anthill-{}", execution_id));
+    }
+
+    /// Installs a Python plugin's packages for the image's interpreter into a
+    /// host dir mounted at `/deps`. The host venv is not reused: its interpreter
+    /// and native wheels are built for the host, not the image.
+    async fn ensure_python_deps(&self, plugin: &Plugin) -> Result<Option<PathBuf>> {
+        if plugin.plugin_type != PluginType::Python {
+            return Ok(None);
+        }
+        let Some(raw) = plugin.python_dependencies.as_deref() else {
+            return Ok(None);
+        };
+        let declared: PythonDependencies = serde_json::from_str(raw).map_err(|e| {
+            AppError::Execution(format!("Invalid python dependencies of plugin: {}", e))
+        })?;
+        // Prefer the versions pinned when the host venv was built
+        let pinned: Vec<String> = plugin
+            .resolved_dependencies
+            .as_deref()
+            .and_then(|raw| serde_json::from_str::<Vec<String>>(raw).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|line| !line.starts_with('-') && !line.contains(" @ "))
+            .collect();
+        let install_args = match declared {
+            _ if !pinned.is_empty() => pinned,
+            PythonDependencies::Requirements { path } => vec![
+                "-r".to_string(),
+                format!("{}/{}", PLUGIN_MOUNT, path.replace('\\', "/")),
+            ],
+            PythonDependencies::Pyproject { .. } => {
+                return Err(AppError::Execution(format!(
+                    "Plugin {} has no resolved package list to install in a container; rebuild its python env",
+                    plugin.plugin_id
+                )));
+            }
+        };
+
+        let deps_dir = self.dirs.container_deps_dir()?.join(&plugin.plugin_id);
+        let marker = format!("{}\n{}", self.python_image, install_args.join("\n"));
+        let _guard = self.install_lock.lock().await;
+        if std::fs::read_to_string(deps_dir.join(DEPS_MARKER)).is_ok_and(|found| found == marker) {
+            return Ok(Some(deps_dir));
+        }
+
+        tracing::info!(
+            "Installing python dependencies of plugin {} for image {}",
+            plugin.plugin_id,
+            self.python_image
+        );
+        let _ = std::fs::remove_dir_all(&deps_dir);
+        std::fs::create_dir_all(&deps_dir)?;
+        let output = tokio::process::Command::new(&self.runtime)
+            .args(["run", "--rm"])
+            .arg("-v")
+            .arg(format!(
+                "{}:{}:ro",
+                Path::new(&plugin.plugin_path).display(),
+                PLUGIN_MOUNT
+            ))
+            .arg("-v")
+            .arg(format!("{}:{}", deps_dir.display(), DEPS_MOUNT))
+            .arg(&self.python_image)
+            .args([
+                "python3",
+                "-m",
+                "pip",
+                "install",
+                "--disable-pip-version-check",
+                "--no-cache-dir",
+                "--target",
+                DEPS_MOUNT,
+            ])
+            .args(&install_args)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .await
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => AppError::RuntimeMissing(format!(
+                    "Container runtime not found: {}",
+                    self.runtime
+                )),
+                _ => AppError::Io(e),
+            })?;
+        if !output.status.success() {
+            let _ = std::fs::remove_dir_all(&deps_dir);
+            return Err(AppError::Execution(format!(
+                "Failed to install python dependencies of plugin {} in {}: {}",
+                plugin.plugin_id,
+                self.python_image,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        std::fs::write(deps_dir.join(DEPS_MARKER), marker)?;
+        Ok(Some(deps_dir))
+    }
+
+    pub fn with_resource_limits(mut self, resource_limits: ResourceLimits) -> Self {
+        self.resource_limits = resource_limits;
+        self
+    }
+
+    pub fn with_dirs(mut self, dirs: paths::Dirs) -> Self {
+        self.dirs = dirs;
+        self
+    }
+
+    fn image_and_interpreter(&self, plugin_type: PluginType) -> (&str, &str) {
+        match plugin_type {
+            PluginType::Python => (&self.python_image, "python3"),
+            PluginType::JavaScript => (&self.node_image, "node"),
+            // The slim Python image ships a POSIX shell, so reuse it for shell plugins
+            PluginType::Shell => (&self.python_image, "sh"),
+        }
+    }
+}
+
+impl PluginExecutor for ContainerExecutor {
+    async fn execute(
+        &self,
+        plugin: &Plugin,
+        args: Vec<String>,
+        env: HashMap<String, String>,
+        work_dir: &Path,
+    ) -> Result<(u32, tokio::process::Child)> {
+        let plugin_dir = Path::new(&plugin.plugin_path);
+        let script_path = plugin_dir.join(&plugin.entry_point);
+        if !script_path.is_file() {
+            return Err(AppError::Execution(format!(
+                "Entry point not found: {}",
+                script_path.display()
+            )));
+        }
+
+        let entry_point = plugin.entry_point.replace('\\', "/");
+        let (image, interpreter) = self.image_and_interpreter(plugin.plugin_type);
+        let deps_dir = self.ensure_python_deps(plugin).await?;
+
+        // Build the command
+        let mut cmd = tokio::process::Command::new(&self.runtime);
+        cmd.args(["run", "--rm", "-i"]);
+        cmd.arg("--name").arg(Self::container_name(work_dir));
+        cmd.arg("-v")
+            .arg(format!("{}:{}:ro", plugin_dir.display(), PLUGIN_MOUNT));
+        cmd.arg("-v")
+            .arg(format!("{}:{}", work_dir.display(), WORK_MOUNT));
+        cmd.arg("-w").arg(WORK_MOUNT);
+        if let Some(deps_dir) = &deps_dir {
+            cmd.arg("-v")
+                .arg(format!("{}:{}:ro", deps_dir.display(), DEPS_MOUNT));
+        }
+
+        let limits = plugin
+            .settings()?
+            .resource_limits
+            .within(self.resource_limits);
+        if let Some(mb) = limits.max_memory_mb {
+            cmd.arg("--memory").arg(format!("{}m", mb));
+        }
+        if let Some(secs) = limits.max_cpu_secs {
+            cmd.arg("--ulimit")
+                .arg(format!("cpu={}:{}", secs, secs.saturating_add(1)));
+        }
+        // Relative to the runtime's default weight of 1024
+        match plugin.settings()?.priority {
+            ProcessPriority::Low => {
+                cmd.arg("--cpu-shares").arg("128");
+            }
+            ProcessPriority::BelowNormal => {
+                cmd.arg("--cpu-shares").arg("512");
+            }
+            ProcessPriority::Normal => {}
+        }
+
+        let mut env = env;
+        if deps_dir.is_some() {
+            env.insert("PYTHONPATH".to_string(), DEPS_MOUNT.to_string());
+        }
+        if let Some(state_dir) = env.get(STATE_DIR_ENV).cloned() {
+            cmd.arg("-v").arg(format!("{}:{}", state_dir, STATE_MOUNT));
+            env.insert(STATE_DIR_ENV.to_string(), STATE_MOUNT.to_string());
+        }
+
+        // Pass variables by name so their values do not show up in the process list
+        for (key, value) in env {
+            cmd.arg("-e").arg(&key);
+            cmd.env(key, value);
+        }
+
+        cmd.arg(image)
+            .arg(interpreter)
+            .arg(format!("{}/{}", PLUGIN_MOUNT, entry_point));
+        for arg in args {
+            cmd.arg(arg);
+        }
+        cmd.current_dir(work_dir);
+        super::isolate_process_group(&mut cmd);
+        super::pipe_stdio(&mut cmd);
+
+        let child = cmd.spawn().map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                AppError::RuntimeMissing(format!("Container runtime not found: {}", self.runtime))
+            }
+            _ => AppError::Execution(format!(
+                "Failed to start container runtime '{}': {}",
+                self.runtime, e
+            )),
+        })?;
+
+        let pid = child
+            .id()
+            .ok_or_else(|| AppError::Execution("Failed to get process ID".to_string()))?;
+
+        Ok((pid, child))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::plugin_repository::tests::plugin;
+
+    /// Whether a docker daemon answers; the container tests skip otherwise.
+    fn docker_available() -> bool {
+        std::process::Command::new("docker")
+            .arg("info")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    #[tokio::test]
+    async fn shell_plugin_output_is_captured_from_the_container() {
+        if !docker_available() {
+            eprintln!("skipping: docker is not available");
+            return;
+        }
+        let root = tempfile::tempdir().unwrap();
+        let plugin_dir = root.path().join("plugin");
+        let work_dir = root.path().join("work");
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        std::fs::create_dir_all(&work_dir).unwrap();
+        std::fs::write(
+            plugin_dir.join("main.sh"),
+            "echo \"hello from $ANTHILL_GREETING in $(pwd)\"\n",
+        )
+        .unwrap();
+        let plugin = Plugin {
+            plugin_path: plugin_dir.to_string_lossy().to_string(),
+            entry_point: "main.sh".to_string(),
+            ..plugin("p1")
+        };
+        let executor = ContainerExecutor::new(
+            "docker".to_string(),
+            "python:3.12-slim".to_string(),
+            "node:22-slim".to_string(),
+        )
+        .with_dirs(paths::Dirs::under(root.path()));
+
+        let (_, child) = executor
+            .execute(
+                &plugin,
+                Vec::new(),
+                HashMap::from([("ANTHILL_GREETING".to_string(), "docker".to_string())]),
+                &work_dir,
+            )
+            .await
+            .unwrap();
+        let output = child.wait_with_output().await.unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            format!("hello from docker in {}\n", WORK_MOUNT)
+        );
+    }
+}
@@ -1,13 +1,203 @@
+pub mod container_executor;
 pub mod node_executor;
 pub mod python_executor;
+pub mod shell_executor;
 
+pub use container_executor::{ContainerExecutor, ContainerHandle};
 pub use node_executor::NodeExecutor;
 pub use python_executor::PythonExecutor;
+pub use shell_executor::ShellExecutor;
 
 use crate::error::Result;
-use crate::models::Plugin;
+use crate::models::{Plugin, ProcessPriority, ResourceLimits};
 use std::collections::HashMap;
-use std::path::Path;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Host variables kept when a plugin's inherited environment is cleared; a
+/// trailing `*` matches a prefix. Tools commonly break without these.
+#[cfg(windows)]
+const ESSENTIAL_ENV: &[&str] = &[
+    "PATH",
+    "SYSTEMROOT",
+    "TEMP",
+    "TMP",
+    "USERPROFILE",
+    "USERNAME",
+    "APPDATA",
+    "LOCALAPPDATA",
+];
+#[cfg(not(windows))]
+const ESSENTIAL_ENV: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "LANG", "LC_*", "TMPDIR", "TZ",
+];
+
+fn is_essential_env(key: &str) -> bool {
+    ESSENTIAL_ENV
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => key.starts_with(prefix),
+            None => key == *pattern,
+        })
+}
+
+fn clear_inherited_env(cmd: &mut tokio::process::Command) {
+    cmd.env_clear();
+    for (key, value) in std::env::vars_os() {
+        if key.to_str().is_some_and(is_essential_env) {
+            cmd.env(key, value);
+        }
+    }
+}
+
+/// Puts `dirs` in front of the child's PATH, taken from `env` or else the host.
+fn prepend_path(env: &mut HashMap<String, String>, dirs: &[PathBuf]) {
+    if dirs.is_empty() {
+        return;
+    }
+    let current = env
+        .get("PATH")
+        .map(OsString::from)
+        .or_else(|| std::env::var_os("PATH"))
+        .unwrap_or_default();
+    let paths = dirs
+        .iter()
+        .cloned()
+        .chain(std::env::split_paths(&current))
+        .filter(|path| !path.as_os_str().is_empty());
+    match std::env::join_paths(paths) {
+        Ok(joined) => {
+            env.insert("PATH".to_string(), joined.to_string_lossy().to_string());
+        }
+        Err(err) => tracing::warn!("Cannot extend plugin PATH: {}", err),
+    }
+}
+
+/// Sets rlimits in the child before exec; a no-op off Unix.
+#[cfg(unix)]
+fn apply_resource_limits(cmd: &mut tokio::process::Command, limits: ResourceLimits) {
+    if limits == ResourceLimits::default() {
+        return;
+    }
+    // SAFETY: the hook only calls setrlimit, which is async-signal-safe
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(mb) = limits.max_memory_mb {
+                let bytes = mb.saturating_mul(1024 * 1024) as libc::rlim_t;
+                let limit = libc::rlimit {
+                    rlim_cur: bytes,
+                    rlim_max: bytes,
+                };
+                if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            if let Some(secs) = limits.max_cpu_secs {
+                // The soft limit raises SIGXCPU; the hard limit a second later is SIGKILL
+                let limit = libc::rlimit {
+                    rlim_cur: secs as libc::rlim_t,
+                    rlim_max: secs.saturating_add(1) as libc::rlim_t,
+                };
+                if libc::setrlimit(libc::RLIMIT_CPU, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_resource_limits(_cmd: &mut tokio::process::Command, _limits: ResourceLimits) {}
+
+/// Lowers the child's scheduling priority via `nice` or the Windows priority class.
+fn apply_priority(cmd: &mut tokio::process::Command, priority: ProcessPriority) {
+    #[cfg(unix)]
+    {
+        let nice = match priority {
+            ProcessPriority::Low => 19,
+            ProcessPriority::BelowNormal => 10,
+            ProcessPriority::Normal => return,
+        };
+        // SAFETY: the hook only calls setpriority, which is async-signal-safe
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    #[cfg(windows)]
+    {
+        const IDLE_PRIORITY_CLASS: u32 = 0x0000_0040;
+        const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+        match priority {
+            ProcessPriority::Low => {
+                cmd.creation_flags(IDLE_PRIORITY_CLASS);
+            }
+            ProcessPriority::BelowNormal => {
+                cmd.creation_flags(BELOW_NORMAL_PRIORITY_CLASS);
+            }
+            ProcessPriority::Normal => {}
+        }
+    }
+}
+
+/// Starts the child as the leader of a new process group so [`kill_process_tree`]
+/// also reaches processes it spawns; a no-op off Unix.
+pub(crate) fn isolate_process_group(cmd: &mut tokio::process::Command) {
+    #[cfg(unix)]
+    cmd.process_group(0);
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+pub(crate) fn pipe_stdio(cmd: &mut tokio::process::Command) {
+    // Capture stdout and stderr; stdin is closed unless params are delivered on it
+    cmd.stdin(std::process::Stdio::piped());
+    cmd.stdout(std::process::Stdio::piped());
+    cmd.stderr(std::process::Stdio::piped());
+}
+
+/// Setup shared by the local executors: `env` goes to the child with
+/// `path_prepend` in front of PATH, inherited variables are cleared first when
+/// `clear_env` is set, and the plugin's limits (capped by `limits`) and priority
+/// apply to a child in its own process group with piped stdio.
+fn prepare_command(
+    cmd: &mut tokio::process::Command,
+    plugin: &Plugin,
+    mut env: HashMap<String, String>,
+    clear_env: bool,
+    path_prepend: &[PathBuf],
+    limits: ResourceLimits,
+) -> Result<()> {
+    if clear_env {
+        clear_inherited_env(cmd);
+    }
+    prepend_path(&mut env, path_prepend);
+    cmd.envs(env);
+
+    let settings = plugin.settings()?;
+    apply_resource_limits(cmd, settings.resource_limits.within(limits));
+    apply_priority(cmd, settings.priority);
+    isolate_process_group(cmd);
+    pipe_stdio(cmd);
+    Ok(())
+}
+
+/// Kills the child and, on Unix, every process in its group.
+pub(crate) fn kill_process_tree(child: &mut tokio::process::Child) -> std::io::Result<()> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id().and_then(|pid| libc::pid_t::try_from(pid).ok()) {
+        // SAFETY: plain syscall; the group id is the child's pid, set at spawn
+        if unsafe { libc::kill(-pid, libc::SIGKILL) } == 0 {
+            return Ok(());
+        }
+    }
+    child.start_kill()
+}
 
 pub(crate) trait PluginExecutor {
     async fn execute(
@@ -18,3 +208,25 @@ pub(crate) trait PluginExecutor {
         work_dir: &Path,
     ) -> Result<(u32, tokio::process::Child)>;
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn essential_env_keeps_the_standard_minimal_set() {
+        for key in [
+            "PATH", "HOME", "USER", "LANG", "LC_ALL", "LC_CTYPE", "TMPDIR",
+        ] {
+            assert!(is_essential_env(key), "{} should be kept", key);
+        }
+        for key in [
+            "AWS_SECRET_ACCESS_KEY",
+            "DATABASE_URL",
+            "HOMEBREW_PREFIX",
+            "LANGUAGE_X",
+        ] {
+            assert!(!is_essential_env(key), "{} should be dropped", key);
+        }
+    }
+}
@@ -1,20 +1,43 @@
 use super::PluginExecutor;
 use crate::error::{AppError, Result};
-use crate::models::Plugin;
+use crate::models::{Plugin, ResourceLimits};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Clone)]
 pub struct NodeExecutor {
     node_path: String,
+    clear_env: bool,
+    resource_limits: ResourceLimits,
+    path_prepend: Vec<PathBuf>,
 }
 
 impl NodeExecutor {
     pub fn new(node_path: Option<String>) -> Self {
         Self {
             node_path: node_path.unwrap_or_else(|| "node".to_string()),
+            clear_env: true,
+            resource_limits: ResourceLimits::default(),
+            path_prepend: Vec::new(),
         }
     }
+
+    pub fn with_clear_env(mut self, clear_env: bool) -> Self {
+        self.clear_env = clear_env;
+        self
+    }
+
+    /// Server-wide limits, used as defaults and ceilings for each plugin's own.
+    pub fn with_resource_limits(mut self, resource_limits: ResourceLimits) -> Self {
+        self.resource_limits = resource_limits;
+        self
+    }
+
+    /// Directories put in front of the child's PATH.
+    pub fn with_path_prepend(mut self, path_prepend: Vec<PathBuf>) -> Self {
+        self.path_prepend = path_prepend;
+        self
+    }
 }
 
 impl Default for NodeExecutor {
@@ -48,16 +71,21 @@ impl PluginExecutor for NodeExecutor {
             cmd.arg(arg);
         }
 
-        // Set environment variables
-        for (key, value) in env {
-            cmd.env(key, value);
-        }
-
-        // Capture stdout and stderr
-        cmd.stdout(std::process::Stdio::piped());
-        cmd.stderr(std::process::Stdio::piped());
+        super::prepare_command(
+            &mut cmd,
+            plugin,
+            env,
+            self.clear_env,
+            &self.path_prepend,
+            self.resource_limits,
+        )?;
 
-        let child = cmd.spawn()?;
+        let child = cmd.spawn().map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                AppError::RuntimeMissing(format!("Node.js runtime not found: {}", self.node_path))
+            }
+            _ => AppError::Io(e),
+        })?;
 
         let pid = child
             .id()
@@ -1,20 +1,43 @@
 use super::PluginExecutor;
 use crate::error::{AppError, Result};
-use crate::models::Plugin;
+use crate::models::{Plugin, ResourceLimits};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 #[derive(Clone)]
 pub struct PythonExecutor {
     python_path: String,
+    clear_env: bool,
+    resource_limits: ResourceLimits,
+    path_prepend: Vec<PathBuf>,
 }
 
 impl PythonExecutor {
     pub fn new(python_path: Option<String>) -> Self {
         Self {
             python_path: python_path.unwrap_or_else(|| "python3".to_string()),
+            clear_env: true,
+            resource_limits: ResourceLimits::default(),
+            path_prepend: Vec::new(),
         }
     }
+
+    pub fn with_clear_env(mut self, clear_env: bool) -> Self {
+        self.clear_env = clear_env;
+        self
+    }
+
+    /// Server-wide limits, used as defaults and ceilings for each plugin's own.
+    pub fn with_resource_limits(mut self, resource_limits: ResourceLimits) -> Self {
+        self.resource_limits = resource_limits;
+        self
+    }
+
+    /// Directories put in front of the child's PATH.
+    pub fn with_path_prepend(mut self, path_prepend: Vec<PathBuf>) -> Self {
+        self.path_prepend = path_prepend;
+        self
+    }
 }
 
 impl Default for PythonExecutor {
@@ -44,7 +67,7 @@ impl PluginExecutor for PythonExecutor {
                 let venv_root = PathBuf::from(venv_path);
                 let venv_python = Self::python_executable_path(&venv_root);
                 if !venv_python.is_file() {
-                    return Err(AppError::Execution(format!(
+                    return Err(AppError::RuntimeMissing(format!(
                         "Python venv not found: {}",
                         venv_python.display()
                     )));
@@ -63,37 +86,33 @@ impl PluginExecutor for PythonExecutor {
             cmd.arg(arg);
         }
 
-        // Set environment variables
         let mut env = env;
+        let mut path_prepend = Vec::with_capacity(self.path_prepend.len() + 1);
         if let Some(venv_root) = venv_root {
-            let bin_dir = Self::python_bin_dir(&venv_root);
+            // The venv stays ahead of the configured dirs
+            path_prepend.push(Self::python_bin_dir(&venv_root));
             env.insert(
                 "VIRTUAL_ENV".to_string(),
                 venv_root.to_string_lossy().to_string(),
             );
-            let path_separator = if cfg!(windows) { ";" } else { ":" };
-            let existing_path = env
-                .get("PATH")
-                .cloned()
-                .or_else(|| std::env::var("PATH").ok());
-            let new_path = match existing_path {
-                Some(current) if !current.is_empty() => {
-                    format!("{}{}{}", bin_dir.display(), path_separator, current)
-                }
-                _ => bin_dir.to_string_lossy().to_string(),
-            };
-            env.insert("PATH".to_string(), new_path);
         }
+        path_prepend.extend(self.path_prepend.iter().cloned());
+        super::prepare_command(
+            &mut cmd,
+            plugin,
+            env,
+            self.clear_env,
+            &path_prepend,
+            self.resource_limits,
+        )?;
 
-        for (key, value) in env {
-            cmd.env(key, value);
-        }
-
-        // Capture stdout and stderr
-        cmd.stdout(std::process::Stdio::piped());
-        cmd.stderr(std::process::Stdio::piped());
-
-        let child = cmd.spawn()?;
+        let child = cmd.spawn().map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::RuntimeMissing(format!(
+                "Python interpreter not found: {}",
+                python_path.display()
+            )),
+            _ => AppError::Io(e),
+        })?;
 
         let pid = child
             .id()
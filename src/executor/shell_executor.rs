@@ -0,0 +1,129 @@
+use super::PluginExecutor;
+use crate::error::{AppError, Result};
+use crate::models::{Plugin, ResourceLimits};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[cfg(windows)]
+const DEFAULT_SHELL: &str = "bash";
+#[cfg(not(windows))]
+const DEFAULT_SHELL: &str = "sh";
+
+#[derive(Clone)]
+pub struct ShellExecutor {
+    shell_path: String,
+    clear_env: bool,
+    resource_limits: ResourceLimits,
+    path_prepend: Vec<PathBuf>,
+}
+
+impl ShellExecutor {
+    pub fn new(shell_path: Option<String>) -> Self {
+        Self {
+            shell_path: shell_path.unwrap_or_else(|| DEFAULT_SHELL.to_string()),
+            clear_env: true,
+            resource_limits: ResourceLimits::default(),
+            path_prepend: Vec::new(),
+        }
+    }
+
+    pub fn with_clear_env(mut self, clear_env: bool) -> Self {
+        self.clear_env = clear_env;
+        self
+    }
+
+    /// Server-wide limits, used as defaults and ceilings for each plugin's own.
+    pub fn with_resource_limits(mut self, resource_limits: ResourceLimits) -> Self {
+        self.resource_limits = resource_limits;
+        self
+    }
+
+    /// Directories put in front of the child's PATH.
+    pub fn with_path_prepend(mut self, path_prepend: Vec<PathBuf>) -> Self {
+        self.path_prepend = path_prepend;
+        self
+    }
+
+    pub fn with_shell_path(mut self, shell_path: String) -> Self {
+        self.shell_path = shell_path;
+        self
+    }
+
+    /// Picks the interpreter and its leading arguments for a script.
+    fn interpreter(&self, script_path: &Path) -> (String, Vec<String>) {
+        let extension = script_path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("ps1") if cfg!(windows) => (
+                "powershell".to_string(),
+                vec![
+                    "-NoProfile".to_string(),
+                    "-ExecutionPolicy".to_string(),
+                    "Bypass".to_string(),
+                    "-File".to_string(),
+                ],
+            ),
+            Some("cmd" | "bat") if cfg!(windows) => ("cmd".to_string(), vec!["/C".to_string()]),
+            _ => (self.shell_path.clone(), Vec::new()),
+        }
+    }
+}
+
+impl Default for ShellExecutor {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl PluginExecutor for ShellExecutor {
+    async fn execute(
+        &self,
+        plugin: &Plugin,
+        args: Vec<String>,
+        env: HashMap<String, String>,
+        work_dir: &Path,
+    ) -> Result<(u32, tokio::process::Child)> {
+        let script_path = Path::new(&plugin.plugin_path).join(&plugin.entry_point);
+        if !script_path.is_file() {
+            return Err(AppError::Execution(format!(
+                "Entry point not found: {}",
+                script_path.display()
+            )));
+        }
+
+        let (interpreter, interpreter_args) = self.interpreter(&script_path);
+
+        // Build the command
+        let mut cmd = tokio::process::Command::new(&interpreter);
+        cmd.args(interpreter_args);
+        cmd.arg(&script_path);
+        cmd.current_dir(work_dir);
+
+        for arg in args {
+            cmd.arg(arg);
+        }
+
+        super::prepare_command(
+            &mut cmd,
+            plugin,
+            env,
+            self.clear_env,
+            &self.path_prepend,
+            self.resource_limits,
+        )?;
+
+        let child = cmd.spawn().map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                AppError::RuntimeMissing(format!("Shell not found: {}", interpreter))
+            }
+            _ => AppError::Io(e),
+        })?;
+
+        let pid = child
+            .id()
+            .ok_or_else(|| AppError::Execution("Failed to get process ID".to_string()))?;
+
+        Ok((pid, child))
+    }
+}
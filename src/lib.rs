@@ -5,3 +5,4 @@ pub mod models;
 pub mod paths;
 pub mod repository;
 pub mod services;
+pub mod shutdown;
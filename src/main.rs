@@ -11,18 +11,26 @@ mod models;
 mod paths;
 mod repository;
 mod services;
+mod shutdown;
 #[cfg(target_os = "windows")]
 mod windows_tray;
 
-use crate::config::Config;
-use crate::repository::{ExecutionRepository, PluginRepository, establish_connection};
-use crate::services::{ExecutionService, PluginService, UpdateService};
+use crate::config::{Config, LogFormat};
+use crate::executor::ContainerExecutor;
+use crate::models::ResourceLimits;
+use crate::repository::{
+    AuditRepository, ExecutionRepository, PluginRepository, PoolSettings, establish_connection,
+    redact_database_url,
+};
+use crate::services::{
+    AuditService, ExecutionService, PluginService, SystemService, UpdateService,
+};
+use crate::shutdown::Shutdown;
 use api::create_router;
-use std::future::Future;
 use std::net::SocketAddr;
 use std::path::PathBuf;
-use tower_http::trace::TraceLayer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use std::time::Duration;
+use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
 
 fn prepend_bin_to_path() -> anyhow::Result<()> {
     let bin_dir = crate::paths::install_root()?.join("bin");
@@ -42,28 +50,65 @@ fn prepend_bin_to_path() -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn run_server<F>(shutdown: F) -> anyhow::Result<()>
-where
-    F: Future<Output = ()> + Send + 'static,
-{
+#[cfg(unix)]
+fn bind_unix_socket(path: &std::path::Path) -> anyhow::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    // Remove a socket left behind by a previous run, but never clobber other files
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => anyhow::bail!("Refusing to replace non-socket file {}", path.display()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// How long plugins killed on shutdown get to have their results recorded.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+async fn run_server(
+    config: Config,
+    shutdown: Shutdown,
+    stopped: tokio::sync::oneshot::Receiver<()>,
+) -> anyhow::Result<()> {
     // Initialize tracing
+    let fmt_layer = match config.log_format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().boxed(),
+    };
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "anthill=debug,tower_http=debug,axum=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(fmt_layer)
         .init();
 
     prepend_bin_to_path()?;
 
-    if let Err(err) = UpdateService::apply_pending_update() {
+    let mut update_service = UpdateService::new()
+        .with_asset_pattern(config.update_asset_pattern.clone())
+        .with_preserve_dirs(config.update_preserve_dirs.clone())
+        .with_shutdown(shutdown);
+    if let Some(public_key) = config.update_public_key.as_deref() {
+        update_service = update_service.with_public_key(public_key)?;
+        tracing::info!("Update packages must be signed");
+    }
+
+    if let Err(err) = update_service.apply_pending_update() {
         tracing::error!("Failed to apply pending update: {}", err);
     }
 
-    // Load configuration
-    let config = Config::from_env()?;
-    tracing::info!("Starting anthill with config: {:?}", config);
+    tracing::info!("Starting anthill {}", env!("CARGO_PKG_VERSION"));
 
     if let Some(path) = config.database_url.strip_prefix("sqlite:") {
         let path = std::path::Path::new(path);
@@ -73,61 +118,240 @@ where
     }
 
     // Establish database connection
-    let db_pool = establish_connection(&config.database_url).await?;
-    tracing::info!("Database connected: {}", config.database_url);
+    let db_pool = establish_connection(
+        &config.database_url,
+        PoolSettings {
+            max_connections: config.db_max_connections,
+            busy_timeout: std::time::Duration::from_millis(config.db_busy_timeout_ms),
+            wal: config.db_wal,
+        },
+    )
+    .await?;
+    tracing::info!(
+        "Database connected: {}",
+        redact_database_url(&config.database_url)
+    );
 
     // Initialize repositories
     let plugin_repo = PluginRepository::new(db_pool.clone());
-    let execution_repo = ExecutionRepository::new(db_pool);
+    let execution_repo = ExecutionRepository::new(db_pool.clone());
+    let audit_service = AuditService::new(AuditRepository::new(db_pool));
 
     // Initialize services
-    let plugin_service = PluginService::new(plugin_repo.clone(), config.uv_path.clone());
-    let execution_service = ExecutionService::new(execution_repo, plugin_repo);
+    let dirs = config.dirs();
+    let plugin_types = config.plugin_types()?;
+    if !plugin_types.is_empty() {
+        tracing::info!("Enabled plugin types: {:?}", plugin_types);
+    }
+    let plugin_service = PluginService::new(plugin_repo.clone(), config.uv_path.clone())
+        .with_recycle_retention_ms((config.recycle_retention_hours * 60 * 60 * 1000) as i64)
+        .with_strict_metadata(config.strict_metadata)
+        .with_strict_entry_point(config.strict_entry_point)
+        .with_max_upload_bytes(config.max_upload_mb * 1024 * 1024)
+        .with_offline(config.offline)
+        .with_max_plugins(config.max_plugins)
+        .with_allowed_hosts(config.allowed_hosts.clone())
+        .with_enabled_plugin_types(plugin_types.clone())
+        .with_audit(audit_service.clone())
+        .with_dirs(dirs.clone());
+    let mut execution_service = ExecutionService::new(execution_repo, plugin_repo)
+        .with_output_flush_ms(config.output_flush_ms)
+        .with_auto_disable_missing_runtime(config.auto_disable_missing_runtime)
+        .with_env_passthrough(config.plugin_env_passthrough.clone())
+        .with_inherit_env(config.inherit_plugin_env)
+        .with_path_prepend(config.executor_path_prepend.clone())
+        .with_shell_path(config.shell_path.clone())
+        .with_keep_work_dir_on_failure(config.keep_work_dir_on_failure)
+        .with_max_concurrent_executions(config.max_concurrent_executions)
+        .with_work_dir_retention_ms((config.work_dir_retention_hours * 60 * 60 * 1000) as i64)
+        .with_allowed_hosts(config.allowed_hosts.clone())
+        .with_enabled_plugin_types(plugin_types)
+        .with_audit(audit_service.clone())
+        .with_dirs(dirs.clone())
+        .with_resource_limits(ResourceLimits {
+            max_memory_mb: config.plugin_max_memory_mb,
+            max_cpu_secs: config.plugin_max_cpu_secs,
+        });
+    if let Some(runtime) = config.container_runtime.clone() {
+        tracing::info!("Running plugins in containers via {}", runtime);
+        execution_service = execution_service.with_container_executor(ContainerExecutor::new(
+            runtime,
+            config.container_python_image.clone(),
+            config.container_node_image.clone(),
+        ));
+    }
+    match execution_service.recover_interrupted().await {
+        Ok(0) => {}
+        Ok(count) => tracing::warn!("Marked {} interrupted executions as failed", count),
+        Err(err) => tracing::error!("Failed to recover interrupted executions: {}", err),
+    }
+
+    // Purge expired recycle bin entries
+    let sweeper = plugin_service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            match sweeper.purge_expired_recycled().await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Purged {} expired recycled plugins", count),
+                Err(err) => tracing::warn!("Failed to purge recycle bin: {}", err),
+            }
+        }
+    });
+
+    // Purge work dirs retained from failed executions
+    if config.keep_work_dir_on_failure {
+        let sweeper = execution_service.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+            loop {
+                interval.tick().await;
+                match sweeper.purge_expired_work_dirs().await {
+                    Ok(0) => {}
+                    Ok(count) => tracing::info!("Purged {} expired work dirs", count),
+                    Err(err) => tracing::warn!("Failed to purge work dirs: {}", err),
+                }
+            }
+        });
+    }
+
+    // Expire previews left unapplied past their expiry
+    if config.preview_sweep_interval_secs > 0 {
+        let sweeper = execution_service.clone();
+        let period = std::time::Duration::from_secs(config.preview_sweep_interval_secs);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                match sweeper.prune_previews(false).await {
+                    Ok(pruned) if pruned.is_empty() => {}
+                    Ok(pruned) => tracing::info!("Expired {} unapplied previews", pruned.len()),
+                    Err(err) => tracing::warn!("Failed to sweep expired previews: {}", err),
+                }
+            }
+        });
+    }
+
+    // Keep the tray tooltip counts current
+    #[cfg(target_os = "windows")]
+    {
+        let plugins = plugin_service.clone();
+        let executions = execution_service.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+            loop {
+                interval.tick().await;
+                if let Ok(count) = plugins.count_plugins().await {
+                    windows_tray::PLUGIN_COUNT
+                        .store(count as usize, std::sync::atomic::Ordering::Relaxed);
+                }
+                if let Ok(count) = executions.count_running().await {
+                    windows_tray::RUNNING_COUNT
+                        .store(count as usize, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+        });
+    }
 
     // Create router
-    let app = create_router(plugin_service, execution_service);
-    let app = app.layer(TraceLayer::new_for_http());
+    let executions = execution_service.clone();
+    let app = create_router(
+        plugin_service,
+        execution_service,
+        update_service,
+        SystemService::new(config.reported()).with_dirs(dirs),
+        audit_service,
+        config.max_request_body_bytes,
+        config.expose_internal_errors,
+    );
 
     // Start server
-    let addr = format!("{}:{}", config.host, config.port);
-    let addr = addr.parse::<SocketAddr>()?;
-    tracing::info!("Server listening on {}", addr);
+    let stopped = async move {
+        let _ = stopped.await;
+    };
+    if let Some(socket_path) = config.host.strip_prefix("unix:") {
+        #[cfg(unix)]
+        {
+            let listener = bind_unix_socket(std::path::Path::new(socket_path))?;
+            tracing::info!("Server listening on unix:{}", socket_path);
+            axum::serve(listener, app)
+                .with_graceful_shutdown(stopped)
+                .await?;
+        }
+        #[cfg(not(unix))]
+        anyhow::bail!(
+            "Unix domain sockets are not supported on this platform: {}",
+            socket_path
+        );
+    } else {
+        let addr = format!("{}:{}", config.host, config.port);
+        let addr = addr.parse::<SocketAddr>()?;
+        tracing::info!("Server listening on {}", addr);
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown)
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        // Peer addresses let loopback-only routes tell local callers apart
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(stopped)
         .await?;
+    }
 
+    // Plugin processes run in their own process groups and would outlive the server
+    executions.shutdown(SHUTDOWN_GRACE).await;
     Ok(())
 }
 
 #[cfg(not(target_os = "windows"))]
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    run_server(std::future::pending::<()>()).await
+    // Resolve before an update replaces the binary: on Linux the path of a
+    // replaced executable gets a " (deleted)" suffix
+    let exe = std::env::current_exe()?;
+    let config = Config::from_env()?;
+    let (shutdown, stopped) = Shutdown::new();
+    run_server(config, shutdown.clone(), stopped).await?;
+
+    if shutdown.restart_requested() {
+        tracing::info!("Restarting {}", exe.display());
+        shutdown::relaunch(&exe)?;
+    }
+    Ok(())
 }
 
 #[cfg(target_os = "windows")]
 fn main() -> anyhow::Result<()> {
-    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+    let config = Config::from_env()?;
+    let base_url = windows_tray::base_url(&config.host, config.port);
+    let (shutdown, stopped) = Shutdown::new();
 
     let runtime = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()?;
 
-    let server_handle = runtime.spawn(run_server(async move {
-        let _ = shutdown_rx.await;
-    }));
+    let server_handle = runtime.spawn(run_server(config, shutdown.clone(), stopped));
 
-    let _tray_thread = std::thread::spawn(move || {
-        if let Err(err) = windows_tray::run_tray_loop(shutdown_tx) {
+    let tray_shutdown = shutdown.clone();
+    let tray_thread = std::thread::spawn(move || {
+        if let Err(err) = windows_tray::run_tray_loop(base_url, tray_shutdown) {
             eprintln!("tray loop failed: {err}");
         }
     });
 
     match runtime.block_on(async { server_handle.await }) {
-        Ok(Ok(())) => Ok(()),
-        Ok(Err(err)) => Err(err),
-        Err(err) => Err(anyhow::anyhow!(err)),
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => return Err(err),
+        Err(err) => return Err(anyhow::anyhow!(err)),
+    }
+
+    if shutdown.restart_requested() {
+        // Wait so the tray icon is gone before relaunching. The new process
+        // inherits our environment, ANTHILL_HOME included.
+        windows_tray::quit();
+        let _ = tray_thread.join();
+        shutdown::relaunch(&std::env::current_exe()?)?;
     }
+    Ok(())
 }
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// One row of the append-only audit log.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: String,
+    /// `install`, `update`, `uninstall`, `delete`, `restore`, `enable`,
+    /// `disable`, `prepare`, `execute` or `apply`.
+    pub action: String,
+    pub plugin_id: String,
+    /// Who triggered the action; `None` until requests carry an identity.
+    pub actor: Option<String>,
+    pub timestamp: i64,
+    /// Action-specific JSON object.
+    pub detail: Option<String>,
+}
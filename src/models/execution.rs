@@ -15,6 +15,46 @@ pub struct Execution {
     pub expires_at: Option<i64>,
     pub started_at: i64,
     pub finished_at: Option<i64>,
+    pub applied_at: Option<i64>,
+    pub prepare_duration_ms: Option<i64>,
+    /// Prepare-phase output, kept once the apply phase takes over stdout/stderr.
+    pub preview_stdout: Option<String>,
+    pub preview_stderr: Option<String>,
+    pub idempotency_key: Option<String>,
+    /// Started with `ANTHILL_DRY_RUN=1`; the plugin was asked to skip side effects.
+    pub dry_run: bool,
+    /// Resolved parameters as a JSON object; the apply phase overwrites the prepare ones.
+    pub params: Option<String>,
+    /// Unix signal that terminated the process, if it did not exit normally.
+    pub exit_signal: Option<i32>,
+    /// Name of the `metadata.commands` entry that was run; `None` for the main entry point.
+    pub command: Option<String>,
+    /// URL that receives the final execution once it reaches a terminal status.
+    pub callback_url: Option<String>,
+}
+
+/// Most recent execution of a plugin, as joined into the plugin list.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct LastExecution {
+    pub plugin_id: String,
+    pub id: String,
+    pub phase: ExecutionPhase,
+    pub status: ExecutionStatus,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+impl Execution {
+    /// Conventional name of `exit_signal`, such as `SIGKILL`.
+    pub fn exit_signal_name(&self) -> Option<&'static str> {
+        self.exit_signal.and_then(signal_name)
+    }
+
+    pub fn duration_ms(&self) -> Option<i64> {
+        let started_at = self.applied_at.unwrap_or(self.started_at);
+        self.finished_at
+            .map(|finished_at| (finished_at - started_at).max(0))
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq)]
@@ -34,4 +74,60 @@ pub enum ExecutionStatus {
     Completed = 4,
     Failed = 5,
     Stopped = 6,
+    /// A preview that was not applied before `expires_at`.
+    Expired = 7,
+}
+
+impl ExecutionStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Self::Completed | Self::Failed | Self::Stopped | Self::Expired
+        )
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalized = name.trim().replace(['_', '-'], "").to_ascii_lowercase();
+        match normalized.as_str() {
+            "pending" => Some(Self::Pending),
+            "running" => Some(Self::Running),
+            "previewready" => Some(Self::PreviewReady),
+            "applying" => Some(Self::Applying),
+            "completed" => Some(Self::Completed),
+            "failed" => Some(Self::Failed),
+            "stopped" => Some(Self::Stopped),
+            "expired" => Some(Self::Expired),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn signal_name(signal: i32) -> Option<&'static str> {
+    let name = match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGUSR1 => "SIGUSR1",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGUSR2 => "SIGUSR2",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGXCPU => "SIGXCPU",
+        libc::SIGXFSZ => "SIGXFSZ",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(not(unix))]
+fn signal_name(_signal: i32) -> Option<&'static str> {
+    None
 }
@@ -1,7 +1,11 @@
+pub mod audit;
 pub mod execution;
 pub mod plugin;
 
-pub use execution::{Execution, ExecutionPhase, ExecutionStatus};
+pub use audit::AuditEntry;
+pub use execution::{Execution, ExecutionPhase, ExecutionStatus, LastExecution};
 pub use plugin::{
-    Plugin, PluginParamType, PluginParameter, PluginParameterGroup, PluginType, PythonDependencies,
+    ParamDelivery, Plugin, PluginCommand, PluginHealthCheck, PluginMetadata, PluginParamType,
+    PluginParameter, PluginParameterGroup, PluginParameterValidation, PluginRateLimit, PluginType,
+    PluginVersion, ProcessPriority, PythonDependencies, ResourceLimits,
 };
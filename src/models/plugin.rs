@@ -1,5 +1,9 @@
+use crate::error::{AppError, Result};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Plugin {
@@ -21,6 +25,224 @@ pub struct Plugin {
     pub python_dependencies: Option<String>,
     pub created_at: i64,
     pub updated_at: i64,
+    pub deleted_at: Option<i64>,
+    pub default_timeout_ms: Option<i64>,
+    pub max_timeout_ms: Option<i64>,
+    pub disabled_reason: Option<String>,
+    pub resolved_dependencies: Option<String>,
+    pub tags: Option<String>,
+    /// Interpreter version of the venv when it was built, e.g. `3.12.4`.
+    pub python_version: Option<String>,
+    /// `metadata` parsed on first use by [`Plugin::settings`].
+    #[sqlx(skip)]
+    #[serde(skip)]
+    pub(crate) settings: OnceLock<std::result::Result<PluginMetadata, String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PluginVersion {
+    pub plugin_id: String,
+    pub version: String,
+    pub installed_at: i64,
+    pub package_sha256: String,
+}
+
+impl Plugin {
+    /// Feature settings from `metadata`, parsed once per loaded plugin.
+    ///
+    /// Fails instead of falling back to defaults, so a malformed stored value
+    /// never silently turns into "unlimited" at run time.
+    pub fn settings(&self) -> Result<&PluginMetadata> {
+        self.settings
+            .get_or_init(|| {
+                let metadata = self
+                    .metadata
+                    .as_deref()
+                    .map(serde_json::from_str::<Value>)
+                    .transpose()
+                    .map_err(|e| format!("Invalid metadata JSON: {}", e))?;
+                PluginMetadata::from_value(metadata.as_ref())
+            })
+            .as_ref()
+            .map_err(|err| {
+                AppError::Execution(format!(
+                    "Plugin {} has invalid metadata: {}",
+                    self.plugin_id, err
+                ))
+            })
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        self.tags
+            .as_deref()
+            .and_then(|raw| serde_json::from_str(raw).ok())
+            .unwrap_or_default()
+    }
+
+    /// Tags compare exactly but case-insensitively.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags()
+            .iter()
+            .any(|candidate| candidate.to_lowercase() == tag.to_lowercase())
+    }
+}
+
+/// Typed view of the `metadata` object in metadata.json.
+///
+/// Root-level manifest fields (entry point, timeouts, tags, ...) have their own
+/// columns; every optional per-feature setting lives here instead. Keys not
+/// listed are left to plugin authors for custom data.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PluginMetadata {
+    /// Grouping label from `metadata.category`.
+    pub category: Option<String>,
+    /// Author-chosen position from `metadata.order`; lower sorts first.
+    pub order: Option<i64>,
+    /// Icon path relative to the plugin dir.
+    pub icon: Option<String>,
+    /// Also export each parameter as `ANTHILL_PARAM_<NAME>`.
+    pub param_env: bool,
+    pub param_delivery: ParamDelivery,
+    /// Extra env vars set for every run.
+    pub env: BTreeMap<String, String>,
+    /// Dotenv file relative to the plugin dir.
+    pub env_file: Option<String>,
+    pub commands: BTreeMap<String, PluginCommand>,
+    pub health_check: Option<PluginHealthCheck>,
+    pub rate_limit: Option<PluginRateLimit>,
+    pub priority: ProcessPriority,
+    /// Unset fields are unlimited.
+    pub resource_limits: ResourceLimits,
+    /// Modules imported by a warm-up run.
+    pub warm_imports: Vec<String>,
+}
+
+impl PluginMetadata {
+    /// Parses the known settings; errors name the offending `metadata.<field>`.
+    pub fn from_value(metadata: Option<&Value>) -> std::result::Result<Self, String> {
+        let Some(metadata) = metadata else {
+            return Ok(Self::default());
+        };
+        let Some(object) = metadata.as_object() else {
+            return Err("metadata must be a JSON object".to_string());
+        };
+        fn field<T: DeserializeOwned>(
+            object: &Map<String, Value>,
+            name: &str,
+        ) -> std::result::Result<Option<T>, String> {
+            object
+                .get(name)
+                .map(|value| {
+                    serde_json::from_value(value.clone())
+                        .map_err(|e| format!("Invalid metadata.{}: {}", name, e))
+                })
+                .transpose()
+        }
+        Ok(Self {
+            category: field(object, "category")?,
+            order: field(object, "order")?,
+            icon: field(object, "icon")?,
+            param_env: field(object, "param_env")?.unwrap_or_default(),
+            param_delivery: field(object, "param_delivery")?.unwrap_or_default(),
+            env: field(object, "env")?.unwrap_or_default(),
+            env_file: field(object, "env_file")?,
+            commands: field(object, "commands")?.unwrap_or_default(),
+            health_check: field(object, "health_check")?,
+            rate_limit: field(object, "rate_limit")?,
+            priority: field(object, "priority")?.unwrap_or_default(),
+            resource_limits: field(object, "resource_limits")?.unwrap_or_default(),
+            warm_imports: field(object, "warm_imports")?.unwrap_or_default(),
+        })
+    }
+}
+
+/// How resolved parameters reach the plugin process.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamDelivery {
+    /// JSON in `ANTHILL_PLUGIN_PARAMS`.
+    #[default]
+    Env,
+    /// The same JSON written to stdin, for inputs that may exceed env limits.
+    Stdin,
+}
+
+/// Alternative entry point run when a request selects the command by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginCommand {
+    pub entry_point: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Runs `script` (relative to the plugin dir) or the entry point with `args`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginHealthCheck {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Only lowering is supported, since raising priority needs extra privileges.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessPriority {
+    Low,
+    BelowNormal,
+    #[default]
+    Normal,
+}
+
+/// Per-process caps enforced with rlimits (or container flags).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceLimits {
+    /// Address space cap; runtimes that reserve large virtual ranges need headroom.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cpu_secs: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Applies `ceiling` as the default for unset fields and the maximum for set ones.
+    pub fn within(self, ceiling: ResourceLimits) -> Self {
+        let cap = |value: Option<u64>, ceiling: Option<u64>| match (value, ceiling) {
+            (Some(value), Some(ceiling)) => Some(value.min(ceiling)),
+            (value, ceiling) => value.or(ceiling),
+        };
+        Self {
+            max_memory_mb: cap(self.max_memory_mb, ceiling.max_memory_mb),
+            max_cpu_secs: cap(self.max_cpu_secs, ceiling.max_cpu_secs),
+        }
+    }
+}
+
+/// At most `max_executions` runs started within the last `window_secs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginRateLimit {
+    pub max_executions: u32,
+    #[serde(default = "default_rate_limit_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_rate_limit_window_secs() -> u64 {
+    60
+}
+
+pub fn is_env_var_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq)]
@@ -28,6 +250,27 @@ pub struct Plugin {
 pub enum PluginType {
     Python = 0,
     JavaScript = 1,
+    Shell = 2,
+}
+
+impl PluginType {
+    /// Parses the `plugin_type` names accepted in metadata.json.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "python" => Some(Self::Python),
+            "javascript" | "js" => Some(Self::JavaScript),
+            "shell" | "bash" => Some(Self::Shell),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Python => "python",
+            Self::JavaScript => "javascript",
+            Self::Shell => "shell",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -58,6 +301,17 @@ impl PluginParamType {
             Self::Json | Self::Select | Self::MultiSelect => true,
         }
     }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Self::Number | Self::Integer)
+    }
+
+    pub fn is_textual(&self) -> bool {
+        matches!(
+            self,
+            Self::String | Self::Date | Self::File | Self::Directory | Self::Textarea
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,11 +322,53 @@ pub struct PluginParameter {
     pub description: Option<String>,
     pub default: Option<Value>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub choices: Option<Vec<Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validation: Option<PluginParameterValidation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<bool>,
+    #[serde(default, flatten)]
+    pub extras: std::collections::BTreeMap<String, Value>,
+}
+
+impl PluginParameter {
+    pub fn is_required(&self) -> bool {
+        self.required.unwrap_or(self.default.is_none())
+    }
+
+    /// Secret values reach the plugin but are stored and returned as `"***"`.
+    pub fn is_secret(&self) -> bool {
+        self.secret == Some(true)
+    }
+
+    pub fn env_var_name(&self) -> Option<String> {
+        let name = self.name.as_str();
+        is_env_var_name(name).then(|| format!("ANTHILL_PARAM_{}", name.to_ascii_uppercase()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginParameterValidation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
     #[serde(default, flatten)]
     pub extras: std::collections::BTreeMap<String, Value>,
 }
 
+impl PluginParameterValidation {
+    pub fn pattern_regex(&self) -> Option<std::result::Result<regex::Regex, regex::Error>> {
+        self.pattern
+            .as_deref()
+            .map(|pattern| regex::Regex::new(&format!("^(?:{})$", pattern)))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginParameterGroup {
     pub id: String,
@@ -1,5 +1,5 @@
 use crate::error::{AppError, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 const BIN_DIR: &str = "bin";
 const PLUGINS_DIR: &str = "plugins";
@@ -7,8 +7,75 @@ const WORK_DIR: &str = "work_dir";
 const CONF_DIR: &str = "conf";
 const DATA_DIR: &str = "data";
 const PYTHON_ENVS_DIR: &str = "python_envs";
+const RECYCLE_BIN_DIR: &str = "recycle_bin";
+const PLUGIN_STATE_DIR: &str = "plugin_state";
+const CONTAINER_DEPS_DIR: &str = "container_deps";
 const HOME_ENV: &str = "ANTHILL_HOME";
 
+/// Where the server keeps plugins, work dirs and data. A directory left as
+/// `None` sits at its default place under the install root.
+#[derive(Debug, Clone, Default)]
+pub struct Dirs {
+    pub plugins: Option<PathBuf>,
+    pub work: Option<PathBuf>,
+    pub data: Option<PathBuf>,
+    pub python_envs: Option<PathBuf>,
+}
+
+impl Dirs {
+    /// Every directory under `root`, so tests never touch the real install root.
+    #[cfg(test)]
+    pub(crate) fn under(root: &Path) -> Self {
+        Self {
+            plugins: Some(root.join(PLUGINS_DIR)),
+            work: Some(root.join(WORK_DIR)),
+            data: Some(root.join(DATA_DIR)),
+            python_envs: Some(root.join(PYTHON_ENVS_DIR)),
+        }
+    }
+
+    pub fn plugins_dir(&self) -> Result<PathBuf> {
+        match &self.plugins {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(install_root()?.join(PLUGINS_DIR)),
+        }
+    }
+
+    pub fn work_dir(&self) -> Result<PathBuf> {
+        match &self.work {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(install_root()?.join(WORK_DIR)),
+        }
+    }
+
+    pub fn data_dir(&self) -> Result<PathBuf> {
+        match &self.data {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(install_root()?.join(DATA_DIR)),
+        }
+    }
+
+    pub fn python_envs_dir(&self) -> Result<PathBuf> {
+        match &self.python_envs {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(self.data_dir()?.join(PYTHON_ENVS_DIR)),
+        }
+    }
+
+    pub fn recycle_bin_dir(&self) -> Result<PathBuf> {
+        Ok(self.data_dir()?.join(RECYCLE_BIN_DIR))
+    }
+
+    pub fn plugin_state_dir(&self) -> Result<PathBuf> {
+        Ok(self.data_dir()?.join(PLUGIN_STATE_DIR))
+    }
+
+    /// Python packages installed for the container image, one dir per plugin.
+    pub fn container_deps_dir(&self) -> Result<PathBuf> {
+        Ok(self.data_dir()?.join(CONTAINER_DEPS_DIR))
+    }
+}
+
 pub fn install_root() -> Result<PathBuf> {
     if let Ok(home) = std::env::var(HOME_ENV) {
         if home.trim().is_empty() {
@@ -34,22 +101,31 @@ pub fn install_root() -> Result<PathBuf> {
     Ok(exe_dir.to_path_buf())
 }
 
-pub fn plugins_dir() -> Result<PathBuf> {
-    Ok(install_root()?.join(PLUGINS_DIR))
-}
-
-pub fn work_dir() -> Result<PathBuf> {
-    Ok(install_root()?.join(WORK_DIR))
-}
-
 pub fn conf_dir() -> Result<PathBuf> {
     Ok(install_root()?.join(CONF_DIR))
 }
 
-pub fn data_dir() -> Result<PathBuf> {
-    Ok(install_root()?.join(DATA_DIR))
+pub fn dir_size(path: &Path) -> Result<u64> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err.into()),
+    };
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut total = 0;
+    for entry in std::fs::read_dir(path)? {
+        total += dir_size(&entry?.path())?;
+    }
+    Ok(total)
 }
 
-pub fn python_envs_dir() -> Result<PathBuf> {
-    Ok(data_dir()?.join(PYTHON_ENVS_DIR))
+pub fn count_entries(path: &Path) -> Result<u64> {
+    match std::fs::read_dir(path) {
+        Ok(entries) => Ok(entries.count() as u64),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(err.into()),
+    }
 }
@@ -0,0 +1,64 @@
+use crate::error::Result;
+use crate::models::AuditEntry;
+use crate::repository::DbPool;
+
+#[derive(Clone)]
+pub struct AuditRepository {
+    pool: DbPool,
+}
+
+impl AuditRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn record(&self, entry: &AuditEntry) -> Result<()> {
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "INSERT INTO audit_log (id, action, plugin_id, actor, timestamp, detail) VALUES ($1, $2, $3, $4, $5, $6)",
+            )
+            .bind(&entry.id)
+            .bind(&entry.action)
+            .bind(&entry.plugin_id)
+            .bind(&entry.actor)
+            .bind(entry.timestamp)
+            .bind(&entry.detail)
+            .execute(pool)
+            .await
+            .map(|_| ())
+        })?;
+
+        Ok(())
+    }
+
+    /// Entries within `[since, until]` (epoch millis), newest first.
+    pub async fn list(
+        &self,
+        since: Option<i64>,
+        until: Option<i64>,
+        plugin_id: Option<&str>,
+        action: Option<&str>,
+    ) -> Result<Vec<AuditEntry>> {
+        let entries = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, AuditEntry>(
+                r#"
+                SELECT id, action, plugin_id, actor, timestamp, detail
+                FROM audit_log
+                WHERE ($1 IS NULL OR timestamp >= $1)
+                  AND ($2 IS NULL OR timestamp <= $2)
+                  AND ($3 IS NULL OR plugin_id = $3)
+                  AND ($4 IS NULL OR action = $4)
+                ORDER BY timestamp DESC
+                "#,
+            )
+            .bind(since)
+            .bind(until)
+            .bind(plugin_id)
+            .bind(action)
+            .fetch_all(pool)
+            .await
+        })?;
+
+        Ok(entries)
+    }
+}
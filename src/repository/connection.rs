@@ -1,8 +1,41 @@
 use crate::repository::DbPool;
 use anyhow::Result;
-use sqlx::Row;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{PgPool, Row, SqlitePool};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Pool tuning applied by [`establish_connection`].
+#[derive(Debug, Clone, Copy)]
+pub struct PoolSettings {
+    pub max_connections: u32,
+    /// How long a SQLite connection waits on a locked database before failing.
+    pub busy_timeout: Duration,
+    /// Use SQLite's write-ahead log so readers do not block the writer.
+    pub wal: bool,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            busy_timeout: Duration::from_secs(5),
+            wal: true,
+        }
+    }
+}
+
+pub async fn establish_connection(database_url: &str, settings: PoolSettings) -> Result<DbPool> {
+    if is_postgres_url(database_url) {
+        let pool = PgPoolOptions::new()
+            .max_connections(settings.max_connections)
+            .connect(database_url)
+            .await?;
+        migrate_postgres(&pool).await?;
+        return Ok(DbPool::Postgres(pool));
+    }
 
-pub async fn establish_connection(database_url: &str) -> Result<DbPool> {
     // Ensure the database URL has the correct format
     let db_url = if database_url.starts_with("sqlite:") {
         database_url.to_string()
@@ -10,10 +43,81 @@ pub async fn establish_connection(database_url: &str) -> Result<DbPool> {
         format!("sqlite:{}", database_url)
     };
 
-    // Create connection with create_if_missing option
-    let connection_string = format!("{}?mode=rwc", db_url);
-    let pool = sqlx::SqlitePool::connect(&connection_string).await?;
+    // Create if missing; SQLite only enforces foreign keys (and the executions
+    // cascade) when each connection turns them on
+    let mut options = SqliteConnectOptions::from_str(&db_url)?
+        .create_if_missing(true)
+        .foreign_keys(true)
+        .busy_timeout(settings.busy_timeout);
+    let pool_options = SqlitePoolOptions::new().max_connections(settings.max_connections);
+    let pool = if is_memory_url(&db_url) {
+        // Every pooled connection shares one in-memory database, which is
+        // dropped once the last connection closes, so never let the pool empty
+        pool_options
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(options)
+            .await?
+    } else {
+        if settings.wal {
+            options = options.journal_mode(SqliteJournalMode::Wal);
+        }
+        pool_options.connect_with(options).await?
+    };
+    migrate_sqlite(&pool).await?;
+
+    Ok(DbPool::Sqlite(pool))
+}
+
+pub fn is_postgres_url(database_url: &str) -> bool {
+    database_url.starts_with("postgres://") || database_url.starts_with("postgresql://")
+}
+
+/// Masks passwords in database URLs for logs and APIs, both the
+/// `user:password@` part and query parameters such as `?password=`.
+pub fn redact_database_url(url: &str) -> String {
+    let (base, query) = match url.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (url, None),
+    };
+    let mut redacted = redact_authority_password(base);
+    if let Some(query) = query {
+        let params = query
+            .split('&')
+            .map(|param| match param.split_once('=') {
+                Some((key, _)) if key.to_ascii_lowercase().contains("password") => {
+                    format!("{}=***", key)
+                }
+                _ => param.to_string(),
+            })
+            .collect::<Vec<_>>();
+        redacted.push('?');
+        redacted.push_str(&params.join("&"));
+    }
+    redacted
+}
+
+fn redact_authority_password(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    let Some(at) = rest[..authority_end].rfind('@') else {
+        return url.to_string();
+    };
+    match rest[..at].split_once(':') {
+        Some((user, _)) => format!("{}://{}:***{}", scheme, user, &rest[at..]),
+        None => url.to_string(),
+    }
+}
+
+/// `sqlite::memory:` or a `mode=memory` URI, e.g. for tests.
+pub fn is_memory_url(db_url: &str) -> bool {
+    db_url.contains(":memory:") || db_url.contains("mode=memory")
+}
 
+async fn migrate_sqlite(pool: &SqlitePool) -> Result<()> {
     // Run migrations
     sqlx::query(
         r#"
@@ -36,7 +140,15 @@ pub async fn establish_connection(database_url: &str) -> Result<DbPool> {
             parameter_groups TEXT,
             metadata TEXT,
             python_venv_path TEXT,
-            python_dependencies TEXT
+            python_dependencies TEXT,
+            deleted_at INTEGER,
+            default_timeout_ms INTEGER,
+            max_timeout_ms INTEGER,
+            disabled_reason TEXT,
+            resolved_dependencies TEXT,
+            tags TEXT,
+            history_only BOOLEAN NOT NULL DEFAULT 0,
+            python_version TEXT
         );
 
         -- 执行记录表
@@ -54,27 +166,173 @@ pub async fn establish_connection(database_url: &str) -> Result<DbPool> {
             expires_at INTEGER,
             started_at INTEGER NOT NULL,
             finished_at INTEGER,
+            applied_at INTEGER,
+            prepare_duration_ms INTEGER,
+            preview_stdout TEXT,
+            preview_stderr TEXT,
+            idempotency_key TEXT,
+            dry_run BOOLEAN NOT NULL DEFAULT 0,
+            params TEXT,
+            exit_signal INTEGER,
+            command TEXT,
+            callback_url TEXT,
             FOREIGN KEY (plugin_id) REFERENCES plugins(plugin_id) ON DELETE CASCADE
         );
 
+        -- 插件版本历史表
+        CREATE TABLE IF NOT EXISTS plugin_versions (
+            plugin_id TEXT NOT NULL,
+            version TEXT NOT NULL,
+            installed_at INTEGER NOT NULL,
+            package_sha256 TEXT NOT NULL
+        );
+
+        -- 审计日志表（只追加）
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id TEXT PRIMARY KEY,
+            action TEXT NOT NULL,
+            plugin_id TEXT NOT NULL,
+            actor TEXT,
+            timestamp INTEGER NOT NULL,
+            detail TEXT
+        );
+
         CREATE INDEX IF NOT EXISTS idx_executions_plugin_id ON executions(plugin_id);
+        CREATE INDEX IF NOT EXISTS idx_plugin_versions_plugin_id ON plugin_versions(plugin_id);
         CREATE INDEX IF NOT EXISTS idx_plugins_enabled ON plugins(enabled);
         CREATE INDEX IF NOT EXISTS idx_plugins_plugin_id ON plugins(plugin_id);
         CREATE INDEX IF NOT EXISTS idx_plugins_name ON plugins(name);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log(timestamp);
         "#,
     )
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
-    ensure_min_anthill_version_column(&pool).await?;
-    ensure_parameter_groups_column(&pool).await?;
-    ensure_metadata_column(&pool).await?;
-    ensure_execution_new_columns(&pool).await?;
+    ensure_min_anthill_version_column(pool).await?;
+    ensure_parameter_groups_column(pool).await?;
+    ensure_metadata_column(pool).await?;
+    ensure_deleted_at_column(pool).await?;
+    ensure_timeout_columns(pool).await?;
+    ensure_disabled_reason_column(pool).await?;
+    ensure_resolved_dependencies_column(pool).await?;
+    ensure_python_version_column(pool).await?;
+    ensure_tags_column(pool).await?;
+    ensure_history_only_column(pool).await?;
+    ensure_execution_new_columns(pool).await?;
 
-    Ok(pool)
+    Ok(())
 }
 
-async fn ensure_min_anthill_version_column(pool: &DbPool) -> Result<()> {
+async fn migrate_postgres(pool: &PgPool) -> Result<()> {
+    let statements = [
+        r#"
+        CREATE TABLE IF NOT EXISTS plugins (
+            id TEXT PRIMARY KEY,
+            plugin_id TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
+            version TEXT NOT NULL,
+            min_anthill_version TEXT,
+            plugin_type INTEGER NOT NULL,
+            description TEXT,
+            author TEXT,
+            plugin_path TEXT NOT NULL,
+            entry_point TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at BIGINT NOT NULL,
+            updated_at BIGINT NOT NULL,
+            parameters TEXT,
+            parameter_groups TEXT,
+            metadata TEXT,
+            python_venv_path TEXT,
+            python_dependencies TEXT,
+            deleted_at BIGINT,
+            default_timeout_ms BIGINT,
+            max_timeout_ms BIGINT,
+            disabled_reason TEXT,
+            resolved_dependencies TEXT,
+            tags TEXT,
+            history_only BOOLEAN NOT NULL DEFAULT FALSE,
+            python_version TEXT
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS executions (
+            id TEXT PRIMARY KEY,
+            plugin_id TEXT NOT NULL,
+            phase INTEGER NOT NULL DEFAULT 0,
+            status INTEGER NOT NULL,
+            pid INTEGER,
+            exit_code INTEGER,
+            stdout TEXT,
+            stderr TEXT,
+            preview_payload TEXT,
+            confirm_token TEXT,
+            expires_at BIGINT,
+            started_at BIGINT NOT NULL,
+            finished_at BIGINT,
+            applied_at BIGINT,
+            prepare_duration_ms BIGINT,
+            preview_stdout TEXT,
+            preview_stderr TEXT,
+            idempotency_key TEXT,
+            dry_run BOOLEAN NOT NULL DEFAULT FALSE,
+            params TEXT,
+            exit_signal INTEGER,
+            command TEXT,
+            callback_url TEXT,
+            FOREIGN KEY (plugin_id) REFERENCES plugins(plugin_id) ON DELETE CASCADE
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS plugin_versions (
+            plugin_id TEXT NOT NULL,
+            version TEXT NOT NULL,
+            installed_at BIGINT NOT NULL,
+            package_sha256 TEXT NOT NULL
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id TEXT PRIMARY KEY,
+            action TEXT NOT NULL,
+            plugin_id TEXT NOT NULL,
+            actor TEXT,
+            timestamp BIGINT NOT NULL,
+            detail TEXT
+        )
+        "#,
+        "ALTER TABLE plugins ADD COLUMN IF NOT EXISTS default_timeout_ms BIGINT",
+        "ALTER TABLE plugins ADD COLUMN IF NOT EXISTS max_timeout_ms BIGINT",
+        "ALTER TABLE plugins ADD COLUMN IF NOT EXISTS disabled_reason TEXT",
+        "ALTER TABLE plugins ADD COLUMN IF NOT EXISTS resolved_dependencies TEXT",
+        "ALTER TABLE plugins ADD COLUMN IF NOT EXISTS tags TEXT",
+        "ALTER TABLE plugins ADD COLUMN IF NOT EXISTS history_only BOOLEAN NOT NULL DEFAULT FALSE",
+        "ALTER TABLE plugins ADD COLUMN IF NOT EXISTS python_version TEXT",
+        "ALTER TABLE executions ADD COLUMN IF NOT EXISTS preview_stdout TEXT",
+        "ALTER TABLE executions ADD COLUMN IF NOT EXISTS preview_stderr TEXT",
+        "ALTER TABLE executions ADD COLUMN IF NOT EXISTS idempotency_key TEXT",
+        "ALTER TABLE executions ADD COLUMN IF NOT EXISTS dry_run BOOLEAN NOT NULL DEFAULT FALSE",
+        "ALTER TABLE executions ADD COLUMN IF NOT EXISTS params TEXT",
+        "ALTER TABLE executions ADD COLUMN IF NOT EXISTS exit_signal INTEGER",
+        "ALTER TABLE executions ADD COLUMN IF NOT EXISTS command TEXT",
+        "ALTER TABLE executions ADD COLUMN IF NOT EXISTS callback_url TEXT",
+        "CREATE INDEX IF NOT EXISTS idx_executions_plugin_id ON executions(plugin_id)",
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_executions_idempotency_key ON executions(idempotency_key)",
+        "CREATE INDEX IF NOT EXISTS idx_plugin_versions_plugin_id ON plugin_versions(plugin_id)",
+        "CREATE INDEX IF NOT EXISTS idx_plugins_enabled ON plugins(enabled)",
+        "CREATE INDEX IF NOT EXISTS idx_plugins_plugin_id ON plugins(plugin_id)",
+        "CREATE INDEX IF NOT EXISTS idx_plugins_name ON plugins(name)",
+        "CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log(timestamp)",
+    ];
+
+    for statement in statements {
+        sqlx::query(statement).execute(pool).await?;
+    }
+
+    Ok(())
+}
+
+async fn ensure_min_anthill_version_column(pool: &SqlitePool) -> Result<()> {
     let columns = sqlx::query("PRAGMA table_info(plugins)")
         .fetch_all(pool)
         .await?;
@@ -89,7 +347,7 @@ async fn ensure_min_anthill_version_column(pool: &DbPool) -> Result<()> {
     Ok(())
 }
 
-async fn ensure_execution_new_columns(pool: &DbPool) -> Result<()> {
+async fn ensure_execution_new_columns(pool: &SqlitePool) -> Result<()> {
     let columns = sqlx::query("PRAGMA table_info(executions)")
         .fetch_all(pool)
         .await?;
@@ -98,6 +356,16 @@ async fn ensure_execution_new_columns(pool: &DbPool) -> Result<()> {
     let mut has_preview_payload = false;
     let mut has_confirm_token = false;
     let mut has_expires_at = false;
+    let mut has_applied_at = false;
+    let mut has_prepare_duration_ms = false;
+    let mut has_preview_stdout = false;
+    let mut has_preview_stderr = false;
+    let mut has_idempotency_key = false;
+    let mut has_dry_run = false;
+    let mut has_params = false;
+    let mut has_exit_signal = false;
+    let mut has_command = false;
+    let mut has_callback_url = false;
 
     for row in &columns {
         let name: String = row.get("name");
@@ -106,6 +374,16 @@ async fn ensure_execution_new_columns(pool: &DbPool) -> Result<()> {
             "preview_payload" => has_preview_payload = true,
             "confirm_token" => has_confirm_token = true,
             "expires_at" => has_expires_at = true,
+            "applied_at" => has_applied_at = true,
+            "prepare_duration_ms" => has_prepare_duration_ms = true,
+            "preview_stdout" => has_preview_stdout = true,
+            "preview_stderr" => has_preview_stderr = true,
+            "idempotency_key" => has_idempotency_key = true,
+            "dry_run" => has_dry_run = true,
+            "params" => has_params = true,
+            "exit_signal" => has_exit_signal = true,
+            "command" => has_command = true,
+            "callback_url" => has_callback_url = true,
             _ => {}
         }
     }
@@ -130,11 +408,66 @@ async fn ensure_execution_new_columns(pool: &DbPool) -> Result<()> {
             .execute(pool)
             .await?;
     }
+    if !has_applied_at {
+        sqlx::query("ALTER TABLE executions ADD COLUMN applied_at INTEGER")
+            .execute(pool)
+            .await?;
+    }
+    if !has_prepare_duration_ms {
+        sqlx::query("ALTER TABLE executions ADD COLUMN prepare_duration_ms INTEGER")
+            .execute(pool)
+            .await?;
+    }
+    if !has_preview_stdout {
+        sqlx::query("ALTER TABLE executions ADD COLUMN preview_stdout TEXT")
+            .execute(pool)
+            .await?;
+    }
+    if !has_preview_stderr {
+        sqlx::query("ALTER TABLE executions ADD COLUMN preview_stderr TEXT")
+            .execute(pool)
+            .await?;
+    }
+    if !has_idempotency_key {
+        sqlx::query("ALTER TABLE executions ADD COLUMN idempotency_key TEXT")
+            .execute(pool)
+            .await?;
+    }
+    if !has_dry_run {
+        sqlx::query("ALTER TABLE executions ADD COLUMN dry_run BOOLEAN NOT NULL DEFAULT 0")
+            .execute(pool)
+            .await?;
+    }
+    if !has_params {
+        sqlx::query("ALTER TABLE executions ADD COLUMN params TEXT")
+            .execute(pool)
+            .await?;
+    }
+    if !has_exit_signal {
+        sqlx::query("ALTER TABLE executions ADD COLUMN exit_signal INTEGER")
+            .execute(pool)
+            .await?;
+    }
+    if !has_command {
+        sqlx::query("ALTER TABLE executions ADD COLUMN command TEXT")
+            .execute(pool)
+            .await?;
+    }
+    if !has_callback_url {
+        sqlx::query("ALTER TABLE executions ADD COLUMN callback_url TEXT")
+            .execute(pool)
+            .await?;
+    }
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_executions_idempotency_key ON executions(idempotency_key)",
+    )
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
 
-async fn ensure_parameter_groups_column(pool: &DbPool) -> Result<()> {
+async fn ensure_parameter_groups_column(pool: &SqlitePool) -> Result<()> {
     let columns = sqlx::query("PRAGMA table_info(plugins)")
         .fetch_all(pool)
         .await?;
@@ -149,7 +482,7 @@ async fn ensure_parameter_groups_column(pool: &DbPool) -> Result<()> {
     Ok(())
 }
 
-async fn ensure_metadata_column(pool: &DbPool) -> Result<()> {
+async fn ensure_metadata_column(pool: &SqlitePool) -> Result<()> {
     let columns = sqlx::query("PRAGMA table_info(plugins)")
         .fetch_all(pool)
         .await?;
@@ -163,3 +496,208 @@ async fn ensure_metadata_column(pool: &DbPool) -> Result<()> {
     }
     Ok(())
 }
+
+async fn ensure_deleted_at_column(pool: &SqlitePool) -> Result<()> {
+    let columns = sqlx::query("PRAGMA table_info(plugins)")
+        .fetch_all(pool)
+        .await?;
+    let has_column = columns
+        .iter()
+        .any(|row| row.get::<String, _>("name") == "deleted_at");
+    if !has_column {
+        sqlx::query("ALTER TABLE plugins ADD COLUMN deleted_at INTEGER")
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn ensure_disabled_reason_column(pool: &SqlitePool) -> Result<()> {
+    let columns = sqlx::query("PRAGMA table_info(plugins)")
+        .fetch_all(pool)
+        .await?;
+    let has_column = columns
+        .iter()
+        .any(|row| row.get::<String, _>("name") == "disabled_reason");
+    if !has_column {
+        sqlx::query("ALTER TABLE plugins ADD COLUMN disabled_reason TEXT")
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn ensure_resolved_dependencies_column(pool: &SqlitePool) -> Result<()> {
+    let columns = sqlx::query("PRAGMA table_info(plugins)")
+        .fetch_all(pool)
+        .await?;
+    let has_column = columns
+        .iter()
+        .any(|row| row.get::<String, _>("name") == "resolved_dependencies");
+    if !has_column {
+        sqlx::query("ALTER TABLE plugins ADD COLUMN resolved_dependencies TEXT")
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn ensure_python_version_column(pool: &SqlitePool) -> Result<()> {
+    let columns = sqlx::query("PRAGMA table_info(plugins)")
+        .fetch_all(pool)
+        .await?;
+    let has_column = columns
+        .iter()
+        .any(|row| row.get::<String, _>("name") == "python_version");
+    if !has_column {
+        sqlx::query("ALTER TABLE plugins ADD COLUMN python_version TEXT")
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn ensure_tags_column(pool: &SqlitePool) -> Result<()> {
+    let columns = sqlx::query("PRAGMA table_info(plugins)")
+        .fetch_all(pool)
+        .await?;
+    let has_column = columns
+        .iter()
+        .any(|row| row.get::<String, _>("name") == "tags");
+    if !has_column {
+        sqlx::query("ALTER TABLE plugins ADD COLUMN tags TEXT")
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn ensure_history_only_column(pool: &SqlitePool) -> Result<()> {
+    let columns = sqlx::query("PRAGMA table_info(plugins)")
+        .fetch_all(pool)
+        .await?;
+    let has_column = columns
+        .iter()
+        .any(|row| row.get::<String, _>("name") == "history_only");
+    if !has_column {
+        sqlx::query("ALTER TABLE plugins ADD COLUMN history_only BOOLEAN NOT NULL DEFAULT 0")
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn ensure_timeout_columns(pool: &SqlitePool) -> Result<()> {
+    let columns = sqlx::query("PRAGMA table_info(plugins)")
+        .fetch_all(pool)
+        .await?;
+    for column in ["default_timeout_ms", "max_timeout_ms"] {
+        let has_column = columns
+            .iter()
+            .any(|row| row.get::<String, _>("name") == column);
+        if !has_column {
+            sqlx::query(&format!(
+                "ALTER TABLE plugins ADD COLUMN {} INTEGER",
+                column
+            ))
+            .execute(pool)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ExecutionPhase, ExecutionStatus};
+    use crate::repository::{ExecutionRepository, NewExecution};
+
+    #[test]
+    fn redact_database_url_masks_only_the_password() {
+        assert_eq!(
+            redact_database_url("postgres://anthill:s3cret@db:5432/anthill"),
+            "postgres://anthill:***@db:5432/anthill"
+        );
+        assert_eq!(
+            redact_database_url("postgres://anthill@db/anthill"),
+            "postgres://anthill@db/anthill"
+        );
+        assert_eq!(
+            redact_database_url("sqlite:/var/lib/anthill.db"),
+            "sqlite:/var/lib/anthill.db"
+        );
+    }
+
+    #[test]
+    fn redact_database_url_masks_query_passwords() {
+        assert_eq!(
+            redact_database_url(
+                "postgres://db/anthill?user=anthill&password=s3cret&sslpassword=k3y&sslmode=require"
+            ),
+            "postgres://db/anthill?user=anthill&password=***&sslpassword=***&sslmode=require"
+        );
+        assert_eq!(
+            redact_database_url("postgres://a:b@db/anthill?PASSWORD=c"),
+            "postgres://a:***@db/anthill?PASSWORD=***"
+        );
+    }
+
+    #[test]
+    fn postgres_urls_are_detected() {
+        assert!(is_postgres_url("postgres://db/anthill"));
+        assert!(is_postgres_url("postgresql://db/anthill"));
+        assert!(!is_postgres_url("sqlite:anthill.db"));
+    }
+
+    /// Runs against a real server only when `ANTHILL_TEST_POSTGRES_URL` is set.
+    #[tokio::test]
+    async fn postgres_migrations_are_idempotent_and_usable() {
+        let Ok(url) = std::env::var("ANTHILL_TEST_POSTGRES_URL") else {
+            eprintln!("skipped: ANTHILL_TEST_POSTGRES_URL is not set");
+            return;
+        };
+        establish_connection(&url, PoolSettings::default())
+            .await
+            .unwrap();
+        let pool = establish_connection(&url, PoolSettings::default())
+            .await
+            .unwrap();
+        let DbPool::Postgres(pg) = &pool else {
+            panic!("expected a postgres pool");
+        };
+        let plugin_id = format!("pg-test-{}", uuid::Uuid::new_v4());
+        sqlx::query(
+            "INSERT INTO plugins (id, plugin_id, name, version, plugin_type, plugin_path, entry_point, created_at, updated_at) VALUES ($1, $1, 'pg', '1.0.0', 2, '/tmp/pg', 'main.sh', 0, 0)",
+        )
+        .bind(&plugin_id)
+        .execute(pg)
+        .await
+        .unwrap();
+
+        let repo = ExecutionRepository::new(pool.clone());
+        let (execution, created) = repo
+            .create_with_phase(NewExecution {
+                plugin_id: plugin_id.clone(),
+                phase: ExecutionPhase::Apply,
+                id: None,
+                idempotency_key: None,
+                dry_run: false,
+                params: None,
+                command: None,
+                callback_url: None,
+                rate_limit: None,
+            })
+            .await
+            .unwrap();
+        assert!(created);
+        let stored = repo.get(&execution.id).await.unwrap();
+        assert_eq!(stored.status, ExecutionStatus::Pending);
+
+        sqlx::query("DELETE FROM plugins WHERE plugin_id = $1")
+            .bind(&plugin_id)
+            .execute(pg)
+            .await
+            .unwrap();
+    }
+}
@@ -1,29 +1,68 @@
 use crate::error::{AppError, Result};
-use crate::models::{Execution, ExecutionPhase, ExecutionStatus};
+use crate::models::{Execution, ExecutionPhase, ExecutionStatus, PluginRateLimit};
 use crate::repository::DbPool;
 use chrono::Utc;
+use tokio::sync::broadcast;
+
+const STATUS_EVENT_CAPACITY: usize = 256;
+
+/// Fields of a new pending execution row.
+#[derive(Debug, Clone)]
+pub struct NewExecution {
+    pub plugin_id: String,
+    pub phase: ExecutionPhase,
+    /// Generated when `None`.
+    pub id: Option<String>,
+    pub idempotency_key: Option<String>,
+    pub dry_run: bool,
+    pub params: Option<String>,
+    pub command: Option<String>,
+    pub callback_url: Option<String>,
+    /// Refuses the insert once the plugin started this many executions in the window.
+    pub rate_limit: Option<PluginRateLimit>,
+}
 
 #[derive(Clone)]
 pub struct ExecutionRepository {
     pool: DbPool,
+    status_events: broadcast::Sender<String>,
 }
 
 impl ExecutionRepository {
     pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+        let (status_events, _) = broadcast::channel(STATUS_EVENT_CAPACITY);
+        Self {
+            pool,
+            status_events,
+        }
     }
 
-    pub async fn create_with_phase(
-        &self,
-        plugin_id: &str,
-        phase: ExecutionPhase,
-    ) -> Result<Execution> {
-        let id = uuid::Uuid::new_v4().to_string();
+    pub fn subscribe_status_changes(&self) -> broadcast::Receiver<String> {
+        self.status_events.subscribe()
+    }
+
+    fn publish_status_change(&self, id: &str) {
+        let _ = self.status_events.send(id.to_string());
+    }
+
+    pub async fn create_with_phase(&self, new: NewExecution) -> Result<(Execution, bool)> {
+        let NewExecution {
+            plugin_id,
+            phase,
+            id,
+            idempotency_key,
+            dry_run,
+            params,
+            command,
+            callback_url,
+            rate_limit,
+        } = new;
+        let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
         let now = Utc::now().timestamp_millis();
 
         let execution = Execution {
             id: id.clone(),
-            plugin_id: plugin_id.to_string(),
+            plugin_id,
             phase,
             status: ExecutionStatus::Pending,
             pid: None,
@@ -35,62 +74,226 @@ impl ExecutionRepository {
             expires_at: None,
             started_at: now,
             finished_at: None,
+            applied_at: None,
+            prepare_duration_ms: None,
+            preview_stdout: None,
+            preview_stderr: None,
+            idempotency_key,
+            dry_run,
+            params,
+            exit_signal: None,
+            command,
+            callback_url,
         };
 
-        sqlx::query(
-            r#"
-            INSERT INTO executions (id, plugin_id, phase, status, started_at, finished_at)
-            VALUES (?, ?, ?, ?, ?, NULL)
-            "#,
-        )
-        .bind(&execution.id)
-        .bind(&execution.plugin_id)
-        .bind(execution.phase as i32)
-        .bind(execution.status as i32)
-        .bind(execution.started_at)
-        .execute(&self.pool)
-        .await?;
+        let window_ms = rate_limit
+            .as_ref()
+            .map(|limit| i64::try_from(limit.window_secs.saturating_mul(1000)).unwrap_or(i64::MAX));
+        // Postgres reads the count under READ COMMITTED, so concurrent inserts for
+        // one plugin take turns; SQLite already serializes writing statements
+        let quota_lock = match (&self.pool, &rate_limit) {
+            (DbPool::Postgres(_), Some(_)) => Some("SELECT pg_advisory_xact_lock(hashtext($1))"),
+            _ => None,
+        };
+
+        let rows_affected = with_pool!(&self.pool, |pool| {
+            let mut tx = pool.begin().await?;
+            if let Some(sql) = quota_lock {
+                sqlx::query(sql)
+                    .bind(&execution.plugin_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            let rows_affected = sqlx::query(
+                r#"
+                INSERT INTO executions (id, plugin_id, phase, status, started_at, finished_at, idempotency_key, dry_run, params, command, callback_url)
+                SELECT $1, $2, $3, $4, $5, NULL, $6, $7, $8, $9, $10
+                WHERE $11 IS NULL
+                   OR (SELECT COUNT(*) FROM executions WHERE plugin_id = $2 AND started_at >= $5 - $12) < $11
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(&execution.id)
+            .bind(&execution.plugin_id)
+            .bind(execution.phase as i32)
+            .bind(execution.status as i32)
+            .bind(execution.started_at)
+            .bind(&execution.idempotency_key)
+            .bind(execution.dry_run)
+            .bind(&execution.params)
+            .bind(&execution.command)
+            .bind(&execution.callback_url)
+            .bind(rate_limit.as_ref().map(|limit| i64::from(limit.max_executions)))
+            .bind(window_ms)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(rows_affected)
+        })?;
+
+        if rows_affected == 0 {
+            if let Some(key) = &execution.idempotency_key
+                && let Some(existing) = self.find_by_idempotency_key(key).await?
+            {
+                if existing.plugin_id != execution.plugin_id || existing.phase != execution.phase {
+                    return Err(AppError::Execution(format!(
+                        "Idempotency key '{}' is already used by another request",
+                        key
+                    )));
+                }
+                return Ok((existing, false));
+            }
+            // Nothing holds the id, so the rate limit refused the insert
+            let existing = match (self.get(&execution.id).await, &rate_limit) {
+                (Err(AppError::ExecutionNotFound(_)), Some(limit)) => {
+                    return Err(AppError::RateLimited(format!(
+                        "Plugin '{}' allows at most {} executions per {}s",
+                        execution.plugin_id, limit.max_executions, limit.window_secs
+                    )));
+                }
+                (result, _) => result?,
+            };
+            if existing.plugin_id != execution.plugin_id || existing.phase != execution.phase {
+                return Err(AppError::Execution(format!(
+                    "Execution id '{}' is already used by another request",
+                    execution.id
+                )));
+            }
+            return Ok((existing, false));
+        }
+
+        Ok((execution, true))
+    }
 
+    pub async fn find_by_idempotency_key(&self, key: &str) -> Result<Option<Execution>> {
+        let execution = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, Execution>("SELECT * FROM executions WHERE idempotency_key = $1")
+                .bind(key)
+                .fetch_optional(pool)
+                .await
+        })?;
         Ok(execution)
     }
 
+    /// Executions with a live process: running or applying.
+    pub async fn count_running(&self) -> Result<u64> {
+        let count = with_pool!(&self.pool, |pool| {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM executions WHERE status IN ($1, $2)")
+                .bind(ExecutionStatus::Running as i32)
+                .bind(ExecutionStatus::Applying as i32)
+                .fetch_one(pool)
+                .await
+        })?;
+        Ok(count.max(0) as u64)
+    }
+
     pub async fn get(&self, id: &str) -> Result<Execution> {
-        let execution = sqlx::query_as::<_, Execution>("SELECT * FROM executions WHERE id = ?")
-            .bind(id)
-            .fetch_optional(&self.pool)
-            .await?
-            .ok_or_else(|| AppError::ExecutionNotFound(id.to_string()))?;
+        let execution = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, Execution>("SELECT * FROM executions WHERE id = $1")
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+        })?
+        .ok_or_else(|| AppError::ExecutionNotFound(id.to_string()))?;
 
         Ok(execution)
     }
 
     pub async fn list_by_plugin(&self, plugin_id: &str) -> Result<Vec<Execution>> {
-        let executions = sqlx::query_as::<_, Execution>(
-            "SELECT * FROM executions WHERE plugin_id = ? ORDER BY started_at DESC",
-        )
-        .bind(plugin_id)
-        .fetch_all(&self.pool)
-        .await?;
+        let executions = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, Execution>(
+                "SELECT * FROM executions WHERE plugin_id = $1 ORDER BY started_at DESC",
+            )
+            .bind(plugin_id)
+            .fetch_all(pool)
+            .await
+        })?;
 
         Ok(executions)
     }
 
     pub async fn list_all(&self) -> Result<Vec<Execution>> {
-        let executions =
+        let executions = with_pool!(&self.pool, |pool| {
             sqlx::query_as::<_, Execution>("SELECT * FROM executions ORDER BY started_at DESC")
-                .fetch_all(&self.pool)
-                .await?;
+                .fetch_all(pool)
+                .await
+        })?;
 
         Ok(executions)
     }
 
-    pub async fn update_pid(&self, id: &str, pid: u32) -> Result<()> {
-        sqlx::query("UPDATE executions SET pid = ?, status = ? WHERE id = ?")
-            .bind(pid as i32)
+    pub async fn list_by_status(
+        &self,
+        status: ExecutionStatus,
+        plugin_id: Option<&str>,
+    ) -> Result<Vec<Execution>> {
+        let executions = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, Execution>(
+                "SELECT * FROM executions WHERE status = $1 AND ($2 IS NULL OR plugin_id = $2) ORDER BY started_at DESC",
+            )
+            .bind(status as i32)
+            .bind(plugin_id)
+            .fetch_all(pool)
+            .await
+        })?;
+
+        Ok(executions)
+    }
+
+    /// Running, applying and pending executions, oldest first.
+    pub async fn list_active(&self) -> Result<Vec<Execution>> {
+        let executions = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, Execution>(
+                "SELECT * FROM executions WHERE status IN ($1, $2, $3) ORDER BY started_at ASC",
+            )
             .bind(ExecutionStatus::Running as i32)
-            .bind(id)
-            .execute(&self.pool)
-            .await?;
+            .bind(ExecutionStatus::Applying as i32)
+            .bind(ExecutionStatus::Pending as i32)
+            .fetch_all(pool)
+            .await
+        })?;
+
+        Ok(executions)
+    }
+
+    /// Fails executions left pending, running or applying by a previous
+    /// process, which can no longer finish them. Returns how many there were.
+    pub async fn fail_interrupted(&self) -> Result<u64> {
+        let rows_affected = with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                r#"
+                UPDATE executions
+                SET status = $1, finished_at = $2,
+                    stderr = COALESCE(stderr, '') || $3
+                WHERE status IN ($4, $5, $6)
+                "#,
+            )
+            .bind(ExecutionStatus::Failed as i32)
+            .bind(Utc::now().timestamp_millis())
+            .bind("Execution interrupted by a server restart\n")
+            .bind(ExecutionStatus::Pending as i32)
+            .bind(ExecutionStatus::Running as i32)
+            .bind(ExecutionStatus::Applying as i32)
+            .execute(pool)
+            .await
+            .map(|result| result.rows_affected())
+        })?;
+
+        Ok(rows_affected)
+    }
+
+    pub async fn update_pid(&self, id: &str, pid: u32) -> Result<()> {
+        with_pool!(&self.pool, |pool| {
+            sqlx::query("UPDATE executions SET pid = $1, status = $2 WHERE id = $3")
+                .bind(pid as i32)
+                .bind(ExecutionStatus::Running as i32)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })?;
+        self.publish_status_change(id);
 
         Ok(())
     }
@@ -101,23 +304,48 @@ impl ExecutionRepository {
         stdout: Option<String>,
         stderr: Option<String>,
         exit_code: Option<i32>,
+        exit_signal: Option<i32>,
         status: ExecutionStatus,
     ) -> Result<()> {
-        sqlx::query(
-            r#"
-            UPDATE executions
-            SET stdout = ?, stderr = ?, exit_code = ?, status = ?, finished_at = ?
-            WHERE id = ?
-            "#,
-        )
-        .bind(stdout)
-        .bind(stderr)
-        .bind(exit_code)
-        .bind(status as i32)
-        .bind(Utc::now().timestamp_millis())
-        .bind(id)
-        .execute(&self.pool)
-        .await?;
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                r#"
+                UPDATE executions
+                SET stdout = $1, stderr = $2, exit_code = $3, exit_signal = $4, status = $5, finished_at = $6
+                WHERE id = $7
+                "#,
+            )
+            .bind(stdout)
+            .bind(stderr)
+            .bind(exit_code)
+            .bind(exit_signal)
+            .bind(status as i32)
+            .bind(Utc::now().timestamp_millis())
+            .bind(id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+        })?;
+        self.publish_status_change(id);
+
+        Ok(())
+    }
+
+    pub async fn update_output(
+        &self,
+        id: &str,
+        stdout: Option<String>,
+        stderr: Option<String>,
+    ) -> Result<()> {
+        with_pool!(&self.pool, |pool| {
+            sqlx::query("UPDATE executions SET stdout = $1, stderr = $2 WHERE id = $3")
+                .bind(stdout)
+                .bind(stderr)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })?;
 
         Ok(())
     }
@@ -131,51 +359,329 @@ impl ExecutionRepository {
         confirm_token: String,
         expires_at: i64,
     ) -> Result<()> {
-        sqlx::query(
-            r#"
-            UPDATE executions
-            SET stdout = ?, stderr = ?, exit_code = ?, status = ?, finished_at = ?, preview_payload = ?, confirm_token = ?, expires_at = ?
-            WHERE id = ?
-            "#,
-        )
-        .bind(stdout.clone())
-        .bind(stderr)
-        .bind(exit_code)
-        .bind(ExecutionStatus::PreviewReady as i32)
-        .bind(Utc::now().timestamp_millis())
-        .bind(stdout)
-        .bind(confirm_token)
-        .bind(expires_at)
-        .bind(id)
-        .execute(&self.pool)
-        .await?;
+        let now = Utc::now().timestamp_millis();
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                r#"
+                UPDATE executions
+                SET stdout = $1, stderr = $2, exit_code = $3, status = $4, finished_at = $5, prepare_duration_ms = $6 - started_at, preview_payload = $7, confirm_token = $8, expires_at = $9
+                WHERE id = $10
+                "#,
+            )
+            .bind(stdout.clone())
+            .bind(stderr)
+            .bind(exit_code)
+            .bind(ExecutionStatus::PreviewReady as i32)
+            .bind(now)
+            .bind(now)
+            .bind(stdout)
+            .bind(confirm_token)
+            .bind(expires_at)
+            .bind(id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+        })?;
+        self.publish_status_change(id);
         Ok(())
     }
 
-    pub async fn begin_apply(&self, id: &str) -> Result<()> {
-        sqlx::query(
-            r#"
-            UPDATE executions
-            SET phase = ?, status = ?, pid = NULL, exit_code = NULL, stdout = NULL, stderr = NULL, started_at = ?, finished_at = NULL, confirm_token = NULL
-            WHERE id = ?
-            "#,
-        )
-        .bind(ExecutionPhase::Apply as i32)
-        .bind(ExecutionStatus::Pending as i32)
-        .bind(Utc::now().timestamp_millis())
-        .bind(id)
-        .execute(&self.pool)
-        .await?;
+    /// Claims an unexpired pending preview for applying.
+    ///
+    /// Like [`Self::revoke_preview`], this only matches `status = PreviewReady`,
+    /// so of a concurrent apply, discard or expiry sweep exactly one wins; the
+    /// others get a conflict or `false`.
+    pub async fn begin_apply(&self, id: &str, params: Option<&str>) -> Result<()> {
+        let now = Utc::now().timestamp_millis();
+        let rows_affected = with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                r#"
+                UPDATE executions
+                SET phase = $1, status = $2, pid = NULL, exit_code = NULL, exit_signal = NULL, preview_stdout = stdout, preview_stderr = stderr, stdout = NULL, stderr = NULL, applied_at = $3, finished_at = NULL, confirm_token = NULL, params = $4
+                WHERE id = $5 AND status = $6 AND (expires_at IS NULL OR expires_at > $3)
+                "#,
+            )
+            .bind(ExecutionPhase::Apply as i32)
+            .bind(ExecutionStatus::Pending as i32)
+            .bind(now)
+            .bind(params)
+            .bind(id)
+            .bind(ExecutionStatus::PreviewReady as i32)
+            .execute(pool)
+            .await
+            .map(|result| result.rows_affected())
+        })?;
+        if rows_affected == 0 {
+            return Err(AppError::Conflict(format!(
+                "Execution {} is no longer a pending preview",
+                id
+            )));
+        }
+        self.publish_status_change(id);
         Ok(())
     }
 
     pub async fn update_status(&self, id: &str, status: ExecutionStatus) -> Result<()> {
-        sqlx::query("UPDATE executions SET status = ? WHERE id = ?")
+        with_pool!(&self.pool, |pool| {
+            sqlx::query("UPDATE executions SET status = $1 WHERE id = $2")
+                .bind(status as i32)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })?;
+        self.publish_status_change(id);
+
+        Ok(())
+    }
+
+    /// Ends a pending preview with `status`; false if it was no longer pending,
+    /// e.g. because [`Self::begin_apply`] claimed it first.
+    pub async fn revoke_preview(&self, id: &str, status: ExecutionStatus) -> Result<bool> {
+        let rows_affected = with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "UPDATE executions SET status = $1, confirm_token = NULL WHERE id = $2 AND status = $3",
+            )
             .bind(status as i32)
             .bind(id)
-            .execute(&self.pool)
-            .await?;
+            .bind(ExecutionStatus::PreviewReady as i32)
+            .execute(pool)
+            .await
+            .map(|result| result.rows_affected())
+        })?;
 
-        Ok(())
+        if rows_affected > 0 {
+            self.publish_status_change(id);
+        }
+        Ok(rows_affected > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{PoolSettings, establish_connection};
+
+    async fn repo_with_plugin() -> ExecutionRepository {
+        let pool = establish_connection("sqlite::memory:", PoolSettings::default())
+            .await
+            .unwrap();
+        with_pool!(&pool, |conn| {
+            sqlx::query(
+                "INSERT INTO plugins (id, plugin_id, name, version, plugin_type, plugin_path, entry_point, created_at, updated_at) VALUES ('p1', 'demo', 'demo', '1.0.0', 2, '/tmp/demo', 'main.sh', 0, 0)",
+            )
+            .execute(conn)
+            .await
+            .map(|_| ())
+        })
+        .unwrap();
+        ExecutionRepository::new(pool)
+    }
+
+    async fn preview(repo: &ExecutionRepository, expires_at: i64) -> String {
+        let (execution, _) = repo
+            .create_with_phase(NewExecution {
+                plugin_id: "demo".to_string(),
+                phase: ExecutionPhase::Prepare,
+                id: None,
+                idempotency_key: None,
+                dry_run: false,
+                params: None,
+                command: None,
+                callback_url: None,
+                rate_limit: None,
+            })
+            .await
+            .unwrap();
+        repo.mark_preview_ready(
+            &execution.id,
+            None,
+            None,
+            Some(0),
+            "token".to_string(),
+            expires_at,
+        )
+        .await
+        .unwrap();
+        execution.id
+    }
+
+    fn new_execution(id: &str, phase: ExecutionPhase) -> NewExecution {
+        NewExecution {
+            plugin_id: "demo".to_string(),
+            phase,
+            id: Some(id.to_string()),
+            idempotency_key: None,
+            dry_run: false,
+            params: None,
+            command: None,
+            callback_url: None,
+            rate_limit: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn create_with_a_client_id_is_idempotent() {
+        let repo = repo_with_plugin().await;
+        let id = "6f1c1a52-52a8-4c5e-9a53-3f5d1d0b7e21";
+
+        let (first, created) = repo
+            .create_with_phase(new_execution(id, ExecutionPhase::Apply))
+            .await
+            .unwrap();
+        assert!(created);
+        assert_eq!(first.id, id);
+        let (second, created) = repo
+            .create_with_phase(new_execution(id, ExecutionPhase::Apply))
+            .await
+            .unwrap();
+        assert!(!created);
+        assert_eq!(second.id, id);
+        assert_eq!(second.started_at, first.started_at);
+        assert_eq!(repo.list_by_plugin("demo").await.unwrap().len(), 1);
+
+        assert!(matches!(
+            repo.create_with_phase(new_execution(id, ExecutionPhase::Prepare))
+                .await,
+            Err(AppError::Execution(_))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_creates_respect_the_rate_limit() {
+        let repo = repo_with_plugin().await;
+        let creates = (0..8).map(|_| {
+            let repo = repo.clone();
+            tokio::spawn(async move {
+                repo.create_with_phase(NewExecution {
+                    rate_limit: Some(PluginRateLimit {
+                        max_executions: 3,
+                        window_secs: 60,
+                    }),
+                    ..new_execution(&uuid::Uuid::new_v4().to_string(), ExecutionPhase::Apply)
+                })
+                .await
+            })
+        });
+
+        let mut created = 0;
+        for create in creates.collect::<Vec<_>>() {
+            match create.await.unwrap() {
+                Ok((_, true)) => created += 1,
+                Err(AppError::RateLimited(_)) => {}
+                other => panic!("unexpected result: {:?}", other.map(|(e, c)| (e.id, c))),
+            }
+        }
+        assert_eq!(created, 3);
+        assert_eq!(repo.list_by_plugin("demo").await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn fail_interrupted_only_touches_unfinished_executions() {
+        let repo = repo_with_plugin().await;
+        for (id, status) in [
+            ("pending", ExecutionStatus::Pending),
+            ("running", ExecutionStatus::Running),
+            ("applying", ExecutionStatus::Applying),
+            ("done", ExecutionStatus::Completed),
+        ] {
+            repo.create_with_phase(new_execution(id, ExecutionPhase::Apply))
+                .await
+                .unwrap();
+            repo.update_status(id, status).await.unwrap();
+        }
+        let preview = preview(&repo, in_one_hour()).await;
+
+        assert_eq!(repo.fail_interrupted().await.unwrap(), 3);
+        for id in ["pending", "running", "applying"] {
+            let execution = repo.get(id).await.unwrap();
+            assert_eq!(execution.status, ExecutionStatus::Failed);
+            assert!(execution.finished_at.is_some());
+            assert_eq!(
+                execution.stderr.as_deref(),
+                Some("Execution interrupted by a server restart\n")
+            );
+        }
+        assert_eq!(
+            repo.get("done").await.unwrap().status,
+            ExecutionStatus::Completed
+        );
+        assert_eq!(
+            repo.get(&preview).await.unwrap().status,
+            ExecutionStatus::PreviewReady
+        );
+    }
+
+    fn in_one_hour() -> i64 {
+        Utc::now().timestamp_millis() + 3_600_000
+    }
+
+    #[tokio::test]
+    async fn begin_apply_claims_a_preview_once() {
+        let repo = repo_with_plugin().await;
+        let id = preview(&repo, in_one_hour()).await;
+
+        repo.begin_apply(&id, None).await.unwrap();
+        assert!(matches!(
+            repo.begin_apply(&id, None).await,
+            Err(AppError::Conflict(_))
+        ));
+        assert!(
+            !repo
+                .revoke_preview(&id, ExecutionStatus::Stopped)
+                .await
+                .unwrap()
+        );
+        let execution = repo.get(&id).await.unwrap();
+        assert_eq!(execution.phase, ExecutionPhase::Apply);
+        assert_eq!(execution.status, ExecutionStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn begin_apply_loses_to_a_discard_or_sweep() {
+        let repo = repo_with_plugin().await;
+        let discarded = preview(&repo, in_one_hour()).await;
+        assert!(
+            repo.revoke_preview(&discarded, ExecutionStatus::Stopped)
+                .await
+                .unwrap()
+        );
+        assert!(matches!(
+            repo.begin_apply(&discarded, None).await,
+            Err(AppError::Conflict(_))
+        ));
+
+        let expired = preview(&repo, Utc::now().timestamp_millis() - 1).await;
+        assert!(matches!(
+            repo.begin_apply(&expired, None).await,
+            Err(AppError::Conflict(_))
+        ));
+        assert!(
+            repo.revoke_preview(&expired, ExecutionStatus::Expired)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            repo.get(&expired).await.unwrap().status,
+            ExecutionStatus::Expired
+        );
+    }
+
+    #[tokio::test]
+    async fn count_running_includes_applying() {
+        let repo = repo_with_plugin().await;
+        for (id, status) in [
+            ("running", ExecutionStatus::Running),
+            ("applying", ExecutionStatus::Applying),
+            ("pending", ExecutionStatus::Pending),
+            ("done", ExecutionStatus::Completed),
+        ] {
+            repo.create_with_phase(new_execution(id, ExecutionPhase::Apply))
+                .await
+                .unwrap();
+            repo.update_status(id, status).await.unwrap();
+        }
+
+        assert_eq!(repo.count_running().await.unwrap(), 2);
     }
 }
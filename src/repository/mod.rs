@@ -1,9 +1,24 @@
+macro_rules! with_pool {
+    ($pool:expr, |$conn:ident| $body:expr) => {
+        match $pool {
+            $crate::repository::DbPool::Sqlite($conn) => $body,
+            $crate::repository::DbPool::Postgres($conn) => $body,
+        }
+    };
+}
+
+pub mod audit_repository;
 pub mod connection;
 pub mod execution_repository;
 pub mod plugin_repository;
 
-pub use connection::establish_connection;
-pub use execution_repository::ExecutionRepository;
+pub use audit_repository::AuditRepository;
+pub use connection::{PoolSettings, establish_connection, is_memory_url, redact_database_url};
+pub use execution_repository::{ExecutionRepository, NewExecution};
 pub use plugin_repository::PluginRepository;
 
-pub type DbPool = sqlx::SqlitePool;
+#[derive(Clone, Debug)]
+pub enum DbPool {
+    Sqlite(sqlx::SqlitePool),
+    Postgres(sqlx::PgPool),
+}
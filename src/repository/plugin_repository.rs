@@ -1,8 +1,16 @@
 use crate::error::{AppError, Result};
-use crate::models::Plugin;
+use crate::models::{LastExecution, Plugin, PluginVersion};
 use crate::repository::DbPool;
 use chrono::Utc;
 
+const SELECT_PLUGINS: &str = r#"
+    SELECT id, plugin_id, name, version, min_anthill_version, plugin_type, description, author, plugin_path, entry_point,
+           enabled, created_at, updated_at, parameters, parameter_groups, metadata,
+           python_venv_path, python_dependencies, deleted_at, default_timeout_ms, max_timeout_ms,
+           disabled_reason, resolved_dependencies, tags, python_version
+    FROM plugins
+"#;
+
 #[derive(Clone)]
 pub struct PluginRepository {
     pool: DbPool,
@@ -14,126 +22,269 @@ impl PluginRepository {
     }
 
     pub async fn list(&self) -> Result<Vec<Plugin>> {
-        let plugins = sqlx::query_as::<_, Plugin>(
-            r#"
-            SELECT id, plugin_id, name, version, min_anthill_version, plugin_type, description, author, plugin_path, entry_point,
-                   enabled, created_at, updated_at, parameters, parameter_groups, metadata,
-                   python_venv_path, python_dependencies
-            FROM plugins
-            ORDER BY created_at DESC
-            "#,
-        )
-        .fetch_all(&self.pool)
-        .await?;
+        let sql = format!(
+            "{} WHERE deleted_at IS NULL ORDER BY created_at DESC",
+            SELECT_PLUGINS
+        );
+        let plugins = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, Plugin>(&sql).fetch_all(pool).await
+        })?;
 
         Ok(plugins)
     }
 
+    /// Latest execution per plugin in one grouped query; ties on `started_at` yield several rows.
+    pub async fn latest_executions(&self) -> Result<Vec<LastExecution>> {
+        let executions = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, LastExecution>(
+                r#"
+                SELECT e.plugin_id, e.id, e.phase, e.status, e.started_at, e.finished_at
+                FROM executions e
+                JOIN (
+                    SELECT plugin_id, MAX(started_at) AS started_at
+                    FROM executions
+                    GROUP BY plugin_id
+                ) latest ON latest.plugin_id = e.plugin_id AND latest.started_at = e.started_at
+                "#,
+            )
+            .fetch_all(pool)
+            .await
+        })?;
+
+        Ok(executions)
+    }
+
     pub async fn get(&self, id: &str) -> Result<Plugin> {
-        let plugin = sqlx::query_as::<_, Plugin>(
-            r#"
-            SELECT id, plugin_id, name, version, min_anthill_version, plugin_type, description, author, plugin_path, entry_point,
-                   enabled, created_at, updated_at, parameters, parameter_groups, metadata,
-                   python_venv_path, python_dependencies
-            FROM plugins
-            WHERE plugin_id = ?
-            "#,
-        )
-        .bind(id)
-        .fetch_optional(&self.pool)
-        .await?
+        let sql = format!(
+            "{} WHERE plugin_id = $1 AND deleted_at IS NULL",
+            SELECT_PLUGINS
+        );
+        let plugin = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, Plugin>(&sql)
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+        })?
         .ok_or_else(|| AppError::PluginNotFound(id.to_string()))?;
 
         Ok(plugin)
     }
 
     pub async fn get_by_name(&self, name: &str) -> Result<Plugin> {
-        let plugin = sqlx::query_as::<_, Plugin>(
-            r#"
-            SELECT id, plugin_id, name, version, min_anthill_version, plugin_type, description, author, plugin_path, entry_point,
-                   enabled, created_at, updated_at, parameters, parameter_groups, metadata,
-                   python_venv_path, python_dependencies
-            FROM plugins
-            WHERE name = ?
-            "#,
-        )
-        .bind(name)
-        .fetch_optional(&self.pool)
-        .await?
+        let sql = format!("{} WHERE name = $1 AND deleted_at IS NULL", SELECT_PLUGINS);
+        let plugin = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, Plugin>(&sql)
+                .bind(name)
+                .fetch_optional(pool)
+                .await
+        })?
         .ok_or_else(|| AppError::PluginNotFound(name.to_string()))?;
 
         Ok(plugin)
     }
 
+    pub async fn get_deleted(&self, id: &str) -> Result<Plugin> {
+        let sql = format!(
+            "{} WHERE plugin_id = $1 AND deleted_at IS NOT NULL AND NOT history_only",
+            SELECT_PLUGINS
+        );
+        let plugin = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, Plugin>(&sql)
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+        })?
+        .ok_or_else(|| AppError::PluginNotFound(id.to_string()))?;
+
+        Ok(plugin)
+    }
+
+    pub async fn list_deleted_before(&self, cutoff: i64) -> Result<Vec<Plugin>> {
+        let sql = format!(
+            "{} WHERE deleted_at IS NOT NULL AND NOT history_only AND deleted_at <= $1",
+            SELECT_PLUGINS
+        );
+        let plugins = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, Plugin>(&sql)
+                .bind(cutoff)
+                .fetch_all(pool)
+                .await
+        })?;
+
+        Ok(plugins)
+    }
+
+    /// Number of installed plugins, excluding the recycle bin.
+    pub async fn count(&self) -> Result<u64> {
+        let count = with_pool!(&self.pool, |pool| {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM plugins WHERE deleted_at IS NULL")
+                .fetch_one(pool)
+                .await
+        })?;
+
+        Ok(count.max(0) as u64)
+    }
+
+    /// Inserts the plugin, taking over a row left by an uninstall, update or the
+    /// recycle bin so its executions reattach.
     pub async fn create(&self, plugin: &Plugin) -> Result<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO plugins (id, plugin_id, name, version, min_anthill_version, plugin_type, description, author, plugin_path, entry_point, enabled, created_at, updated_at, parameters, parameter_groups, metadata, python_venv_path, python_dependencies)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(&plugin.id)
-        .bind(&plugin.plugin_id)
-        .bind(&plugin.name)
-        .bind(&plugin.version)
-        .bind(&plugin.min_anthill_version)
-        .bind(plugin.plugin_type as i32)
-        .bind(&plugin.description)
-        .bind(&plugin.author)
-        .bind(&plugin.plugin_path)
-        .bind(&plugin.entry_point)
-        .bind(plugin.enabled)
-        .bind(plugin.created_at)
-        .bind(plugin.updated_at)
-        .bind(&plugin.parameters)
-        .bind(&plugin.parameter_groups)
-        .bind(&plugin.metadata)
-        .bind(&plugin.python_venv_path)
-        .bind(&plugin.python_dependencies)
-        .execute(&self.pool)
-        .await?;
+        let rows_affected = with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                r#"
+                INSERT INTO plugins (id, plugin_id, name, version, min_anthill_version, plugin_type, description, author, plugin_path, entry_point, enabled, created_at, updated_at, parameters, parameter_groups, metadata, python_venv_path, python_dependencies, deleted_at, default_timeout_ms, max_timeout_ms, resolved_dependencies, tags, python_version)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24)
+                ON CONFLICT (plugin_id) DO UPDATE
+                SET id = excluded.id, name = excluded.name, version = excluded.version, min_anthill_version = excluded.min_anthill_version, plugin_type = excluded.plugin_type, description = excluded.description, author = excluded.author, plugin_path = excluded.plugin_path, entry_point = excluded.entry_point, enabled = excluded.enabled, created_at = excluded.created_at, updated_at = excluded.updated_at, parameters = excluded.parameters, parameter_groups = excluded.parameter_groups, metadata = excluded.metadata, python_venv_path = excluded.python_venv_path, python_dependencies = excluded.python_dependencies, deleted_at = excluded.deleted_at, default_timeout_ms = excluded.default_timeout_ms, max_timeout_ms = excluded.max_timeout_ms, resolved_dependencies = excluded.resolved_dependencies, tags = excluded.tags, python_version = excluded.python_version, disabled_reason = NULL, history_only = FALSE
+                WHERE plugins.deleted_at IS NOT NULL
+                "#,
+            )
+            .bind(&plugin.id)
+            .bind(&plugin.plugin_id)
+            .bind(&plugin.name)
+            .bind(&plugin.version)
+            .bind(&plugin.min_anthill_version)
+            .bind(plugin.plugin_type as i32)
+            .bind(&plugin.description)
+            .bind(&plugin.author)
+            .bind(&plugin.plugin_path)
+            .bind(&plugin.entry_point)
+            .bind(plugin.enabled)
+            .bind(plugin.created_at)
+            .bind(plugin.updated_at)
+            .bind(&plugin.parameters)
+            .bind(&plugin.parameter_groups)
+            .bind(&plugin.metadata)
+            .bind(&plugin.python_venv_path)
+            .bind(&plugin.python_dependencies)
+            .bind(plugin.deleted_at)
+            .bind(plugin.default_timeout_ms)
+            .bind(plugin.max_timeout_ms)
+            .bind(&plugin.resolved_dependencies)
+            .bind(&plugin.tags)
+            .bind(&plugin.python_version)
+            .execute(pool)
+            .await
+            .map(|result| result.rows_affected())
+        })?;
+
+        if rows_affected == 0 {
+            return Err(AppError::PluginAlreadyExists(plugin.plugin_id.clone()));
+        }
 
         Ok(())
     }
 
     #[allow(unused)]
     pub async fn update(&self, plugin: &Plugin) -> Result<()> {
-        sqlx::query(
-            r#"
-            UPDATE plugins
-            SET name = ?, version = ?, min_anthill_version = ?, plugin_type = ?, description = ?, author = ?, plugin_path = ?, entry_point = ?, enabled = ?, updated_at = ?, parameters = ?, parameter_groups = ?, metadata = ?, python_venv_path = ?, python_dependencies = ?
-            WHERE plugin_id = ?
-            "#,
-        )
-        .bind(&plugin.name)
-        .bind(&plugin.version)
-        .bind(&plugin.min_anthill_version)
-        .bind(plugin.plugin_type as i32)
-        .bind(&plugin.description)
-        .bind(&plugin.author)
-        .bind(&plugin.plugin_path)
-        .bind(&plugin.entry_point)
-        .bind(plugin.enabled)
-        .bind(Utc::now().timestamp_millis())
-        .bind(&plugin.parameters)
-        .bind(&plugin.parameter_groups)
-        .bind(&plugin.metadata)
-        .bind(&plugin.python_venv_path)
-        .bind(&plugin.python_dependencies)
-        .bind(&plugin.plugin_id)
-        .execute(&self.pool)
-        .await?;
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                r#"
+                UPDATE plugins
+                SET name = $1, version = $2, min_anthill_version = $3, plugin_type = $4, description = $5, author = $6, plugin_path = $7, entry_point = $8, enabled = $9, updated_at = $10, parameters = $11, parameter_groups = $12, metadata = $13, python_venv_path = $14, python_dependencies = $15, default_timeout_ms = $16, max_timeout_ms = $17, resolved_dependencies = $18, tags = $19, python_version = $20
+                WHERE plugin_id = $21
+                "#,
+            )
+            .bind(&plugin.name)
+            .bind(&plugin.version)
+            .bind(&plugin.min_anthill_version)
+            .bind(plugin.plugin_type as i32)
+            .bind(&plugin.description)
+            .bind(&plugin.author)
+            .bind(&plugin.plugin_path)
+            .bind(&plugin.entry_point)
+            .bind(plugin.enabled)
+            .bind(Utc::now().timestamp_millis())
+            .bind(&plugin.parameters)
+            .bind(&plugin.parameter_groups)
+            .bind(&plugin.metadata)
+            .bind(&plugin.python_venv_path)
+            .bind(&plugin.python_dependencies)
+            .bind(plugin.default_timeout_ms)
+            .bind(plugin.max_timeout_ms)
+            .bind(&plugin.resolved_dependencies)
+            .bind(&plugin.tags)
+            .bind(&plugin.python_version)
+            .bind(&plugin.plugin_id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+        })?;
 
         Ok(())
     }
 
     pub async fn delete(&self, id: &str) -> Result<()> {
-        let result = sqlx::query("DELETE FROM plugins WHERE plugin_id = ?")
+        let rows_affected = with_pool!(&self.pool, |pool| {
+            sqlx::query("DELETE FROM plugins WHERE plugin_id = $1")
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|result| result.rows_affected())
+        })?;
+
+        if rows_affected == 0 {
+            return Err(AppError::PluginNotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn mark_deleted(&self, id: &str, deleted_at: i64) -> Result<()> {
+        let rows_affected = with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "UPDATE plugins SET deleted_at = $1, updated_at = $2 WHERE plugin_id = $3 AND deleted_at IS NULL",
+            )
+            .bind(deleted_at)
+            .bind(Utc::now().timestamp_millis())
+            .bind(id)
+            .execute(pool)
+            .await
+            .map(|result| result.rows_affected())
+        })?;
+
+        if rows_affected == 0 {
+            return Err(AppError::PluginNotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the row only as the parent of its executions; it is hidden everywhere
+    /// else and never restored or purged.
+    pub async fn mark_history_only(&self, id: &str) -> Result<()> {
+        let now = Utc::now().timestamp_millis();
+        let rows_affected = with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "UPDATE plugins SET deleted_at = $1, updated_at = $1, history_only = $2 WHERE plugin_id = $3 AND deleted_at IS NULL",
+            )
+            .bind(now)
+            .bind(true)
+            .bind(id)
+            .execute(pool)
+            .await
+            .map(|result| result.rows_affected())
+        })?;
+
+        if rows_affected == 0 {
+            return Err(AppError::PluginNotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn restore(&self, id: &str) -> Result<()> {
+        let rows_affected = with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "UPDATE plugins SET deleted_at = NULL, updated_at = $1 WHERE plugin_id = $2 AND deleted_at IS NOT NULL",
+            )
+            .bind(Utc::now().timestamp_millis())
             .bind(id)
-            .execute(&self.pool)
-            .await?;
+            .execute(pool)
+            .await
+            .map(|result| result.rows_affected())
+        })?;
 
-        if result.rows_affected() == 0 {
+        if rows_affected == 0 {
             return Err(AppError::PluginNotFound(id.to_string()));
         }
 
@@ -141,13 +292,228 @@ impl PluginRepository {
     }
 
     pub async fn update_enabled(&self, id: &str, enabled: bool) -> Result<()> {
-        sqlx::query("UPDATE plugins SET enabled = ?, updated_at = ? WHERE plugin_id = ?")
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "UPDATE plugins SET enabled = $1, disabled_reason = NULL, updated_at = $2 WHERE plugin_id = $3",
+            )
             .bind(enabled)
             .bind(Utc::now().timestamp_millis())
             .bind(id)
-            .execute(&self.pool)
-            .await?;
+            .execute(pool)
+            .await
+            .map(|_| ())
+        })?;
+
+        Ok(())
+    }
+
+    pub async fn disable_with_reason(&self, id: &str, reason: &str) -> Result<()> {
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "UPDATE plugins SET enabled = $1, disabled_reason = $2, updated_at = $3 WHERE plugin_id = $4",
+            )
+            .bind(false)
+            .bind(reason)
+            .bind(Utc::now().timestamp_millis())
+            .bind(id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+        })?;
 
         Ok(())
     }
+
+    pub async fn record_version(&self, version: &PluginVersion) -> Result<()> {
+        with_pool!(&self.pool, |pool| {
+            sqlx::query(
+                "INSERT INTO plugin_versions (plugin_id, version, installed_at, package_sha256) VALUES ($1, $2, $3, $4)",
+            )
+            .bind(&version.plugin_id)
+            .bind(&version.version)
+            .bind(version.installed_at)
+            .bind(&version.package_sha256)
+            .execute(pool)
+            .await
+            .map(|_| ())
+        })?;
+
+        Ok(())
+    }
+
+    pub async fn list_versions(&self, plugin_id: &str) -> Result<Vec<PluginVersion>> {
+        let versions = with_pool!(&self.pool, |pool| {
+            sqlx::query_as::<_, PluginVersion>(
+                r#"
+                SELECT plugin_id, version, installed_at, package_sha256
+                FROM plugin_versions
+                WHERE plugin_id = $1
+                ORDER BY installed_at DESC
+                "#,
+            )
+            .bind(plugin_id)
+            .fetch_all(pool)
+            .await
+        })?;
+
+        Ok(versions)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::models::PluginType;
+    use crate::repository::{PoolSettings, establish_connection};
+
+    pub(crate) fn plugin(id: &str) -> Plugin {
+        Plugin {
+            id: id.to_string(),
+            plugin_id: "demo".to_string(),
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            min_anthill_version: None,
+            plugin_type: PluginType::Shell,
+            description: String::new(),
+            author: String::new(),
+            plugin_path: "/tmp/demo".to_string(),
+            entry_point: "main.sh".to_string(),
+            enabled: true,
+            parameters: None,
+            parameter_groups: None,
+            metadata: None,
+            python_venv_path: None,
+            python_dependencies: None,
+            created_at: 0,
+            updated_at: 0,
+            deleted_at: None,
+            default_timeout_ms: None,
+            max_timeout_ms: None,
+            disabled_reason: None,
+            resolved_dependencies: None,
+            tags: None,
+            python_version: None,
+            settings: Default::default(),
+        }
+    }
+
+    async fn repo_with_execution() -> (PluginRepository, DbPool) {
+        let pool = establish_connection("sqlite::memory:", PoolSettings::default())
+            .await
+            .unwrap();
+        let repo = PluginRepository::new(pool.clone());
+        repo.create(&plugin("p1")).await.unwrap();
+        with_pool!(&pool, |conn| {
+            sqlx::query(
+                "INSERT INTO executions (id, plugin_id, status, started_at) VALUES ('e1', 'demo', 4, 0)",
+            )
+            .execute(conn)
+            .await
+            .map(|_| ())
+        })
+        .unwrap();
+        (repo, pool)
+    }
+
+    async fn execution_count(pool: &DbPool) -> i64 {
+        with_pool!(pool, |conn| {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM executions WHERE plugin_id = 'demo'")
+                .fetch_one(conn)
+                .await
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn plugins_round_trip_every_column() {
+        let pool = establish_connection("sqlite::memory:", PoolSettings::default())
+            .await
+            .unwrap();
+        let repo = PluginRepository::new(pool);
+        let mut plugin = Plugin {
+            min_anthill_version: Some("0.3.0".to_string()),
+            plugin_type: PluginType::Python,
+            description: "desc".to_string(),
+            author: "me".to_string(),
+            enabled: false,
+            created_at: 10,
+            updated_at: 20,
+            parameters: Some(r#"[{"name":"n","type":"string"}]"#.to_string()),
+            parameter_groups: Some(r#"[{"id":"g","label":"G"}]"#.to_string()),
+            metadata: Some(r#"{"category":"tools"}"#.to_string()),
+            python_venv_path: Some("/tmp/venv".to_string()),
+            python_dependencies: Some(r#"["requests"]"#.to_string()),
+            default_timeout_ms: Some(1_000),
+            max_timeout_ms: Some(5_000),
+            resolved_dependencies: Some(r#"["requests==2.32.3"]"#.to_string()),
+            tags: Some(r#"["net"]"#.to_string()),
+            python_version: Some("3.12.1".to_string()),
+            ..plugin("p1")
+        };
+        repo.create(&plugin).await.unwrap();
+        assert_eq!(repo.list().await.unwrap().len(), 1);
+
+        let read = repo.get("demo").await.unwrap();
+        assert_eq!(read.min_anthill_version.as_deref(), Some("0.3.0"));
+        assert_eq!(
+            serde_json::to_value(&read).unwrap(),
+            serde_json::to_value(&plugin).unwrap()
+        );
+
+        plugin.min_anthill_version = Some("0.4.0".to_string());
+        plugin.version = "2.0.0".to_string();
+        repo.update(&plugin).await.unwrap();
+        let read = repo.get("demo").await.unwrap();
+        assert_eq!(read.min_anthill_version.as_deref(), Some("0.4.0"));
+        assert_eq!(read.version, "2.0.0");
+        assert_eq!(read.python_version.as_deref(), Some("3.12.1"));
+    }
+
+    #[tokio::test]
+    async fn deleting_a_plugin_cascades_to_its_executions() {
+        let (repo, pool) = repo_with_execution().await;
+        assert_eq!(execution_count(&pool).await, 1);
+
+        repo.delete("demo").await.unwrap();
+        assert_eq!(execution_count(&pool).await, 0);
+
+        let orphan = with_pool!(&pool, |conn| {
+            sqlx::query(
+                "INSERT INTO executions (id, plugin_id, status, started_at) VALUES ('e2', 'missing', 4, 0)",
+            )
+            .execute(conn)
+            .await
+            .map(|_| ())
+        });
+        assert!(
+            orphan.is_err(),
+            "executions must reference an existing plugin"
+        );
+    }
+
+    #[tokio::test]
+    async fn create_rejects_an_installed_plugin() {
+        let (repo, _pool) = repo_with_execution().await;
+        assert!(matches!(
+            repo.create(&plugin("p2")).await,
+            Err(AppError::PluginAlreadyExists(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn create_takes_over_retired_and_recycled_rows() {
+        let (repo, pool) = repo_with_execution().await;
+
+        repo.mark_history_only("demo").await.unwrap();
+        repo.create(&plugin("p2")).await.unwrap();
+        assert_eq!(repo.get("demo").await.unwrap().id, "p2");
+        assert_eq!(execution_count(&pool).await, 1);
+
+        repo.mark_deleted("demo", 1).await.unwrap();
+        repo.create(&plugin("p3")).await.unwrap();
+        let reinstalled = repo.get("demo").await.unwrap();
+        assert_eq!(reinstalled.id, "p3");
+        assert_eq!(reinstalled.deleted_at, None);
+        assert_eq!(execution_count(&pool).await, 1);
+    }
 }
@@ -0,0 +1,48 @@
+use crate::error::Result;
+use crate::models::AuditEntry;
+use crate::repository::AuditRepository;
+use chrono::Utc;
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct AuditService {
+    repo: AuditRepository,
+}
+
+impl AuditService {
+    pub fn new(repo: AuditRepository) -> Self {
+        Self { repo }
+    }
+
+    /// Appends an entry. The action has already happened, so a failed write
+    /// is logged rather than returned.
+    pub async fn record(&self, action: &str, plugin_id: &str, detail: Value) {
+        let entry = AuditEntry {
+            id: Uuid::new_v4().to_string(),
+            action: action.to_string(),
+            plugin_id: plugin_id.to_string(),
+            actor: None,
+            timestamp: Utc::now().timestamp_millis(),
+            detail: Some(detail.to_string()),
+        };
+        if let Err(err) = self.repo.record(&entry).await {
+            tracing::warn!(
+                "Failed to write audit entry {} for {}: {}",
+                action,
+                plugin_id,
+                err
+            );
+        }
+    }
+
+    pub async fn list(
+        &self,
+        since: Option<i64>,
+        until: Option<i64>,
+        plugin_id: Option<&str>,
+        action: Option<&str>,
+    ) -> Result<Vec<AuditEntry>> {
+        self.repo.list(since, until, plugin_id, action).await
+    }
+}
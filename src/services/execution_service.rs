@@ -1,13 +1,27 @@
+use crate::api::dto::execution::ExecutionResponse;
 use crate::error::{AppError, Result};
-use crate::executor::{NodeExecutor, PluginExecutor, PythonExecutor};
-use crate::models::{Execution, ExecutionPhase, ExecutionStatus, PluginParamType, PluginParameter};
+use crate::executor::{
+    ContainerExecutor, ContainerHandle, NodeExecutor, PluginExecutor, PythonExecutor,
+    ShellExecutor, kill_process_tree,
+};
+use crate::models::{
+    Execution, ExecutionPhase, ExecutionStatus, ParamDelivery, Plugin, PluginParamType,
+    PluginParameter, PluginParameterValidation, PluginType, ResourceLimits,
+};
 use crate::paths;
-use crate::repository::{ExecutionRepository, PluginRepository};
+use crate::repository::{ExecutionRepository, NewExecution, PluginRepository};
+use crate::services::AuditService;
+use crate::services::plugin_service::{ensure_host_allowed, host_in_allowlist};
 use chrono::Utc;
 use semver::Version;
+use serde::Serialize;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use tokio::time::{sleep, Duration};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::{Duration, sleep};
+use tracing::Instrument;
 
 #[derive(Clone)]
 pub struct ExecutionService {
@@ -15,9 +29,162 @@ pub struct ExecutionService {
     plugin_repo: PluginRepository,
     python_executor: PythonExecutor,
     node_executor: NodeExecutor,
+    shell_executor: ShellExecutor,
+    container_executor: Option<ContainerExecutor>,
+    output_flush_ms: u64,
+    auto_disable_missing_runtime: bool,
+    env_passthrough: Vec<String>,
+    keep_work_dir_on_failure: bool,
+    work_dir_retention_ms: i64,
+    resource_limits: ResourceLimits,
+    allowed_hosts: Vec<String>,
+    enabled_plugin_types: Vec<PluginType>,
+    audit: Option<AuditService>,
+    queue: ExecutionQueue,
+    patterns: PatternCache,
+    dirs: paths::Dirs,
+    /// Set by [`ExecutionService::shutdown`]: running processes are killed and
+    /// queued executions never start.
+    stopping: Arc<tokio::sync::watch::Sender<bool>>,
+}
+
+/// Compiled `validation.pattern` regexes shared by all executions, so a
+/// pattern is compiled once rather than on every run. Install has already
+/// checked that each pattern compiles.
+#[derive(Clone, Default)]
+struct PatternCache(Arc<Mutex<HashMap<String, regex::Regex>>>);
+
+impl PatternCache {
+    fn get(
+        &self,
+        validation: &PluginParameterValidation,
+    ) -> Option<std::result::Result<regex::Regex, regex::Error>> {
+        let pattern = validation.pattern.as_ref()?;
+        if let Some(regex) = self.0.lock().unwrap().get(pattern) {
+            return Some(Ok(regex.clone()));
+        }
+        let compiled = validation.pattern_regex()?;
+        if let Ok(regex) = &compiled {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(pattern.clone(), regex.clone());
+        }
+        Some(compiled)
+    }
+}
+
+/// Caps how many plugin processes run at once. Executions past the cap wait
+/// here in start order until a running one finishes.
+#[derive(Clone, Default)]
+struct ExecutionQueue {
+    permits: Option<Arc<Semaphore>>,
+    waiting: Arc<Mutex<Vec<String>>>,
+}
+
+impl ExecutionQueue {
+    fn new(max_concurrent: usize) -> Self {
+        Self {
+            permits: (max_concurrent > 0).then(|| Arc::new(Semaphore::new(max_concurrent))),
+            waiting: Arc::default(),
+        }
+    }
+
+    fn push(&self, id: &str) {
+        self.waiting.lock().unwrap().push(id.to_string());
+    }
+
+    /// Drops `id` from the queue; false if it was not waiting.
+    fn remove(&self, id: &str) -> bool {
+        let mut waiting = self.waiting.lock().unwrap();
+        let before = waiting.len();
+        waiting.retain(|queued| queued != id);
+        waiting.len() != before
+    }
+
+    /// 1-based place of `id` among waiting executions.
+    fn position(&self, id: &str) -> Option<usize> {
+        let waiting = self.waiting.lock().unwrap();
+        waiting
+            .iter()
+            .position(|queued| queued == id)
+            .map(|i| i + 1)
+    }
 }
 
 const PREVIEW_TTL_MS: i64 = 10 * 60 * 1000;
+const DEFAULT_OUTPUT_FLUSH_MS: u64 = 500;
+const DEFAULT_WORK_DIR_RETENTION_MS: i64 = 24 * 60 * 60 * 1000;
+const MAX_OUTPUT_CHUNK: usize = 8 * 1024;
+/// Per-stream cap on output kept in memory and stored on the execution.
+const MAX_OUTPUT_BYTES: usize = 10 * 1024 * 1024;
+/// How long to keep reading after the process exits; a backgrounded
+/// grandchild may hold the pipes open indefinitely.
+const OUTPUT_DRAIN_GRACE: Duration = Duration::from_secs(2);
+const DEFAULT_HEALTH_CHECK_TIMEOUT_MS: u64 = 10_000;
+const MAX_HEALTH_CHECK_TIMEOUT_MS: u64 = 60_000;
+const WARM_TIMEOUT_MS: u64 = 60_000;
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+const CALLBACK_ATTEMPTS: u32 = 3;
+const CALLBACK_TIMEOUT_MS: u64 = 10_000;
+
+/// Per-request options for starting a prepare or execute run.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionOptions {
+    pub params: HashMap<String, serde_json::Value>,
+    /// Requested timeout, clamped by the plugin's `max_timeout_ms`.
+    pub timeout_ms: Option<u64>,
+    /// Caller-chosen UUID; reusing one returns the existing execution.
+    pub execution_id: Option<String>,
+    pub idempotency_key: Option<String>,
+    pub dry_run: bool,
+    /// Name from `metadata.commands` to run instead of the entry point.
+    pub command: Option<String>,
+    pub callback_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthCheckResult {
+    pub plugin_id: String,
+    pub passed: bool,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WarmResult {
+    pub plugin_id: String,
+    pub warmed: bool,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    pub stderr: Option<String>,
+    pub error: Option<String>,
+}
+
+/// What an unrecorded run left behind once it exited or was killed.
+struct ProcessOutcome {
+    exit_code: Option<i32>,
+    timed_out: bool,
+    stdout: Option<String>,
+    stderr: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct WorkDirCleanup {
+    pub removed_dirs: u64,
+    pub reclaimed_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum OutputStream {
+    Stdout,
+    Stderr,
+}
 
 impl ExecutionService {
     pub fn new(exec_repo: ExecutionRepository, plugin_repo: PluginRepository) -> Self {
@@ -26,70 +193,183 @@ impl ExecutionService {
             plugin_repo,
             python_executor: PythonExecutor::default(),
             node_executor: NodeExecutor::default(),
+            shell_executor: ShellExecutor::default(),
+            container_executor: None,
+            output_flush_ms: DEFAULT_OUTPUT_FLUSH_MS,
+            auto_disable_missing_runtime: false,
+            env_passthrough: Vec::new(),
+            keep_work_dir_on_failure: false,
+            work_dir_retention_ms: DEFAULT_WORK_DIR_RETENTION_MS,
+            resource_limits: ResourceLimits::default(),
+            allowed_hosts: Vec::new(),
+            enabled_plugin_types: Vec::new(),
+            audit: None,
+            queue: ExecutionQueue::default(),
+            patterns: PatternCache::default(),
+            dirs: paths::Dirs::default(),
+            stopping: Arc::new(tokio::sync::watch::Sender::new(false)),
+        }
+    }
+
+    pub fn with_output_flush_ms(mut self, output_flush_ms: u64) -> Self {
+        self.output_flush_ms = output_flush_ms;
+        self
+    }
+
+    /// Plugin types that may run; empty allows all.
+    pub fn with_enabled_plugin_types(mut self, plugin_types: Vec<PluginType>) -> Self {
+        self.enabled_plugin_types = plugin_types;
+        self
+    }
+
+    pub fn with_auto_disable_missing_runtime(mut self, enabled: bool) -> Self {
+        self.auto_disable_missing_runtime = enabled;
+        self
+    }
+
+    /// Leaves failed executions' work dirs in place for debugging until swept.
+    pub fn with_keep_work_dir_on_failure(mut self, keep: bool) -> Self {
+        self.keep_work_dir_on_failure = keep;
+        self
+    }
+
+    /// Plugin processes allowed to run at once; 0 means unlimited. Later
+    /// executions stay pending with a `queue_position` until a slot frees up.
+    pub fn with_max_concurrent_executions(mut self, max_concurrent: usize) -> Self {
+        self.queue = ExecutionQueue::new(max_concurrent);
+        self
+    }
+
+    pub fn with_work_dir_retention_ms(mut self, retention_ms: i64) -> Self {
+        self.work_dir_retention_ms = retention_ms;
+        self
+    }
+
+    /// Lets plugin processes inherit the server's full environment.
+    pub fn with_inherit_env(mut self, inherit_env: bool) -> Self {
+        self.python_executor = self.python_executor.with_clear_env(!inherit_env);
+        self.node_executor = self.node_executor.with_clear_env(!inherit_env);
+        self.shell_executor = self.shell_executor.with_clear_env(!inherit_env);
+        self
+    }
+
+    /// Directories put in front of PATH for every plugin process.
+    pub fn with_path_prepend(mut self, path_prepend: Vec<PathBuf>) -> Self {
+        self.python_executor = self.python_executor.with_path_prepend(path_prepend.clone());
+        self.node_executor = self.node_executor.with_path_prepend(path_prepend.clone());
+        self.shell_executor = self.shell_executor.with_path_prepend(path_prepend);
+        self
+    }
+
+    /// Shell used for shell plugins; `None` keeps the platform default.
+    pub fn with_shell_path(mut self, shell_path: Option<String>) -> Self {
+        if let Some(shell_path) = shell_path {
+            self.shell_executor = self.shell_executor.with_shell_path(shell_path);
         }
+        self
+    }
+
+    /// Host env vars handed to plugins; a trailing `*` matches by prefix.
+    pub fn with_env_passthrough(mut self, env_passthrough: Vec<String>) -> Self {
+        self.env_passthrough = env_passthrough;
+        self
+    }
+
+    pub fn with_container_executor(mut self, container_executor: ContainerExecutor) -> Self {
+        self.container_executor = Some(
+            container_executor
+                .with_resource_limits(self.resource_limits)
+                .with_dirs(self.dirs.clone()),
+        );
+        self
+    }
+
+    /// Where execution work dirs and plugin state live.
+    pub fn with_dirs(mut self, dirs: paths::Dirs) -> Self {
+        self.container_executor = self
+            .container_executor
+            .map(|executor| executor.with_dirs(dirs.clone()));
+        self.dirs = dirs;
+        self
+    }
+
+    /// Hosts that execution callbacks may be sent to; empty allows any public
+    /// host. Listed hosts may also resolve to private or loopback addresses.
+    pub fn with_allowed_hosts(mut self, allowed_hosts: Vec<String>) -> Self {
+        self.allowed_hosts = allowed_hosts;
+        self
+    }
+
+    pub fn with_audit(mut self, audit: AuditService) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Default and maximum limits for every plugin process.
+    pub fn with_resource_limits(mut self, resource_limits: ResourceLimits) -> Self {
+        self.resource_limits = resource_limits;
+        self.python_executor = self.python_executor.with_resource_limits(resource_limits);
+        self.node_executor = self.node_executor.with_resource_limits(resource_limits);
+        self.shell_executor = self.shell_executor.with_resource_limits(resource_limits);
+        self.container_executor = self
+            .container_executor
+            .map(|executor| executor.with_resource_limits(resource_limits));
+        self
     }
 
     pub async fn execute_plugin(
         &self,
         plugin_id: &str,
-        params: HashMap<String, serde_json::Value>,
+        mut options: ExecutionOptions,
     ) -> Result<Execution> {
         // 直接执行（无预览）的快捷接口，保持向后兼容
+        self.validate_callback_url(options.callback_url.as_deref())?;
         let plugin = self.plugin_repo.get(plugin_id).await?;
         if !plugin.enabled {
             return Err(AppError::PluginDisabled);
         }
+        self.ensure_plugin_type_enabled(&plugin)?;
         Self::ensure_min_anthill_version(&plugin.min_anthill_version)?;
 
-        let resolved_params = Self::resolve_parameters(&plugin.parameters, params)?;
-        let mut env = HashMap::new();
-        if !resolved_params.is_empty() {
-            let params_json = serde_json::to_string(&resolved_params).map_err(|e| {
-                AppError::Execution(format!("Failed to serialize parameters: {}", e))
-            })?;
-            env.insert("ANTHILL_PLUGIN_PARAMS".to_string(), params_json);
-        }
+        let params = std::mem::take(&mut options.params);
+        let resolved_params = self.resolve_parameters(&plugin.parameters, params)?;
+        let mut env = Self::parameter_env(&plugin, &resolved_params)?;
+        let timeout = Self::resolve_timeout(&plugin, options.timeout_ms);
         env.insert("ANTHILL_PHASE".to_string(), "apply".to_string());
+        if options.dry_run {
+            env.insert("ANTHILL_DRY_RUN".to_string(), "1".to_string());
+        }
+        let plugin = Self::select_command(plugin, options.command.as_deref(), &mut env)?;
 
-        self.start_process(
-            plugin,
-            ExecutionPhase::Apply,
-            ExecutionStatus::Completed,
-            env,
-            true,
-        )
-        .await
+        self.start_process(plugin, ExecutionPhase::Apply, env, timeout, options)
+            .await
     }
 
     pub async fn prepare_plugin(
         &self,
         plugin_id: &str,
-        params: HashMap<String, serde_json::Value>,
+        mut options: ExecutionOptions,
     ) -> Result<Execution> {
+        self.validate_callback_url(options.callback_url.as_deref())?;
         let plugin = self.plugin_repo.get(plugin_id).await?;
         if !plugin.enabled {
             return Err(AppError::PluginDisabled);
         }
+        self.ensure_plugin_type_enabled(&plugin)?;
         Self::ensure_min_anthill_version(&plugin.min_anthill_version)?;
 
-        let resolved_params = Self::resolve_parameters(&plugin.parameters, params)?;
-        let mut env = HashMap::new();
-        if !resolved_params.is_empty() {
-            let params_json = serde_json::to_string(&resolved_params).map_err(|e| {
-                AppError::Execution(format!("Failed to serialize parameters: {}", e))
-            })?;
-            env.insert("ANTHILL_PLUGIN_PARAMS".to_string(), params_json);
-        }
+        let params = std::mem::take(&mut options.params);
+        let resolved_params = self.resolve_parameters(&plugin.parameters, params)?;
+        let mut env = Self::parameter_env(&plugin, &resolved_params)?;
+        let timeout = Self::resolve_timeout(&plugin, options.timeout_ms);
         env.insert("ANTHILL_PHASE".to_string(), "prepare".to_string());
+        if options.dry_run {
+            env.insert("ANTHILL_DRY_RUN".to_string(), "1".to_string());
+        }
+        let plugin = Self::select_command(plugin, options.command.as_deref(), &mut env)?;
 
-        self.start_process(
-            plugin,
-            ExecutionPhase::Prepare,
-            ExecutionStatus::PreviewReady,
-            env,
-            false,
-        )
-        .await
+        self.start_process(plugin, ExecutionPhase::Prepare, env, timeout, options)
+            .await
     }
 
     pub async fn apply_execution(
@@ -97,6 +377,7 @@ impl ExecutionService {
         id: &str,
         confirm_token: &str,
         params: HashMap<String, serde_json::Value>,
+        timeout_ms: Option<u64>,
     ) -> Result<Execution> {
         let execution = self.exec_repo.get(id).await?;
         if execution.phase != ExecutionPhase::Prepare {
@@ -112,34 +393,43 @@ impl ExecutionService {
         if execution.confirm_token.as_deref() != Some(confirm_token) {
             return Err(AppError::Execution("Invalid confirm token".to_string()));
         }
-        if let Some(expires_at) = execution.expires_at {
-            if Utc::now().timestamp_millis() > expires_at {
-                return Err(AppError::Execution(
-                    "Preview has expired, please run prepare again".to_string(),
-                ));
+        if let Some(expires_at) = execution.expires_at
+            && Utc::now().timestamp_millis() > expires_at
+        {
+            if self
+                .exec_repo
+                .revoke_preview(id, ExecutionStatus::Expired)
+                .await?
+            {
+                self.remove_preview_work_dir(id)?;
             }
+            return Err(AppError::Execution(
+                "Preview has expired, please run prepare again".to_string(),
+            ));
         }
 
         let plugin = self.plugin_repo.get(&execution.plugin_id).await?;
         if !plugin.enabled {
             return Err(AppError::PluginDisabled);
         }
+        self.ensure_plugin_type_enabled(&plugin)?;
         Self::ensure_min_anthill_version(&plugin.min_anthill_version)?;
 
-        let resolved_params = Self::resolve_parameters(&plugin.parameters, params)?;
-        let mut env = HashMap::new();
-        if !resolved_params.is_empty() {
-            let params_json = serde_json::to_string(&resolved_params).map_err(|e| {
-                AppError::Execution(format!("Failed to serialize parameters: {}", e))
-            })?;
-            env.insert("ANTHILL_PLUGIN_PARAMS".to_string(), params_json);
-        }
+        let resolved_params = self.resolve_parameters(&plugin.parameters, params)?;
+        let mut env = Self::parameter_env(&plugin, &resolved_params)?;
+        let timeout = Self::resolve_timeout(&plugin, timeout_ms);
         env.insert("ANTHILL_PHASE".to_string(), "apply".to_string());
         if let Some(plan) = execution.preview_payload.clone() {
             env.insert("ANTHILL_PREVIEW_PLAN".to_string(), plan);
         }
+        if execution.dry_run {
+            env.insert("ANTHILL_DRY_RUN".to_string(), "1".to_string());
+        }
+        let plugin = Self::select_command(plugin, execution.command.as_deref(), &mut env)?;
 
-        self.exec_repo.begin_apply(id).await?;
+        self.exec_repo
+            .begin_apply(id, Self::recorded_params(&plugin, &env).as_deref())
+            .await?;
 
         let updated_execution = self.exec_repo.get(id).await?;
 
@@ -149,24 +439,263 @@ impl ExecutionService {
             ExecutionStatus::Completed,
             env,
             true,
+            timeout,
         )
         .await?;
+        self.audit_started("apply", &updated_execution).await;
 
         Ok(updated_execution)
     }
 
+    /// Starts a new execution of the same plugin and phase with the recorded params.
+    pub async fn retry_execution(&self, id: &str) -> Result<Execution> {
+        let execution = self.exec_repo.get(id).await?;
+        if matches!(
+            execution.status,
+            ExecutionStatus::Pending | ExecutionStatus::Running | ExecutionStatus::Applying
+        ) {
+            return Err(AppError::Execution(
+                "Execution is still running and cannot be retried".to_string(),
+            ));
+        }
+
+        let params: HashMap<String, serde_json::Value> = match execution.params.as_deref() {
+            Some(raw) => serde_json::from_str(raw).map_err(|err| {
+                AppError::Execution(format!("Failed to parse recorded params: {}", err))
+            })?,
+            None => HashMap::new(),
+        };
+        // Secret values are redacted in history, so they cannot be replayed
+        let plugin = self.plugin_repo.get(&execution.plugin_id).await?;
+        let schema = Self::parse_parameters(&plugin.parameters)?;
+        if let Some(param) = schema
+            .iter()
+            .find(|param| param.is_secret() && params.contains_key(&param.name))
+        {
+            return Err(AppError::Execution(format!(
+                "Execution cannot be retried: secret parameter '{}' is not stored",
+                param.name
+            )));
+        }
+
+        let options = ExecutionOptions {
+            params,
+            dry_run: execution.dry_run,
+            command: execution.command,
+            callback_url: execution.callback_url,
+            ..Default::default()
+        };
+        match execution.phase {
+            ExecutionPhase::Prepare => self.prepare_plugin(&execution.plugin_id, options).await,
+            ExecutionPhase::Apply => self.execute_plugin(&execution.plugin_id, options).await,
+        }
+    }
+
     pub async fn get_execution(&self, id: &str) -> Result<Execution> {
         self.exec_repo.get(id).await
     }
 
-    pub async fn list_executions(&self, plugin_id: Option<String>) -> Result<Vec<Execution>> {
-        if let Some(pid) = plugin_id {
+    pub fn subscribe_status_changes(&self) -> tokio::sync::broadcast::Receiver<String> {
+        self.exec_repo.subscribe_status_changes()
+    }
+
+    /// Executions with a live process: running or applying.
+    #[cfg(target_os = "windows")]
+    pub async fn count_running(&self) -> Result<u64> {
+        self.exec_repo.count_running().await
+    }
+
+    pub async fn list_executions(
+        &self,
+        plugin_id: Option<String>,
+        status: Option<ExecutionStatus>,
+    ) -> Result<Vec<Execution>> {
+        if let Some(status) = status {
+            self.exec_repo
+                .list_by_status(status, plugin_id.as_deref())
+                .await
+        } else if let Some(pid) = plugin_id {
             self.exec_repo.list_by_plugin(&pid).await
         } else {
             self.exec_repo.list_all().await
         }
     }
 
+    /// Active executions with their queue position: those holding a slot
+    /// first in start order, then those waiting for one in queue order.
+    pub async fn execution_queue(&self) -> Result<Vec<(Execution, Option<usize>)>> {
+        let mut queue: Vec<_> = self
+            .exec_repo
+            .list_active()
+            .await?
+            .into_iter()
+            .map(|execution| {
+                let position = self.queue.position(&execution.id);
+                (execution, position)
+            })
+            .collect();
+        // Stable, so executions holding a slot keep their start order
+        queue.sort_by_key(|(_, position)| position.unwrap_or(0));
+        Ok(queue)
+    }
+
+    /// 1-based position of an execution waiting for a slot under
+    /// `max_concurrent_executions`; `None` once it runs or when unlimited.
+    pub fn queue_position(&self, execution_id: &str) -> Option<usize> {
+        self.queue.position(execution_id)
+    }
+
+    pub async fn list_previews(&self) -> Result<Vec<Execution>> {
+        let now = Utc::now().timestamp_millis();
+        let previews = self
+            .exec_repo
+            .list_by_status(ExecutionStatus::PreviewReady, None)
+            .await?;
+        Ok(previews
+            .into_iter()
+            .filter(|execution| {
+                execution
+                    .expires_at
+                    .is_none_or(|expires_at| expires_at > now)
+            })
+            .collect())
+    }
+
+    pub async fn prune_previews(&self, include_active: bool) -> Result<Vec<String>> {
+        let now = Utc::now().timestamp_millis();
+        let mut pruned = Vec::new();
+        for execution in self
+            .exec_repo
+            .list_by_status(ExecutionStatus::PreviewReady, None)
+            .await?
+        {
+            let expired = execution
+                .expires_at
+                .is_some_and(|expires_at| expires_at <= now);
+            if !include_active && !expired {
+                continue;
+            }
+            let status = if expired {
+                ExecutionStatus::Expired
+            } else {
+                ExecutionStatus::Stopped
+            };
+            if !self.exec_repo.revoke_preview(&execution.id, status).await? {
+                continue;
+            }
+            self.remove_preview_work_dir(&execution.id)?;
+            pruned.push(execution.id);
+        }
+        Ok(pruned)
+    }
+
+    /// Cancels a preview before it is applied: the confirm token stops working,
+    /// the execution becomes `Stopped` and its work dir is deleted.
+    pub async fn discard_preview(&self, id: &str) -> Result<Execution> {
+        let execution = self.exec_repo.get(id).await?;
+        if execution.phase != ExecutionPhase::Prepare {
+            return Err(AppError::Execution(
+                "Only preview executions can be discarded".to_string(),
+            ));
+        }
+        // Contends with begin_apply on the same status check, so a preview that an
+        // apply already claimed is left running
+        if !self
+            .exec_repo
+            .revoke_preview(id, ExecutionStatus::Stopped)
+            .await?
+        {
+            return Err(AppError::Conflict(
+                "Execution is not a pending preview".to_string(),
+            ));
+        }
+        self.remove_preview_work_dir(id)?;
+        if let Some(audit) = &self.audit {
+            let detail = serde_json::json!({ "execution_id": id });
+            audit.record("discard", &execution.plugin_id, detail).await;
+        }
+        self.exec_repo.get(id).await
+    }
+
+    fn remove_preview_work_dir(&self, execution_id: &str) -> Result<()> {
+        let work_dir = self.work_dir_for(execution_id)?;
+        if work_dir.exists()
+            && let Err(err) = std::fs::remove_dir_all(&work_dir)
+        {
+            tracing::warn!("Failed to remove work dir {}: {}", work_dir.display(), err);
+        }
+        Ok(())
+    }
+
+    /// Removes work dirs of finished executions untouched for longer than the retention.
+    pub async fn purge_expired_work_dirs(&self) -> Result<u64> {
+        let cutoff = std::time::SystemTime::now()
+            .checked_sub(Duration::from_millis(
+                self.work_dir_retention_ms.max(0) as u64
+            ))
+            .unwrap_or(std::time::UNIX_EPOCH);
+        let cleanup = self.remove_work_dirs(Some(cutoff), true).await?;
+        Ok(cleanup.removed_dirs)
+    }
+
+    /// Removes work dirs without an execution row, plus those of finished
+    /// executions when `include_terminal` is set.
+    pub async fn cleanup_work_dirs(&self, include_terminal: bool) -> Result<WorkDirCleanup> {
+        self.remove_work_dirs(None, include_terminal).await
+    }
+
+    async fn remove_work_dirs(
+        &self,
+        older_than: Option<std::time::SystemTime>,
+        include_terminal: bool,
+    ) -> Result<WorkDirCleanup> {
+        let base_dir = self.dirs.work_dir()?;
+        let entries = match std::fs::read_dir(&base_dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(WorkDirCleanup::default());
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let mut cleanup = WorkDirCleanup::default();
+        for entry in entries {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_dir() {
+                continue;
+            }
+            if let Some(cutoff) = older_than
+                && metadata.modified()? > cutoff
+            {
+                continue;
+            }
+            // Execution dirs are named by id; anything else (e.g. health check temp dirs) is skipped
+            let id = entry.file_name().to_string_lossy().into_owned();
+            if uuid::Uuid::parse_str(&id).is_err() {
+                continue;
+            }
+            // Live executions and pending previews keep their dir regardless of age
+            match self.exec_repo.get(&id).await {
+                Ok(execution) if !execution.status.is_terminal() || !include_terminal => continue,
+                Ok(_) | Err(AppError::ExecutionNotFound(_)) => {}
+                Err(err) => return Err(err),
+            }
+            let bytes = paths::dir_size(&entry.path())?;
+            match std::fs::remove_dir_all(entry.path()) {
+                Ok(()) => {
+                    cleanup.removed_dirs += 1;
+                    cleanup.reclaimed_bytes += bytes;
+                }
+                Err(err) => tracing::warn!(
+                    "Failed to remove work dir {}: {}",
+                    entry.path().display(),
+                    err
+                ),
+            }
+        }
+        Ok(cleanup)
+    }
+
     pub async fn wait_for_states(
         &self,
         id: &str,
@@ -176,7 +705,7 @@ impl ExecutionService {
         let deadline = Utc::now().timestamp_millis() + timeout_ms as i64;
         loop {
             let current = self.exec_repo.get(id).await?;
-            if targets.iter().any(|t| *t == current.status) {
+            if targets.contains(&current.status) {
                 return Ok(current);
             }
             if Utc::now().timestamp_millis() > deadline {
@@ -194,6 +723,11 @@ impl ExecutionService {
             // TODO: Implement proper process management
             tracing::info!("Stopping execution {} with pid {}", id, pid);
         }
+        if let Some(container_executor) = &self.container_executor {
+            container_executor.stop(id);
+        }
+        // A queued execution never starts once it leaves the queue
+        self.queue.remove(id);
 
         self.exec_repo
             .update_status(id, ExecutionStatus::Stopped)
@@ -202,29 +736,261 @@ impl ExecutionService {
         Ok(())
     }
 
+    /// Marks executions a previous process left unfinished as failed; run at
+    /// startup, before anything new is started.
+    pub async fn recover_interrupted(&self) -> Result<u64> {
+        self.exec_repo.fail_interrupted().await
+    }
+
+    /// Kills running plugin processes and waits up to `grace` for their
+    /// results to be recorded. Queued executions stay pending.
+    pub async fn shutdown(&self, grace: Duration) {
+        self.stopping.send_replace(true);
+        let deadline = tokio::time::Instant::now() + grace;
+        while tokio::time::Instant::now() < deadline {
+            match self.exec_repo.count_running().await {
+                Ok(0) | Err(_) => return,
+                Ok(_) => sleep(Duration::from_millis(100)).await,
+            }
+        }
+        tracing::warn!("Running executions did not finish within {:?}", grace);
+    }
+
+    /// Runs the plugin's declared health check without recording an execution.
+    pub async fn health_check(&self, plugin_id: &str) -> Result<HealthCheckResult> {
+        let plugin = self.plugin_repo.get(plugin_id).await?;
+        let Some(health_check) = plugin.settings()?.health_check.clone() else {
+            return Err(AppError::Execution(format!(
+                "Plugin {} does not declare a health check",
+                plugin_id
+            )));
+        };
+        let timeout = Duration::from_millis(
+            health_check
+                .timeout_ms
+                .unwrap_or(DEFAULT_HEALTH_CHECK_TIMEOUT_MS)
+                .clamp(1, MAX_HEALTH_CHECK_TIMEOUT_MS),
+        );
+        let mut target = plugin.clone();
+        if let Some(script) = health_check.script {
+            target.entry_point = script;
+        }
+
+        let base_dir = self.dirs.work_dir()?;
+        std::fs::create_dir_all(&base_dir)?;
+        let work_dir = tempfile::Builder::new()
+            .prefix("healthcheck_")
+            .tempdir_in(&base_dir)?;
+        let mut env = HashMap::new();
+        env.insert("ANTHILL_PHASE".to_string(), "healthcheck".to_string());
+        let env = self.child_env(&plugin, env)?;
+
+        let mut result = HealthCheckResult {
+            plugin_id: plugin.plugin_id.clone(),
+            passed: false,
+            exit_code: None,
+            timed_out: false,
+            duration_ms: 0,
+            stdout: None,
+            stderr: None,
+            error: None,
+        };
+        let started = std::time::Instant::now();
+        let (child, container) = match self
+            .launch(&target, health_check.args, env, work_dir.path())
+            .await
+        {
+            Ok((_, child, container)) => (child, container),
+            Err(err) => {
+                // A missing runtime or entry point is exactly what the check should report
+                result.error = Some(err.to_string());
+                return Ok(result);
+            }
+        };
+
+        let outcome = wait_unrecorded(child, container, timeout).await?;
+        result.duration_ms = started.elapsed().as_millis() as u64;
+        result.timed_out = outcome.timed_out;
+        result.stdout = outcome.stdout;
+        result.stderr = outcome.stderr;
+        result.exit_code = outcome.exit_code;
+        result.passed = !result.timed_out && result.exit_code == Some(0);
+        if result.timed_out {
+            result.error = Some(format!(
+                "Health check timed out after {} ms",
+                timeout.as_millis()
+            ));
+        }
+        tracing::info!(
+            plugin_id = %plugin.plugin_id,
+            passed = result.passed,
+            exit_code = ?result.exit_code,
+            duration_ms = result.duration_ms,
+            "Plugin health check finished"
+        );
+        Ok(result)
+    }
+
+    /// Starts the plugin's interpreter once so later runs find warm caches.
+    /// Python plugins import `metadata.warm_imports`; other types just start and exit.
+    pub async fn warm_plugin(&self, plugin_id: &str) -> Result<WarmResult> {
+        let plugin = self.plugin_repo.get(plugin_id).await?;
+        if !plugin.enabled {
+            return Err(AppError::PluginDisabled);
+        }
+        self.ensure_plugin_type_enabled(&plugin)?;
+
+        let base_dir = self.dirs.work_dir()?;
+        std::fs::create_dir_all(&base_dir)?;
+        let work_dir = tempfile::Builder::new()
+            .prefix("warm_")
+            .tempdir_in(&base_dir)?;
+        // The warm-up script lives in the scratch dir; the entry point never runs
+        let (script_name, script) = match plugin.plugin_type {
+            crate::models::PluginType::Python => {
+                let mut script = format!(
+                    "import sys\nsys.path.insert(0, {})\n",
+                    serde_json::to_string(&plugin.plugin_path).map_err(|e| {
+                        AppError::Execution(format!("Failed to build warm-up script: {}", e))
+                    })?
+                );
+                for module in &plugin.settings()?.warm_imports {
+                    script.push_str(&format!("import {}\n", module));
+                }
+                ("warm.py", script)
+            }
+            crate::models::PluginType::JavaScript => ("warm.js", String::new()),
+            crate::models::PluginType::Shell => ("warm.sh", String::new()),
+        };
+        std::fs::write(work_dir.path().join(script_name), script)?;
+        let mut target = plugin.clone();
+        target.plugin_path = work_dir.path().to_string_lossy().to_string();
+        target.entry_point = script_name.to_string();
+
+        let mut env = HashMap::new();
+        env.insert("ANTHILL_PHASE".to_string(), "warm".to_string());
+        let env = self.child_env(&plugin, env)?;
+
+        let mut result = WarmResult {
+            plugin_id: plugin.plugin_id.clone(),
+            warmed: false,
+            exit_code: None,
+            timed_out: false,
+            duration_ms: 0,
+            stderr: None,
+            error: None,
+        };
+        let started = std::time::Instant::now();
+        let (child, container) = match self.launch(&target, Vec::new(), env, work_dir.path()).await
+        {
+            Ok((_, child, container)) => (child, container),
+            Err(err) => {
+                result.error = Some(err.to_string());
+                return Ok(result);
+            }
+        };
+
+        let outcome =
+            wait_unrecorded(child, container, Duration::from_millis(WARM_TIMEOUT_MS)).await?;
+        result.duration_ms = started.elapsed().as_millis() as u64;
+        result.timed_out = outcome.timed_out;
+        result.stderr = outcome.stderr;
+        result.exit_code = outcome.exit_code;
+        result.warmed = !result.timed_out && result.exit_code == Some(0);
+        if result.timed_out {
+            result.error = Some(format!("Warm-up timed out after {} ms", WARM_TIMEOUT_MS));
+        }
+        tracing::info!(
+            plugin_id = %plugin.plugin_id,
+            warmed = result.warmed,
+            exit_code = ?result.exit_code,
+            duration_ms = result.duration_ms,
+            "Plugin warm-up finished"
+        );
+        Ok(result)
+    }
+
+    /// Records the execution and spawns it; `options.params` is already folded into `env`.
     async fn start_process(
         &self,
         plugin: crate::models::Plugin,
         phase: ExecutionPhase,
-        success_status: ExecutionStatus,
         env: HashMap<String, String>,
-        cleanup_on_success: bool,
+        timeout: Option<Duration>,
+        options: ExecutionOptions,
     ) -> Result<Execution> {
-        let execution = self
+        let execution_id = options
+            .execution_id
+            .map(|id| {
+                uuid::Uuid::parse_str(id.trim())
+                    .map(|uuid| uuid.hyphenated().to_string())
+                    .map_err(|_| AppError::Execution(format!("Invalid execution id: {}", id)))
+            })
+            .transpose()?;
+        let idempotency_key = options
+            .idempotency_key
+            .map(|key| {
+                let key = key.trim();
+                if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+                    return Err(AppError::Execution(format!(
+                        "Idempotency key must be 1-{} characters",
+                        MAX_IDEMPOTENCY_KEY_LEN
+                    )));
+                }
+                Ok(key.to_string())
+            })
+            .transpose()?;
+        let (execution, created) = self
             .exec_repo
-            .create_with_phase(&plugin.plugin_id, phase)
+            .create_with_phase(NewExecution {
+                plugin_id: plugin.plugin_id.clone(),
+                phase,
+                id: execution_id,
+                idempotency_key,
+                dry_run: env.contains_key("ANTHILL_DRY_RUN"),
+                params: Self::recorded_params(&plugin, &env),
+                command: env.get("ANTHILL_COMMAND").cloned(),
+                callback_url: options.callback_url,
+                rate_limit: plugin.settings()?.rate_limit.clone(),
+            })
             .await?;
+        if !created {
+            return Ok(execution);
+        }
+        let (success_status, cleanup_on_success) = match phase {
+            ExecutionPhase::Prepare => (ExecutionStatus::PreviewReady, false),
+            ExecutionPhase::Apply => (ExecutionStatus::Completed, true),
+        };
         self.spawn_process(
             execution.clone(),
             plugin,
             success_status,
             env,
             cleanup_on_success,
+            timeout,
         )
         .await?;
+        let action = match phase {
+            ExecutionPhase::Prepare => "prepare",
+            ExecutionPhase::Apply => "execute",
+        };
+        self.audit_started(action, &execution).await;
         Ok(execution)
     }
 
+    async fn audit_started(&self, action: &str, execution: &Execution) {
+        if let Some(audit) = &self.audit {
+            let detail = serde_json::json!({
+                "execution_id": execution.id,
+                "dry_run": execution.dry_run,
+                "command": execution.command,
+            });
+            audit.record(action, &execution.plugin_id, detail).await;
+        }
+    }
+
+    /// Starts the plugin process now if a slot is free, otherwise queues it
+    /// and returns with the execution still pending.
     async fn spawn_process(
         &self,
         execution: Execution,
@@ -232,58 +998,315 @@ impl ExecutionService {
         success_status: ExecutionStatus,
         env: HashMap<String, String>,
         cleanup_on_success: bool,
+        timeout: Option<Duration>,
     ) -> Result<()> {
-        let work_dir = Self::work_dir_for(&execution.id)?;
-        std::fs::create_dir_all(&work_dir)?;
-
-        let exec_result = match plugin.plugin_type {
-            crate::models::PluginType::Python => {
-                self.python_executor
-                    .execute(&plugin, Vec::new(), env, &work_dir)
-                    .await
-            }
-            crate::models::PluginType::JavaScript => {
-                self.node_executor
-                    .execute(&plugin, Vec::new(), env, &work_dir)
-                    .await
-            }
+        let Some(permits) = self.queue.permits.clone() else {
+            self.run_process(
+                execution,
+                plugin,
+                success_status,
+                env,
+                cleanup_on_success,
+                timeout,
+            )
+            .await?;
+            return Ok(());
         };
+        if let Ok(permit) = permits.clone().try_acquire_owned() {
+            let monitor = self
+                .run_process(
+                    execution,
+                    plugin,
+                    success_status,
+                    env,
+                    cleanup_on_success,
+                    timeout,
+                )
+                .await?;
+            release_when_done(monitor, permit);
+            return Ok(());
+        }
 
-        let (pid, mut child) = match exec_result {
-            Ok(output) => output,
-            Err(err) => {
-                let _ = std::fs::remove_dir_all(&work_dir);
-                return Err(err);
+        tracing::info!(execution_id = %execution.id, "Execution queued");
+        self.queue.push(&execution.id);
+        let service = self.clone();
+        tokio::spawn(async move {
+            let Ok(permit) = permits.acquire_owned().await else {
+                return;
+            };
+            let id = execution.id.clone();
+            if !service.queue.remove(&id) {
+                // Stopped while waiting
+                return;
             }
-        };
-
-        self.exec_repo.update_pid(&execution.id, pid).await?;
-
-        let exec_id = execution.id.clone();
-        let exec_repo_clone = self.exec_repo.clone();
+            if *service.stopping.borrow() {
+                // Left pending; the next startup marks it failed
+                return;
+            }
+            match service
+                .run_process(
+                    execution,
+                    plugin,
+                    success_status,
+                    env,
+                    cleanup_on_success,
+                    timeout,
+                )
+                .await
+            {
+                Ok(monitor) => release_when_done(monitor, permit),
+                Err(err) => {
+                    let message = format!("Failed to start plugin process: {}", err);
+                    if let Err(err) = service
+                        .exec_repo
+                        .update_result(
+                            &id,
+                            None,
+                            Some(message),
+                            None,
+                            None,
+                            ExecutionStatus::Failed,
+                        )
+                        .await
+                    {
+                        tracing::error!("Failed to record queued execution failure: {}", err);
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Launches the plugin process and returns the task that monitors it
+    /// until its result is recorded.
+    async fn run_process(
+        &self,
+        execution: Execution,
+        plugin: crate::models::Plugin,
+        success_status: ExecutionStatus,
+        env: HashMap<String, String>,
+        cleanup_on_success: bool,
+        timeout: Option<Duration>,
+    ) -> Result<tokio::task::JoinHandle<()>> {
+        let span = tracing::info_span!(
+            "execution",
+            execution_id = %execution.id,
+            plugin_id = %plugin.plugin_id,
+            phase = ?execution.phase,
+        );
+        let work_dir = self.work_dir_for(&execution.id)?;
+        std::fs::create_dir_all(&work_dir)?;
+        let mut env = env;
+        // Large params can exceed env size limits, so plugins may opt into stdin
+        let stdin_payload = if plugin.settings()?.param_delivery == ParamDelivery::Stdin {
+            Some(
+                env.remove("ANTHILL_PLUGIN_PARAMS")
+                    .unwrap_or_else(|| "{}".to_string()),
+            )
+        } else {
+            None
+        };
+        let env = self.child_env(&plugin, env)?;
+
+        let exec_result = self
+            .launch(&plugin, Vec::new(), env, &work_dir)
+            .instrument(span.clone())
+            .await;
+
+        let (pid, mut child, mut container) = match exec_result {
+            Ok(output) => output,
+            Err(err) => {
+                span.in_scope(|| tracing::warn!("Failed to start plugin process: {}", err));
+                let _ = std::fs::remove_dir_all(&work_dir);
+                if let AppError::RuntimeMissing(reason) = &err
+                    && self.auto_disable_missing_runtime
+                {
+                    // Avoid failing every run at spawn; enabling the plugin again clears the reason
+                    match self
+                        .plugin_repo
+                        .disable_with_reason(&plugin.plugin_id, reason)
+                        .await
+                    {
+                        Ok(()) => span.in_scope(|| {
+                            tracing::warn!("Plugin disabled due to missing runtime: {}", reason)
+                        }),
+                        Err(disable_err) => span.in_scope(|| {
+                            tracing::error!("Failed to disable plugin: {}", disable_err)
+                        }),
+                    }
+                }
+                return Err(err);
+            }
+        };
+        span.in_scope(|| tracing::info!(pid, "Plugin process started"));
+        if let Some(mut stdin) = child.stdin.take()
+            && let Some(payload) = stdin_payload
+        {
+            // Write from a separate task so a plugin that is slow to read cannot stall output capture
+            tokio::spawn(
+                async move {
+                    use tokio::io::AsyncWriteExt;
+                    if let Err(e) = stdin.write_all(payload.as_bytes()).await {
+                        tracing::warn!("Failed to write params to plugin stdin: {}", e);
+                    }
+                }
+                .instrument(span.clone()),
+            );
+        }
+        let started = std::time::Instant::now();
+
+        self.exec_repo.update_pid(&execution.id, pid).await?;
+
+        let exec_id = execution.id.clone();
+        let exec_repo_clone = self.exec_repo.clone();
+        let callback_url = execution.callback_url.clone();
+        let allowed_hosts = self.allowed_hosts.clone();
         let keep_on_success =
             !cleanup_on_success && success_status == ExecutionStatus::PreviewReady;
+        let keep_on_failure = self.keep_work_dir_on_failure;
+        let limits = plugin
+            .settings()?
+            .resource_limits
+            .within(self.resource_limits);
 
-        tokio::spawn(async move {
-            let mut stdout_child = child.stdout.take();
-            let mut stderr_child = child.stderr.take();
-
-            let status_result = child.wait().await;
+        let flush_interval = Duration::from_millis(self.output_flush_ms.max(1));
+        let mut stopping = self.stopping.subscribe();
 
-            match status_result {
-                Ok(status) => {
-                    let exit_code = status.code();
+        let monitor = async move {
+            let (output_tx, mut output_rx) = tokio::sync::mpsc::unbounded_channel();
+            let mut forwarders = Vec::new();
+            if let Some(stdout) = child.stdout.take() {
+                forwarders.push(tokio::spawn(forward_output(
+                    stdout,
+                    OutputStream::Stdout,
+                    output_tx.clone(),
+                )));
+            }
+            if let Some(stderr) = child.stderr.take() {
+                forwarders.push(tokio::spawn(forward_output(
+                    stderr,
+                    OutputStream::Stderr,
+                    output_tx.clone(),
+                )));
+            }
+            drop(output_tx);
 
-                    use tokio::io::AsyncReadExt;
-                    let mut stdout_buf = String::new();
-                    let mut stderr_buf = String::new();
+            let mut stdout_buf = String::new();
+            let mut stderr_buf = String::new();
+            let mut stdout_truncated = false;
+            let mut stderr_truncated = false;
+            let mut dirty = false;
+            let mut streams_open = true;
+            let mut status_result = None;
+            let mut flush_ticker = tokio::time::interval(flush_interval);
+            flush_ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut timed_out = false;
+            let mut interrupted = false;
+            let deadline = async {
+                match timeout {
+                    Some(timeout) => sleep(timeout).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::pin!(deadline);
+            let stop_requested = async move {
+                if stopping.wait_for(|stopping| *stopping).await.is_err() {
+                    std::future::pending::<()>().await;
+                }
+            };
+            tokio::pin!(stop_requested);
+            // Armed once the process exits
+            let drain_deadline = sleep(Duration::ZERO);
+            tokio::pin!(drain_deadline);
 
-                    if let Some(ref mut stdout) = stdout_child {
-                        let _ = stdout.read_to_string(&mut stdout_buf).await;
+            while streams_open || status_result.is_none() {
+                tokio::select! {
+                    chunk = output_rx.recv(), if streams_open => match chunk {
+                        Some((OutputStream::Stdout, text)) => {
+                            append_output(&mut stdout_buf, &text, &mut stdout_truncated);
+                            dirty = true;
+                        }
+                        Some((OutputStream::Stderr, text)) => {
+                            append_output(&mut stderr_buf, &text, &mut stderr_truncated);
+                            dirty = true;
+                        }
+                        None => streams_open = false,
+                    },
+                    result = child.wait(), if status_result.is_none() => {
+                        status_result = Some(result);
+                        drain_deadline
+                            .as_mut()
+                            .reset(tokio::time::Instant::now() + OUTPUT_DRAIN_GRACE);
+                    }
+                    _ = &mut drain_deadline, if streams_open && status_result.is_some() => {
+                        tracing::warn!(
+                            "Output of execution {} still open after exit, likely held by a background process; ignoring the rest",
+                            exec_id
+                        );
+                        streams_open = false;
+                        for forwarder in &forwarders {
+                            forwarder.abort();
+                        }
                     }
-                    if let Some(ref mut stderr) = stderr_child {
-                        let _ = stderr.read_to_string(&mut stderr_buf).await;
+                    _ = &mut deadline, if !timed_out && status_result.is_none() => {
+                        timed_out = true;
+                        if let Err(e) = kill_process_tree(&mut child) {
+                            tracing::warn!("Failed to kill timed out execution {}: {}", exec_id, e);
+                        }
+                        if let Some(container) = &mut container {
+                            container.remove();
+                        }
+                        let timeout_ms = timeout.map(|timeout| timeout.as_millis()).unwrap_or_default();
+                        stderr_buf.push_str(&format!("Execution timed out after {} ms\n", timeout_ms));
+                        dirty = true;
                     }
+                    _ = &mut stop_requested, if !interrupted && status_result.is_none() => {
+                        // The process group would outlive the server otherwise
+                        interrupted = true;
+                        if let Err(e) = kill_process_tree(&mut child) {
+                            tracing::warn!("Failed to kill execution {} on shutdown: {}", exec_id, e);
+                        }
+                        if let Some(container) = &mut container {
+                            container.remove();
+                        }
+                        stderr_buf.push_str("Execution interrupted by server shutdown\n");
+                        dirty = true;
+                    }
+                    _ = flush_ticker.tick() => {
+                        if dirty {
+                            exec_repo_clone
+                                .update_output(
+                                    &exec_id,
+                                    non_empty(&stdout_buf),
+                                    non_empty(&stderr_buf),
+                                )
+                                .await
+                                .ok();
+                            dirty = false;
+                        }
+                    }
+                }
+            }
+
+            let status_result = status_result.expect("process status is set when the loop exits");
+            let killed = timed_out || interrupted;
+            if !killed && let Some(container) = &mut container {
+                container.exited();
+            }
+            let duration_ms = started.elapsed().as_millis() as u64;
+            if let Ok(status) = &status_result {
+                tracing::info!(
+                    exit_code = ?status.code(),
+                    exit_signal = ?exit_signal(status),
+                    timed_out,
+                    duration_ms,
+                    "Plugin process exited"
+                );
+            }
+
+            match status_result {
+                Ok(status) => {
+                    let exit_code = status.code();
 
                     let stdout = if !stdout_buf.is_empty() {
                         Some(stdout_buf)
@@ -291,13 +1314,19 @@ impl ExecutionService {
                         None
                     };
 
+                    if !killed && let Some(reason) = limit_exit_reason(&status, limits) {
+                        stderr_buf.push_str(&reason);
+                    }
                     let stderr = if !stderr_buf.is_empty() {
                         Some(stderr_buf)
                     } else {
                         None
                     };
 
-                    if exit_code == Some(0) && success_status == ExecutionStatus::PreviewReady {
+                    if !killed
+                        && exit_code == Some(0)
+                        && success_status == ExecutionStatus::PreviewReady
+                    {
                         let confirm_token = uuid::Uuid::new_v4().to_string();
                         let expires_at = Utc::now().timestamp_millis() + PREVIEW_TTL_MS;
                         exec_repo_clone
@@ -317,25 +1346,30 @@ impl ExecutionService {
                         return;
                     }
 
-                    let exec_status = if exit_code == Some(0) {
+                    let exec_status = if exit_code == Some(0) && !killed {
                         success_status
                     } else {
                         ExecutionStatus::Failed
                     };
 
                     exec_repo_clone
-                        .update_result(&exec_id, stdout, stderr, exit_code, exec_status)
+                        .update_result(
+                            &exec_id,
+                            stdout,
+                            stderr,
+                            exit_code,
+                            exit_signal(&status),
+                            exec_status,
+                        )
                         .await
                         .ok();
 
-                    if exit_code != Some(0) || cleanup_on_success {
-                        if let Err(e) = std::fs::remove_dir_all(&work_dir) {
-                            tracing::warn!(
-                                "Failed to remove work dir {}: {}",
-                                work_dir.display(),
-                                e
-                            );
-                        }
+                    let remove = match exec_status {
+                        ExecutionStatus::Failed => !keep_on_failure,
+                        _ => cleanup_on_success,
+                    };
+                    if remove && let Err(e) = std::fs::remove_dir_all(&work_dir) {
+                        tracing::warn!("Failed to remove work dir {}: {}", work_dir.display(), e);
                     }
                 }
                 Err(e) => {
@@ -346,26 +1380,267 @@ impl ExecutionService {
                             None,
                             Some(format!("Error: {}", e)),
                             None,
+                            None,
                             ExecutionStatus::Failed,
                         )
                         .await
                         .ok();
-                    if let Err(err) = std::fs::remove_dir_all(&work_dir) {
+                    if !keep_on_failure && let Err(err) = std::fs::remove_dir_all(&work_dir) {
                         tracing::warn!("Failed to remove work dir {}: {}", work_dir.display(), err);
                     }
                 }
             }
-        });
 
+            if let Some(url) = callback_url {
+                deliver_callback(&exec_repo_clone, &exec_id, &url, &allowed_hosts).await;
+            }
+        };
+        Ok(tokio::spawn(monitor.instrument(span)))
+    }
+
+    fn ensure_plugin_type_enabled(&self, plugin: &Plugin) -> Result<()> {
+        if self.enabled_plugin_types.is_empty()
+            || self.enabled_plugin_types.contains(&plugin.plugin_type)
+        {
+            return Ok(());
+        }
+        Err(AppError::Execution(format!(
+            "Plugin type {} is disabled on this server",
+            plugin.plugin_type.name()
+        )))
+    }
+
+    async fn launch(
+        &self,
+        plugin: &Plugin,
+        args: Vec<String>,
+        env: HashMap<String, String>,
+        work_dir: &Path,
+    ) -> Result<(u32, tokio::process::Child, Option<ContainerHandle>)> {
+        self.ensure_plugin_type_enabled(plugin)?;
+        if let Some(container_executor) = &self.container_executor {
+            let (pid, child) = container_executor
+                .execute(plugin, args, env, work_dir)
+                .await?;
+            return Ok((pid, child, Some(container_executor.handle(work_dir))));
+        }
+        let (pid, child) = match plugin.plugin_type {
+            crate::models::PluginType::Python => {
+                self.python_executor
+                    .execute(plugin, args, env, work_dir)
+                    .await?
+            }
+            crate::models::PluginType::JavaScript => {
+                self.node_executor
+                    .execute(plugin, args, env, work_dir)
+                    .await?
+            }
+            crate::models::PluginType::Shell => {
+                self.shell_executor
+                    .execute(plugin, args, env, work_dir)
+                    .await?
+            }
+        };
+        Ok((pid, child, None))
+    }
+
+    fn child_env(
+        &self,
+        plugin: &Plugin,
+        mut env: HashMap<String, String>,
+    ) -> Result<HashMap<String, String>> {
+        let state_dir = self.state_dir_for(&plugin.plugin_id)?;
+        std::fs::create_dir_all(&state_dir)?;
+        env.insert(
+            "ANTHILL_PLUGIN_STATE_DIR".to_string(),
+            state_dir.to_string_lossy().to_string(),
+        );
+        // Later sources win: host passthrough, then the env file, then plugin metadata,
+        // then ANTHILL_* vars
+        let mut child_env = self.passthrough_env();
+        child_env.extend(Self::env_file_vars(plugin)?);
+        child_env.extend(plugin.settings()?.env.clone());
+        child_env.extend(env);
+        Ok(child_env)
+    }
+
+    /// Variables from the plugin's `metadata.env_file`; empty when unset or absent.
+    fn env_file_vars(plugin: &Plugin) -> Result<HashMap<String, String>> {
+        let Some(env_file) = plugin.settings()?.env_file.as_deref() else {
+            return Ok(HashMap::new());
+        };
+        let plugin_dir = Path::new(&plugin.plugin_path);
+        // Canonicalize both sides so a symlinked env file cannot escape the plugin dir
+        let (Ok(plugin_dir), Ok(path)) = (
+            plugin_dir.canonicalize(),
+            plugin_dir.join(env_file).canonicalize(),
+        ) else {
+            return Ok(HashMap::new());
+        };
+        if !path.starts_with(&plugin_dir) || !path.is_file() {
+            tracing::warn!(
+                "Ignoring env file {} of plugin {}: not a file inside the plugin",
+                env_file,
+                plugin.plugin_id
+            );
+            return Ok(HashMap::new());
+        }
+
+        let iter = dotenvy::from_path_iter(&path).map_err(|err| {
+            AppError::Execution(format!("Failed to read env file {}: {}", env_file, err))
+        })?;
+        let mut vars = HashMap::new();
+        for item in iter {
+            let (key, value) = item.map_err(|err| {
+                AppError::Execution(format!("Failed to parse env file {}: {}", env_file, err))
+            })?;
+            if key.starts_with("ANTHILL_") {
+                tracing::warn!(
+                    "Ignoring reserved variable {} in env file of plugin {}",
+                    key,
+                    plugin.plugin_id
+                );
+                continue;
+            }
+            vars.insert(key, value);
+        }
+        Ok(vars)
+    }
+
+    /// Rejects callbacks outside `allowed_hosts` and unlisted IP literals that
+    /// are not public; hostnames are checked again once resolved at delivery.
+    fn validate_callback_url(&self, callback_url: Option<&str>) -> Result<()> {
+        let Some(url) = callback_url else {
+            return Ok(());
+        };
+        ensure_host_allowed(url, &self.allowed_hosts)?;
+        if let Ok(parsed) = reqwest::Url::parse(url)
+            && let Some(host) = parsed.host_str()
+            && let Ok(ip) = host.trim_matches(['[', ']']).parse::<IpAddr>()
+            && !host_in_allowlist(host, &self.allowed_hosts)
+            && !is_public_ip(ip)
+        {
+            return Err(AppError::Execution(format!(
+                "Callback host {} is not a public address; add it to allowed_hosts to allow it",
+                host
+            )));
+        }
         Ok(())
     }
 
-    fn work_dir_for(execution_id: &str) -> Result<PathBuf> {
-        let base_dir = paths::work_dir()?;
+    /// Points the plugin at the entry point of `command` and exports its name as
+    /// `ANTHILL_COMMAND`; `None` keeps the main entry point.
+    fn select_command(
+        mut plugin: Plugin,
+        command: Option<&str>,
+        env: &mut HashMap<String, String>,
+    ) -> Result<Plugin> {
+        let Some(name) = command else {
+            return Ok(plugin);
+        };
+        let Some(selected) = plugin.settings()?.commands.get(name).cloned() else {
+            return Err(AppError::Execution(format!(
+                "Plugin {} has no command '{}'",
+                plugin.plugin_id, name
+            )));
+        };
+        plugin.entry_point = selected.entry_point;
+        env.insert("ANTHILL_COMMAND".to_string(), name.to_string());
+        Ok(plugin)
+    }
+
+    fn resolve_timeout(plugin: &Plugin, requested_ms: Option<u64>) -> Option<Duration> {
+        let max_ms = plugin.max_timeout_ms.map(|max| max.max(1) as u64);
+        let timeout_ms = requested_ms
+            .or(plugin
+                .default_timeout_ms
+                .map(|default| default.max(1) as u64))
+            .or(max_ms)?;
+        let timeout_ms = match max_ms {
+            Some(max_ms) => timeout_ms.min(max_ms),
+            None => timeout_ms,
+        };
+        Some(Duration::from_millis(timeout_ms.max(1)))
+    }
+
+    fn work_dir_for(&self, execution_id: &str) -> Result<PathBuf> {
+        let base_dir = self.dirs.work_dir()?;
         Ok(base_dir.join(execution_id))
     }
 
+    fn passthrough_env(&self) -> HashMap<String, String> {
+        std::env::vars()
+            .filter(|(key, _)| {
+                self.env_passthrough
+                    .iter()
+                    .any(|pattern| match pattern.strip_suffix('*') {
+                        Some(prefix) => key.starts_with(prefix),
+                        None => key == pattern,
+                    })
+            })
+            .collect()
+    }
+
+    fn state_dir_for(&self, plugin_id: &str) -> Result<PathBuf> {
+        let base_dir = self.dirs.plugin_state_dir()?;
+        Ok(base_dir.join(plugin_id))
+    }
+
+    fn parameter_env(
+        plugin: &Plugin,
+        resolved_params: &HashMap<String, serde_json::Value>,
+    ) -> Result<HashMap<String, String>> {
+        let mut env = HashMap::new();
+        if resolved_params.is_empty() {
+            return Ok(env);
+        }
+
+        let params_json = serde_json::to_string(resolved_params)
+            .map_err(|e| AppError::Execution(format!("Failed to serialize parameters: {}", e)))?;
+        env.insert("ANTHILL_PLUGIN_PARAMS".to_string(), params_json);
+
+        if !plugin.settings()?.param_env {
+            return Ok(env);
+        }
+        let schema = Self::parse_parameters(&plugin.parameters)?;
+        for param in &schema {
+            let Some(value) = resolved_params.get(&param.name) else {
+                continue;
+            };
+            let Some(env_name) = param.env_var_name() else {
+                tracing::warn!(
+                    "Skipping env export for parameter '{}' of plugin {}",
+                    param.name,
+                    plugin.plugin_id
+                );
+                continue;
+            };
+            let rendered = match value {
+                serde_json::Value::String(text) => text.clone(),
+                serde_json::Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            env.insert(env_name, rendered);
+        }
+        Ok(env)
+    }
+
+    /// Params JSON to store on the execution, with secret values replaced by `"***"`.
+    fn recorded_params(plugin: &Plugin, env: &HashMap<String, String>) -> Option<String> {
+        let raw = env.get("ANTHILL_PLUGIN_PARAMS")?;
+        let mut params: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(raw).ok()?;
+        let schema = Self::parse_parameters(&plugin.parameters).unwrap_or_default();
+        for param in schema.iter().filter(|param| param.is_secret()) {
+            if let Some(value) = params.get_mut(&param.name) {
+                *value = serde_json::Value::String("***".to_string());
+            }
+        }
+        serde_json::to_string(&params).ok()
+    }
+
     fn resolve_parameters(
+        &self,
         raw_parameters: &Option<String>,
         provided: HashMap<String, serde_json::Value>,
     ) -> Result<HashMap<String, serde_json::Value>> {
@@ -413,6 +1688,7 @@ impl ExecutionService {
                 )));
             }
             Self::ensure_choice(schema_param, &value)?;
+            self.ensure_constraints(schema_param, &value)?;
             resolved.insert(name, value);
         }
 
@@ -422,8 +1698,9 @@ impl ExecutionService {
             }
             if let Some(default) = &param.default {
                 Self::ensure_choice(param, default)?;
+                self.ensure_constraints(param, default)?;
                 resolved.insert(param.name.clone(), default.clone());
-            } else {
+            } else if param.is_required() {
                 return Err(AppError::Execution(format!(
                     "Missing required parameter: {}",
                     param.name
@@ -465,10 +1742,7 @@ impl ExecutionService {
             return Ok(());
         }
 
-        if choices
-            .iter()
-            .any(|choice| matches_choice(choice, value))
-        {
+        if choices.iter().any(|choice| matches_choice(choice, value)) {
             return Ok(());
         }
 
@@ -478,6 +1752,51 @@ impl ExecutionService {
         )))
     }
 
+    fn ensure_constraints(&self, param: &PluginParameter, value: &serde_json::Value) -> Result<()> {
+        let Some(validation) = &param.validation else {
+            return Ok(());
+        };
+
+        if param.param_type.is_numeric()
+            && let Some(number) = value.as_f64()
+        {
+            if let Some(min) = validation.min
+                && number < min
+            {
+                return Err(AppError::Execution(format!(
+                    "Parameter '{}' must be >= {}",
+                    param.name, min
+                )));
+            }
+            if let Some(max) = validation.max
+                && number > max
+            {
+                return Err(AppError::Execution(format!(
+                    "Parameter '{}' must be <= {}",
+                    param.name, max
+                )));
+            }
+        }
+
+        if let (Some(pattern), Some(text)) = (self.patterns.get(validation), value.as_str()) {
+            let pattern = pattern.map_err(|e| {
+                AppError::Execution(format!(
+                    "Invalid pattern for parameter '{}': {}",
+                    param.name, e
+                ))
+            })?;
+            if !pattern.is_match(text) {
+                return Err(AppError::Execution(format!(
+                    "Parameter '{}' does not match pattern {}",
+                    param.name,
+                    validation.pattern.as_deref().unwrap_or_default()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
     fn ensure_min_anthill_version(required: &Option<String>) -> Result<()> {
         let Some(required) = required.as_deref() else {
             return Ok(());
@@ -523,3 +1842,896 @@ impl ExecutionService {
         Ok(parameters)
     }
 }
+
+/// Holds a concurrency slot until the execution's monitor task finishes.
+fn release_when_done(monitor: tokio::task::JoinHandle<()>, permit: OwnedSemaphorePermit) {
+    tokio::spawn(async move {
+        let _ = monitor.await;
+        drop(permit);
+    });
+}
+
+/// POSTs the final execution to `url`, retrying failed attempts with backoff.
+/// Redirects are not followed, and the connection is pinned to the addresses
+/// checked by [`resolve_callback_host`].
+async fn deliver_callback(
+    exec_repo: &ExecutionRepository,
+    id: &str,
+    url: &str,
+    allowed_hosts: &[String],
+) {
+    let (host, addrs) = match resolve_callback_host(url, allowed_hosts).await {
+        Ok(target) => target,
+        Err(err) => {
+            tracing::warn!("Refusing execution callback: {}", err);
+            return;
+        }
+    };
+    let execution = match exec_repo.get(id).await {
+        Ok(execution) => execution,
+        Err(err) => {
+            tracing::warn!("Failed to load execution for callback: {}", err);
+            return;
+        }
+    };
+    let payload = match serde_json::to_vec(&ExecutionResponse::from(execution)) {
+        Ok(payload) => payload,
+        Err(err) => {
+            tracing::warn!("Failed to serialize execution for callback: {}", err);
+            return;
+        }
+    };
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_millis(CALLBACK_TIMEOUT_MS))
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve_to_addrs(&host, &addrs)
+        .build()
+    {
+        Ok(client) => client,
+        Err(err) => {
+            tracing::warn!("Failed to build callback client: {}", err);
+            return;
+        }
+    };
+
+    for attempt in 1..=CALLBACK_ATTEMPTS {
+        let result = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(payload.clone())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(_) => {
+                tracing::info!(attempt, "Delivered execution callback");
+                return;
+            }
+            Err(err) if attempt < CALLBACK_ATTEMPTS => {
+                tracing::warn!(attempt, "Execution callback failed, retrying: {}", err);
+                sleep(Duration::from_secs(1 << (attempt - 1))).await;
+            }
+            Err(err) => tracing::warn!(attempt, "Giving up on execution callback: {}", err),
+        }
+    }
+}
+
+/// Resolves the callback host, refusing non-public addresses unless the host is
+/// listed in `allowed_hosts`. Every resolved address must pass, so a name that
+/// mixes public and private records is refused as a whole.
+async fn resolve_callback_host(
+    url: &str,
+    allowed_hosts: &[String],
+) -> Result<(String, Vec<SocketAddr>)> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| AppError::Execution(format!("Invalid URL {}: {}", url, e)))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| AppError::Execution(format!("Callback URL has no host: {}", url)))?
+        .to_string();
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.trim_matches(['[', ']']), port))
+        .await
+        .map_err(|e| AppError::Execution(format!("Failed to resolve {}: {}", host, e)))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(AppError::Execution(format!("{} did not resolve", host)));
+    }
+    if !host_in_allowlist(&host, allowed_hosts)
+        && let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip()))
+    {
+        return Err(AppError::Execution(format!(
+            "{} resolves to non-public address {}; add it to allowed_hosts to allow it",
+            host,
+            addr.ip()
+        )));
+    }
+    Ok((host, addrs))
+}
+
+/// Whether `ip` is routable on the public internet. Loopback, private,
+/// link-local (including the 169.254.169.254 metadata service), shared,
+/// unspecified, broadcast and multicast addresses are not.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || v6.is_unique_local()
+                || v6.is_unicast_link_local())
+        }
+    }
+}
+
+async fn forward_output<R>(
+    reader: R,
+    stream: OutputStream,
+    tx: tokio::sync::mpsc::UnboundedSender<(OutputStream, String)>,
+) where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    // Read whole lines so flushed output lands on newline boundaries, but cap
+    // the chunk size so a long unterminated line still shows up. A capped chunk
+    // can end inside a multibyte character, so undecoded bytes carry over.
+    let mut reader = tokio::io::BufReader::new(reader);
+    let mut pending = Vec::new();
+    loop {
+        let read = match (&mut reader)
+            .take(MAX_OUTPUT_CHUNK as u64)
+            .read_until(b'\n', &mut pending)
+            .await
+        {
+            Ok(read) => read,
+            Err(e) => {
+                tracing::warn!("Failed to read process output: {}", e);
+                0
+            }
+        };
+        let text = take_utf8(&mut pending, read == 0);
+        if !text.is_empty() && tx.send((stream, text)).is_err() {
+            break;
+        }
+        if read == 0 {
+            break;
+        }
+    }
+}
+
+/// Decodes and removes the valid UTF-8 prefix of `pending`, keeping a trailing
+/// incomplete character for the next read unless `eof`. Invalid bytes become U+FFFD.
+fn take_utf8(pending: &mut Vec<u8>, eof: bool) -> String {
+    let mut text = String::new();
+    let mut rest = pending.as_slice();
+    while !rest.is_empty() {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                text.push_str(valid);
+                rest = &[];
+            }
+            Err(err) => {
+                let (valid, after) = rest.split_at(err.valid_up_to());
+                text.push_str(&String::from_utf8_lossy(valid));
+                match err.error_len() {
+                    Some(len) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        rest = &after[len..];
+                    }
+                    None if eof => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        rest = &[];
+                    }
+                    None => {
+                        rest = after;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    let consumed = pending.len() - rest.len();
+    pending.drain(..consumed);
+    text
+}
+
+/// Appends `text` up to [`MAX_OUTPUT_BYTES`], adding a marker the first time
+/// output is cut.
+fn append_output(buf: &mut String, text: &str, truncated: &mut bool) {
+    if *truncated {
+        return;
+    }
+    let room = MAX_OUTPUT_BYTES.saturating_sub(buf.len());
+    if text.len() <= room {
+        buf.push_str(text);
+        return;
+    }
+    let mut end = room;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    buf.push_str(&text[..end]);
+    buf.push_str(&format!(
+        "\n[output truncated after {} bytes]\n",
+        MAX_OUTPUT_BYTES
+    ));
+    *truncated = true;
+}
+
+async fn read_output<R>(reader: Option<R>) -> String
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let Some(mut reader) = reader else {
+        return String::new();
+    };
+    let mut buf = Vec::new();
+    if let Err(e) = reader.read_to_end(&mut buf).await {
+        tracing::warn!("Failed to read process output: {}", e);
+    }
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+/// Waits for a health check or warm-up child, killing it once `timeout` passes.
+async fn wait_unrecorded(
+    mut child: tokio::process::Child,
+    mut container: Option<ContainerHandle>,
+    timeout: Duration,
+) -> Result<ProcessOutcome> {
+    drop(child.stdin.take());
+    let stdout = tokio::spawn(read_output(child.stdout.take()));
+    let stderr = tokio::spawn(read_output(child.stderr.take()));
+    let (status, timed_out) = match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(status) => {
+            if let Some(container) = &mut container {
+                container.exited();
+            }
+            (Some(status?), false)
+        }
+        Err(_) => {
+            let _ = kill_process_tree(&mut child);
+            if let Some(container) = &mut container {
+                container.remove();
+            }
+            let _ = child.wait().await;
+            (None, true)
+        }
+    };
+    Ok(ProcessOutcome {
+        exit_code: status.and_then(|status| status.code()),
+        timed_out,
+        stdout: non_empty(&stdout.await.unwrap_or_default()),
+        stderr: non_empty(&stderr.await.unwrap_or_default()),
+    })
+}
+
+fn non_empty(buf: &str) -> Option<String> {
+    if buf.is_empty() {
+        None
+    } else {
+        Some(buf.to_string())
+    }
+}
+
+/// Signal that terminated the process; always `None` off Unix.
+#[cfg(unix)]
+fn exit_signal(status: &std::process::ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: &std::process::ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Explains a signal death, pointing at the resource limit that likely caused it.
+#[cfg(unix)]
+fn limit_exit_reason(status: &std::process::ExitStatus, limits: ResourceLimits) -> Option<String> {
+    use std::os::unix::process::ExitStatusExt;
+
+    if limits == ResourceLimits::default() {
+        return None;
+    }
+    let signal = status.signal()?;
+    let reason = match (signal, limits.max_cpu_secs, limits.max_memory_mb) {
+        (libc::SIGXCPU, Some(secs), _) => {
+            format!("Execution exceeded the CPU time limit of {} s\n", secs)
+        }
+        (libc::SIGSEGV | libc::SIGABRT | libc::SIGBUS, _, Some(mb)) => format!(
+            "Process crashed with signal {}; it likely exceeded the memory limit of {} MB\n",
+            signal, mb
+        ),
+        _ => format!("Process was killed by signal {}\n", signal),
+    };
+    Some(reason)
+}
+
+#[cfg(not(unix))]
+fn limit_exit_reason(
+    _status: &std::process::ExitStatus,
+    _limits: ResourceLimits,
+) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::plugin_repository::tests::plugin;
+    use crate::services::PluginService;
+    use crate::services::plugin_service::tests as plugin_tests;
+
+    fn plugin_with_timeouts(
+        default_timeout_ms: Option<i64>,
+        max_timeout_ms: Option<i64>,
+    ) -> Plugin {
+        Plugin {
+            default_timeout_ms,
+            max_timeout_ms,
+            ..plugin("p1")
+        }
+    }
+
+    #[test]
+    fn resolve_timeout_prefers_the_request_then_the_default() {
+        let timeout = |default, max, requested| {
+            ExecutionService::resolve_timeout(&plugin_with_timeouts(default, max), requested)
+        };
+        assert_eq!(timeout(None, None, None), None);
+        assert_eq!(
+            timeout(None, None, Some(500)),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            timeout(Some(2_000), None, None),
+            Some(Duration::from_millis(2_000))
+        );
+        assert_eq!(
+            timeout(Some(2_000), None, Some(500)),
+            Some(Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn resolve_timeout_clamps_to_the_plugin_maximum() {
+        let timeout = |default, max, requested| {
+            ExecutionService::resolve_timeout(&plugin_with_timeouts(default, max), requested)
+        };
+        assert_eq!(
+            timeout(None, Some(1_000), None),
+            Some(Duration::from_millis(1_000))
+        );
+        assert_eq!(
+            timeout(None, Some(1_000), Some(5_000)),
+            Some(Duration::from_millis(1_000))
+        );
+        assert_eq!(
+            timeout(Some(5_000), Some(1_000), None),
+            Some(Duration::from_millis(1_000))
+        );
+        assert_eq!(timeout(None, None, Some(0)), Some(Duration::from_millis(1)));
+        assert_eq!(timeout(Some(0), None, None), Some(Duration::from_millis(1)));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timeout_kills_background_children_too() {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.args(["-c", "sleep 30 & wait"]);
+        cmd.stdout(std::process::Stdio::piped());
+        cmd.stderr(std::process::Stdio::piped());
+        crate::executor::isolate_process_group(&mut cmd);
+        let child = cmd.spawn().unwrap();
+
+        let started = std::time::Instant::now();
+        let outcome = wait_unrecorded(child, None, Duration::from_millis(200))
+            .await
+            .unwrap();
+        assert!(outcome.timed_out);
+        // Output only reaches EOF once the backgrounded sleep is gone as well
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    async fn forwarded(input: &[u8]) -> Vec<String> {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        forward_output(input, OutputStream::Stdout, tx).await;
+        let mut chunks = Vec::new();
+        while let Some((_, text)) = rx.recv().await {
+            chunks.push(text);
+        }
+        chunks
+    }
+
+    #[tokio::test]
+    async fn forward_output_keeps_characters_split_by_the_chunk_cap() {
+        let mut input = "a".repeat(MAX_OUTPUT_CHUNK - 1);
+        input.push_str("é€\n");
+        let chunks = forwarded(input.as_bytes()).await;
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), input);
+    }
+
+    #[tokio::test]
+    async fn forward_output_replaces_invalid_and_truncated_bytes() {
+        let chunks = forwarded(b"ok\xff\n\xe2\x82").await;
+        assert_eq!(chunks.concat(), "ok\u{fffd}\n\u{fffd}");
+    }
+
+    #[test]
+    fn take_utf8_keeps_an_incomplete_tail() {
+        let mut pending = "x€".as_bytes()[..3].to_vec();
+        assert_eq!(take_utf8(&mut pending, false), "x");
+        assert_eq!(pending, "€".as_bytes()[..2]);
+        pending.push("€".as_bytes()[2]);
+        assert_eq!(take_utf8(&mut pending, false), "€");
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn append_output_caps_on_a_char_boundary() {
+        let mut buf = "a".repeat(MAX_OUTPUT_BYTES - 1);
+        let mut truncated = false;
+        append_output(&mut buf, "éb", &mut truncated);
+        assert!(truncated);
+        assert!(buf.starts_with(&"a".repeat(MAX_OUTPUT_BYTES - 1)));
+        assert!(buf.ends_with("[output truncated after 10485760 bytes]\n"));
+
+        let len = buf.len();
+        append_output(&mut buf, "more", &mut truncated);
+        assert_eq!(buf.len(), len);
+    }
+
+    #[tokio::test]
+    async fn parameter_patterns_are_compiled_once() {
+        let service = service().await;
+        let parameters = Some(
+            serde_json::json!([{
+                "name": "code",
+                "type": "string",
+                "validation": { "pattern": "[A-Z]{3}" }
+            }])
+            .to_string(),
+        );
+        let resolve = |value: &str| {
+            service.resolve_parameters(
+                &parameters,
+                HashMap::from([("code".to_string(), serde_json::json!(value))]),
+            )
+        };
+
+        assert!(resolve("ABC").is_ok());
+        assert!(resolve("ABCD").is_err());
+        assert!(resolve("abc").is_err());
+        let cached = service.patterns.0.lock().unwrap();
+        assert_eq!(cached.len(), 1);
+        assert!(cached.contains_key("[A-Z]{3}"));
+    }
+
+    #[test]
+    fn execution_queue_tracks_waiting_order() {
+        assert!(ExecutionQueue::new(0).permits.is_none());
+        let queue = ExecutionQueue::new(1);
+        queue.push("a");
+        queue.push("b");
+        queue.push("c");
+        assert_eq!(queue.position("b"), Some(2));
+        assert!(queue.remove("a"));
+        assert!(!queue.remove("a"));
+        assert_eq!(queue.position("b"), Some(1));
+        assert_eq!(queue.position("c"), Some(2));
+        assert_eq!(queue.position("a"), None);
+    }
+
+    #[test]
+    fn is_public_ip_rejects_internal_ranges() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.100.100.200",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(
+                !is_public_ip(ip.parse().unwrap()),
+                "{} should be internal",
+                ip
+            );
+        }
+        for ip in ["93.184.216.34", "2606:4700::1111"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{} should be public", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn callbacks_to_internal_hosts_need_an_allowlist_entry() {
+        for url in [
+            "http://127.0.0.1:9000/hook",
+            "http://localhost/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+        ] {
+            assert!(resolve_callback_host(url, &[]).await.is_err(), "{}", url);
+        }
+
+        let allowed = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+        let (host, addrs) = resolve_callback_host("http://localhost:9000/hook", &allowed)
+            .await
+            .unwrap();
+        assert_eq!(host, "localhost");
+        assert!(addrs.iter().all(|addr| addr.port() == 9000));
+        assert!(
+            resolve_callback_host("http://127.0.0.1/hook", &allowed)
+                .await
+                .is_ok()
+        );
+    }
+
+    async fn service() -> ExecutionService {
+        let pool = crate::repository::establish_connection(
+            "sqlite::memory:",
+            crate::repository::PoolSettings::default(),
+        )
+        .await
+        .unwrap();
+        ExecutionService::new(
+            ExecutionRepository::new(pool.clone()),
+            PluginRepository::new(pool),
+        )
+    }
+
+    /// Plugin and execution services over one in-memory database, keeping
+    /// their files in the returned temp dir.
+    async fn services() -> (PluginService, ExecutionService, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let dirs = paths::Dirs::under(dir.path());
+        let pool = crate::repository::establish_connection(
+            "sqlite::memory:",
+            crate::repository::PoolSettings::default(),
+        )
+        .await
+        .unwrap();
+        let plugin_repo = PluginRepository::new(pool.clone());
+        (
+            PluginService::new(plugin_repo.clone(), None).with_dirs(dirs.clone()),
+            ExecutionService::new(ExecutionRepository::new(pool), plugin_repo).with_dirs(dirs),
+            dir,
+        )
+    }
+
+    /// Waits until the process has finished, i.e. the execution is terminal or
+    /// a preview ready to apply.
+    async fn wait_until_finished(service: &ExecutionService, id: &str) -> Execution {
+        for _ in 0..200 {
+            let execution = service.get_execution(id).await.unwrap();
+            if execution.status.is_terminal() || execution.status == ExecutionStatus::PreviewReady {
+                return execution;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+        panic!("execution {} did not finish", id);
+    }
+
+    async fn run(
+        service: &ExecutionService,
+        plugin_id: &str,
+        params: HashMap<String, serde_json::Value>,
+    ) -> Execution {
+        let execution = service
+            .execute_plugin(
+                plugin_id,
+                ExecutionOptions {
+                    params,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        wait_until_finished(service, &execution.id).await
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn param_env_exports_each_parameter_alongside_the_json() {
+        let (plugins, executions, _dir) = services().await;
+        let mut spec = plugin_tests::shell_spec("param-env");
+        spec["parameters"] = serde_json::json!([
+            { "name": "count", "type": "integer" },
+            { "name": "label", "type": "string" },
+            { "name": "extra", "type": "json", "required": false }
+        ]);
+        spec["metadata"] = serde_json::json!({ "param_env": true });
+        plugin_tests::install_script(
+            &plugins,
+            &spec,
+            "echo \"count=$ANTHILL_PARAM_COUNT\"\necho \"label=$ANTHILL_PARAM_LABEL\"\necho \"extra=$ANTHILL_PARAM_EXTRA\"\necho \"json=$ANTHILL_PLUGIN_PARAMS\"\n",
+        )
+        .await
+        .unwrap();
+
+        let execution = run(
+            &executions,
+            "param-env",
+            HashMap::from([
+                ("count".to_string(), serde_json::json!(3)),
+                ("label".to_string(), serde_json::json!("a b")),
+                ("extra".to_string(), serde_json::json!({"k": [1]})),
+            ]),
+        )
+        .await;
+        assert_eq!(execution.status, ExecutionStatus::Completed);
+        let stdout = execution.stdout.unwrap_or_default();
+        assert!(stdout.contains("count=3\n"), "{}", stdout);
+        assert!(stdout.contains("label=a b\n"), "{}", stdout);
+        assert!(stdout.contains("extra={\"k\":[1]}\n"), "{}", stdout);
+        let json_line = stdout
+            .lines()
+            .find(|line| line.starts_with("json="))
+            .unwrap();
+        let params: serde_json::Value = serde_json::from_str(&json_line[5..]).unwrap();
+        assert_eq!(params["count"], 3);
+        assert_eq!(params["label"], "a b");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn partial_output_is_persisted_while_the_plugin_runs() {
+        let (plugins, executions, _dir) = services().await;
+        let executions = executions.with_output_flush_ms(100);
+        plugin_tests::install_script(
+            &plugins,
+            &plugin_tests::shell_spec("chatty"),
+            "echo first\nsleep 3\necho second\n",
+        )
+        .await
+        .unwrap();
+
+        let execution = executions
+            .execute_plugin("chatty", ExecutionOptions::default())
+            .await
+            .unwrap();
+        let started = std::time::Instant::now();
+        let mut partial = None;
+        while started.elapsed() < Duration::from_secs(2) {
+            let current = executions.get_execution(&execution.id).await.unwrap();
+            if current.stdout.as_deref() == Some("first\n") {
+                partial = Some(current);
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        let partial = partial.expect("first line was not persisted before the plugin exited");
+        assert_eq!(partial.status, ExecutionStatus::Running);
+        // Well within the 3 s sleep, so the flush interval rather than exit wrote it
+        assert!(
+            started.elapsed() < Duration::from_secs(1),
+            "{:?}",
+            started.elapsed()
+        );
+
+        let finished = wait_until_finished(&executions, &execution.id).await;
+        assert_eq!(finished.stdout.as_deref(), Some("first\nsecond\n"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn shutdown_kills_running_plugins_and_records_them() {
+        let (plugins, executions, _dir) = services().await;
+        let executions = executions.with_output_flush_ms(50);
+        plugin_tests::install_script(
+            &plugins,
+            &plugin_tests::shell_spec("lingering"),
+            "sleep 30 &\necho $!\nwait\n",
+        )
+        .await
+        .unwrap();
+
+        let execution = executions
+            .execute_plugin("lingering", ExecutionOptions::default())
+            .await
+            .unwrap();
+        let mut background_pid = None;
+        for _ in 0..100 {
+            let current = executions.get_execution(&execution.id).await.unwrap();
+            if let Some(pid) = current
+                .stdout
+                .as_deref()
+                .and_then(|out| out.trim().parse::<u32>().ok())
+            {
+                background_pid = Some(pid);
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        let background_pid = background_pid.expect("plugin did not report its background pid");
+
+        let started = std::time::Instant::now();
+        executions.shutdown(Duration::from_secs(5)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let stopped = executions.get_execution(&execution.id).await.unwrap();
+        assert_eq!(stopped.status, ExecutionStatus::Failed);
+        assert!(
+            stopped
+                .stderr
+                .as_deref()
+                .is_some_and(|stderr| stderr.contains("interrupted by server shutdown")),
+            "{:?}",
+            stopped.stderr
+        );
+        // Gone, or a zombie waiting for a reaper
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", background_pid));
+        assert!(
+            stat.as_deref().map_or(true, |stat| stat.contains(") Z ")),
+            "{:?}",
+            stat
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn state_dir_persists_between_runs() {
+        let (plugins, executions, _dir) = services().await;
+        plugin_tests::install_script(
+            &plugins,
+            &plugin_tests::shell_spec("stateful"),
+            "counter=\"$ANTHILL_PLUGIN_STATE_DIR/runs\"\nruns=$(cat \"$counter\" 2>/dev/null || echo 0)\nruns=$((runs + 1))\necho \"$runs\" > \"$counter\"\necho \"runs=$runs\"\n",
+        )
+        .await
+        .unwrap();
+
+        let first = run(&executions, "stateful", HashMap::new()).await;
+        assert_eq!(first.status, ExecutionStatus::Completed);
+        assert_eq!(first.stdout.unwrap_or_default(), "runs=1\n");
+
+        let second = run(&executions, "stateful", HashMap::new()).await;
+        assert_eq!(second.status, ExecutionStatus::Completed);
+        assert_eq!(second.stdout.unwrap_or_default(), "runs=2\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn prune_previews_revokes_expired_ones_and_their_work_dirs() {
+        let (plugins, executions, _dir) = services().await;
+        plugin_tests::install_script(
+            &plugins,
+            &plugin_tests::shell_spec("preview-prune"),
+            "echo planned\n",
+        )
+        .await
+        .unwrap();
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let execution = executions
+                .prepare_plugin("preview-prune", ExecutionOptions::default())
+                .await
+                .unwrap();
+            let execution = wait_until_finished(&executions, &execution.id).await;
+            assert_eq!(execution.status, ExecutionStatus::PreviewReady);
+            ids.push(execution.id);
+        }
+        // Expire the first two
+        for id in &ids[..2] {
+            executions
+                .exec_repo
+                .mark_preview_ready(id, None, None, Some(0), "token".to_string(), 1)
+                .await
+                .unwrap();
+        }
+
+        let listed: Vec<_> = executions
+            .list_previews()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|execution| execution.id)
+            .collect();
+        assert!(listed.contains(&ids[2]));
+        assert!(!listed.contains(&ids[0]) && !listed.contains(&ids[1]));
+
+        let mut pruned = executions.prune_previews(false).await.unwrap();
+        pruned.sort();
+        let mut expired = ids[..2].to_vec();
+        expired.sort();
+        assert_eq!(pruned, expired);
+        for id in &ids[..2] {
+            let execution = executions.get_execution(id).await.unwrap();
+            assert_eq!(execution.status, ExecutionStatus::Expired);
+            assert!(!executions.work_dir_for(id).unwrap().exists());
+        }
+        let active = executions.get_execution(&ids[2]).await.unwrap();
+        assert_eq!(active.status, ExecutionStatus::PreviewReady);
+        assert!(executions.work_dir_for(&ids[2]).unwrap().exists());
+
+        assert_eq!(
+            executions.prune_previews(true).await.unwrap(),
+            vec![ids[2].clone()]
+        );
+        let stopped = executions.get_execution(&ids[2]).await.unwrap();
+        assert_eq!(stopped.status, ExecutionStatus::Stopped);
+    }
+
+    #[tokio::test]
+    async fn validate_callback_url_rejects_internal_ip_literals() {
+        let service = service().await;
+        assert!(
+            service
+                .validate_callback_url(Some("http://10.0.0.5/hook"))
+                .is_err()
+        );
+        assert!(
+            service
+                .validate_callback_url(Some("http://[::1]/hook"))
+                .is_err()
+        );
+        assert!(
+            service
+                .validate_callback_url(Some("https://example.com/hook"))
+                .is_ok()
+        );
+        let service = service.with_allowed_hosts(vec!["10.0.0.5".to_string()]);
+        assert!(
+            service
+                .validate_callback_url(Some("http://10.0.0.5/hook"))
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_venv_disables_the_plugin_with_the_reason() {
+        let (_, service, _dir) = services().await;
+        let service = service.with_auto_disable_missing_runtime(true);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.py"), "print('hi')\n").unwrap();
+        let venv = dir.path().join("missing-venv");
+        service
+            .plugin_repo
+            .create(&Plugin {
+                plugin_type: PluginType::Python,
+                plugin_path: dir.path().to_string_lossy().to_string(),
+                entry_point: "main.py".to_string(),
+                python_venv_path: Some(venv.to_string_lossy().to_string()),
+                ..plugin("p1")
+            })
+            .await
+            .unwrap();
+
+        let err = service
+            .execute_plugin("demo", ExecutionOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::RuntimeMissing(_)), "{err:?}");
+
+        let plugin = service.plugin_repo.get("demo").await.unwrap();
+        assert!(!plugin.enabled);
+        let reason = plugin.disabled_reason.unwrap();
+        assert!(reason.starts_with("Python venv not found"), "{reason}");
+        assert!(matches!(
+            service
+                .execute_plugin("demo", ExecutionOptions::default())
+                .await,
+            Err(AppError::PluginDisabled)
+        ));
+    }
+}
@@ -1,7 +1,16 @@
+pub mod audit_service;
 pub mod execution_service;
 pub mod plugin_service;
+pub mod system_service;
 pub mod update_service;
 
-pub use execution_service::ExecutionService;
-pub use plugin_service::PluginService;
-pub use update_service::UpdateService;
+pub use audit_service::AuditService;
+pub use execution_service::{
+    ExecutionOptions, ExecutionService, HealthCheckResult, WarmResult, WorkDirCleanup,
+};
+pub use plugin_service::{
+    BatchInstallFailure, PackageValidation, PluginFiles, PluginService, PluginUsage,
+    PythonEnvStatus,
+};
+pub use system_service::{EffectiveConfig, SystemService};
+pub use update_service::{PendingStatus, UpdateImpact, UpdateService};
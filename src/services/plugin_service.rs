@@ -1,20 +1,29 @@
 use crate::error::{AppError, Result};
 use crate::models::{
-    Plugin, PluginParamType, PluginParameter, PluginParameterGroup, PluginType, PythonDependencies,
+    LastExecution, Plugin, PluginCommand, PluginHealthCheck, PluginMetadata, PluginParamType,
+    PluginParameter, PluginParameterGroup, PluginParameterValidation, PluginRateLimit, PluginType,
+    PluginVersion, PythonDependencies, ResourceLimits,
 };
 use crate::paths;
 use crate::repository::PluginRepository;
+use crate::services::AuditService;
 use chrono::Utc;
 use semver::Version;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::ffi::OsStr;
 use std::fs;
-use std::io::{Cursor, Read, Write};
+use std::io::{Cursor, Read, Seek};
 use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
 struct PackageMetadata {
+    // Checked on the raw JSON by ensure_schema_version; kept so it is not an unknown field
+    #[allow(unused)]
+    schema_version: Option<u32>,
     plugin_id: Option<String>,
     name: String,
     version: String,
@@ -26,6 +35,11 @@ struct PackageMetadata {
     parameters: Option<Vec<PluginParameter>>,
     groups: Option<Vec<PluginParameterGroup>>,
     metadata: Option<serde_json::Value>,
+    default_timeout_ms: Option<u64>,
+    max_timeout_ms: Option<u64>,
+    tags: Option<Vec<String>>,
+    #[serde(flatten)]
+    unknown_fields: std::collections::BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -34,24 +48,233 @@ enum PackageMetadataPayload {
     Multi {
         install_plugins: Vec<PackageMetadata>,
     },
-    Single(PackageMetadata),
+    Single(Box<PackageMetadata>),
+}
+
+/// A metadata document listing packages to install together.
+#[derive(Debug, Deserialize)]
+struct InstallManifest {
+    install_plugins: Vec<InstallManifestEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstallManifestEntry {
+    package_url: String,
+}
+
+#[derive(Debug)]
+pub struct BatchInstallFailure {
+    pub package_url: String,
+    pub error: AppError,
+}
+
+#[derive(Debug, Default)]
+pub struct BatchInstallReport {
+    pub installed: Vec<Plugin>,
+    pub failed: Vec<BatchInstallFailure>,
+    pub rolled_back: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct PackageValidation {
+    pub plugin_id: String,
+    pub name: String,
+    pub version: String,
+    pub min_anthill_version: Option<String>,
+    pub plugin_type: PluginType,
+    pub description: String,
+    pub author: String,
+    pub entry_point: String,
+    pub parameters: Option<Vec<PluginParameter>>,
+    pub groups: Option<Vec<PluginParameterGroup>>,
+    pub metadata: Option<serde_json::Value>,
+    pub tags: Vec<String>,
+    pub python_dependencies: Option<PythonDependencies>,
+    pub warnings: Vec<String>,
+}
+
+/// Highest `schema_version` of metadata.json this server understands.
+const METADATA_SCHEMA_VERSION: u32 = 1;
+const DEFAULT_RECYCLE_RETENTION_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+const DEFAULT_MAX_UPLOAD_BYTES: u64 = 256 * 1024 * 1024;
+const MAX_FILE_LISTING_DEPTH: usize = 8;
+const MAX_FILE_LISTING_ENTRIES: usize = 2000;
+const MAX_FILE_READ_BYTES: u64 = 1024 * 1024;
+const PYTHON_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Debug, Serialize)]
+pub struct PluginUsage {
+    pub plugin_bytes: u64,
+    pub venv_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Recorded vs current interpreter version of a Python plugin's venv.
+#[derive(Debug, Serialize)]
+pub struct PythonEnvStatus {
+    pub recorded_version: Option<String>,
+    /// `None` when the venv interpreter no longer runs.
+    pub current_version: Option<String>,
+    pub needs_rebuild: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PluginFiles {
+    pub plugin_id: String,
+    pub entries: Vec<PluginFileEntry>,
+    /// Set when the depth or entry cap cut the listing short.
+    pub truncated: bool,
+}
+
+/// One file or directory, with `path` relative to the plugin dir and `/`-separated.
+#[derive(Debug, Serialize)]
+pub struct PluginFileEntry {
+    pub path: String,
+    pub is_dir: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_symlink: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
 }
 
 #[derive(Clone)]
 pub struct PluginService {
     repo: PluginRepository,
     uv_path: Option<PathBuf>,
+    recycle_retention_ms: i64,
+    strict_metadata: bool,
+    strict_entry_point: bool,
+    max_upload_bytes: u64,
+    offline: bool,
+    max_plugins: u64,
+    allowed_hosts: Vec<String>,
+    enabled_plugin_types: Vec<PluginType>,
+    audit: Option<AuditService>,
+    plugin_locks: Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+    dirs: paths::Dirs,
 }
 
 impl PluginService {
     pub fn new(repo: PluginRepository, uv_path: Option<PathBuf>) -> Self {
-        Self { repo, uv_path }
+        Self {
+            repo,
+            uv_path,
+            recycle_retention_ms: DEFAULT_RECYCLE_RETENTION_MS,
+            strict_metadata: false,
+            strict_entry_point: false,
+            max_upload_bytes: DEFAULT_MAX_UPLOAD_BYTES,
+            offline: false,
+            max_plugins: 0,
+            allowed_hosts: Vec::new(),
+            enabled_plugin_types: Vec::new(),
+            audit: None,
+            plugin_locks: Arc::new(Mutex::new(HashMap::new())),
+            dirs: paths::Dirs::default(),
+        }
+    }
+
+    pub fn with_recycle_retention_ms(mut self, retention_ms: i64) -> Self {
+        self.recycle_retention_ms = retention_ms;
+        self
+    }
+
+    pub fn with_strict_metadata(mut self, strict_metadata: bool) -> Self {
+        self.strict_metadata = strict_metadata;
+        self
+    }
+
+    /// Rejects installs whose entry point does not match the plugin type, instead of warning.
+    pub fn with_strict_entry_point(mut self, strict_entry_point: bool) -> Self {
+        self.strict_entry_point = strict_entry_point;
+        self
+    }
+
+    pub fn with_max_upload_bytes(mut self, max_upload_bytes: u64) -> Self {
+        self.max_upload_bytes = max_upload_bytes;
+        self
+    }
+
+    /// Refuses remote package downloads and runs uv with `--offline`.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Caps installed plugins; 0 means unlimited.
+    pub fn with_max_plugins(mut self, max_plugins: u64) -> Self {
+        self.max_plugins = max_plugins;
+        self
+    }
+
+    /// Plugin types that may be installed; empty allows all.
+    pub fn with_enabled_plugin_types(mut self, plugin_types: Vec<PluginType>) -> Self {
+        self.enabled_plugin_types = plugin_types;
+        self
+    }
+
+    pub fn with_allowed_hosts(mut self, allowed_hosts: Vec<String>) -> Self {
+        self.allowed_hosts = allowed_hosts;
+        self
+    }
+
+    pub fn with_audit(mut self, audit: AuditService) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Where plugins, their venvs, state and the recycle bin live.
+    pub fn with_dirs(mut self, dirs: paths::Dirs) -> Self {
+        self.dirs = dirs;
+        self
+    }
+
+    pub fn max_upload_bytes(&self) -> u64 {
+        self.max_upload_bytes
+    }
+
+    async fn audit(&self, action: &str, plugin_id: &str, detail: serde_json::Value) {
+        if let Some(audit) = &self.audit {
+            audit.record(action, plugin_id, detail).await;
+        }
+    }
+
+    /// Serializes install/update/removal of one plugin id; other ids are not blocked.
+    async fn lock_plugin(&self, plugin_id: &str) -> tokio::sync::OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.plugin_locks.lock().unwrap_or_else(|e| e.into_inner());
+            // Drop entries nobody holds or waits on
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(plugin_id.to_string()).or_default().clone()
+        };
+        lock.lock_owned().await
     }
 
     pub async fn list_plugins(&self) -> Result<Vec<Plugin>> {
         self.repo.list().await
     }
 
+    /// Number of installed plugins, excluding the recycle bin.
+    #[cfg(target_os = "windows")]
+    pub async fn count_plugins(&self) -> Result<u64> {
+        self.repo.count().await
+    }
+
+    pub async fn list_plugins_with_tag(&self, tag: &str) -> Result<Vec<Plugin>> {
+        let mut plugins = self.repo.list().await?;
+        plugins.retain(|plugin| plugin.has_tag(tag.trim()));
+        Ok(plugins)
+    }
+
+    pub async fn latest_executions(&self) -> Result<HashMap<String, LastExecution>> {
+        let mut latest = HashMap::new();
+        for execution in self.repo.latest_executions().await? {
+            latest
+                .entry(execution.plugin_id.clone())
+                .or_insert(execution);
+        }
+        Ok(latest)
+    }
+
     pub async fn get_plugin(&self, id: &str) -> Result<Plugin> {
         self.repo.get(id).await
     }
@@ -61,21 +284,149 @@ impl PluginService {
         self.repo.get_by_name(name).await
     }
 
-    pub async fn install_plugin(&self, package_url: String) -> Result<Plugin> {
-        let bytes = Self::fetch_bytes(&package_url, "package").await?;
-        self.install_plugin_from_bytes(bytes).await
+    pub async fn install_plugin(&self, package_url: String, force: bool) -> Result<Plugin> {
+        let plugin = self.install_package(&package_url, force).await?;
+        self.audit(
+            "install",
+            &plugin.plugin_id,
+            serde_json::json!({
+                "version": plugin.version,
+                "package_url": package_url,
+                "force": force,
+            }),
+        )
+        .await;
+        Ok(plugin)
+    }
+
+    async fn install_package(&self, package_url: &str, force: bool) -> Result<Plugin> {
+        let bytes = self.fetch_bytes(package_url, "package").await?;
+        let plugin_id = Self::package_plugin_id(Cursor::new(&bytes))?;
+        let _guard = self.lock_plugin(&plugin_id).await;
+        if !force {
+            return self.install_plugin_from_archive(Cursor::new(bytes)).await;
+        }
+
+        let Ok(existing) = self.repo.get(&plugin_id).await else {
+            return self.install_plugin_from_archive(Cursor::new(bytes)).await;
+        };
+
+        // Keep the state dir: a forced reinstall repairs files, not plugin data
+        self.retire_plugin(&existing, false).await?;
+        let mut plugin = self.install_plugin_from_archive(Cursor::new(bytes)).await?;
+        if !existing.enabled {
+            match existing.disabled_reason.as_deref() {
+                Some(reason) => self.repo.disable_with_reason(&plugin_id, reason).await?,
+                None => self.repo.update_enabled(&plugin_id, false).await?,
+            }
+            plugin.enabled = false;
+            plugin.disabled_reason = existing.disabled_reason;
+        }
+        Ok(plugin)
+    }
+
+    pub async fn install_plugin_from_file(&self, path: &Path) -> Result<Plugin> {
+        let mut file = fs::File::open(path)?;
+        let plugin_id = Self::package_plugin_id(&mut file)?;
+        let _guard = self.lock_plugin(&plugin_id).await;
+        let plugin = self.install_plugin_from_archive(file).await?;
+        self.audit(
+            "install",
+            &plugin.plugin_id,
+            serde_json::json!({ "version": plugin.version, "source": "upload" }),
+        )
+        .await;
+        Ok(plugin)
     }
 
-    pub async fn update_plugin(&self, id: &str, package_url: String) -> Result<Plugin> {
+    /// Installs every package listed in a metadata document, in order.
+    ///
+    /// By default the first failure stops the batch and uninstalls the plugins
+    /// it already installed, so either all plugins land or none do. With
+    /// `continue_on_error` every entry is attempted and successes are kept.
+    pub async fn install_from_metadata_url(
+        &self,
+        metadata_url: String,
+        continue_on_error: bool,
+    ) -> Result<BatchInstallReport> {
+        let bytes = self.fetch_bytes(&metadata_url, "metadata").await?;
+        let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(&bytes);
+        let manifest: InstallManifest = serde_json::from_slice(bytes)
+            .map_err(|e| AppError::Execution(format!("Invalid metadata document: {}", e)))?;
+        if manifest.install_plugins.is_empty() {
+            return Err(AppError::Execution(
+                "Metadata document lists no plugins".to_string(),
+            ));
+        }
+
+        let mut report = BatchInstallReport::default();
+        for entry in manifest.install_plugins {
+            let package_url = Self::resolve_package_url(&metadata_url, &entry.package_url)?;
+            match self.install_plugin(package_url.clone(), false).await {
+                Ok(plugin) => report.installed.push(plugin),
+                Err(error) => {
+                    tracing::warn!("Failed to install {}: {}", package_url, error);
+                    report
+                        .failed
+                        .push(BatchInstallFailure { package_url, error });
+                    if !continue_on_error {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !continue_on_error && !report.failed.is_empty() {
+            for plugin in std::mem::take(&mut report.installed).into_iter().rev() {
+                match self.uninstall_plugin(&plugin.plugin_id, false).await {
+                    Ok(()) => report.rolled_back.push(plugin.plugin_id),
+                    Err(err) => {
+                        tracing::error!("Failed to roll back plugin {}: {}", plugin.plugin_id, err);
+                        report.installed.push(plugin);
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Resolves a manifest entry relative to the manifest's own location.
+    fn resolve_package_url(metadata_url: &str, package_url: &str) -> Result<String> {
+        if package_url.contains("://") || Path::new(package_url).is_absolute() {
+            return Ok(package_url.to_string());
+        }
+        if metadata_url.starts_with("http://") || metadata_url.starts_with("https://") {
+            let base = reqwest::Url::parse(metadata_url)
+                .map_err(|e| AppError::Execution(format!("Invalid metadata URL: {}", e)))?;
+            let url = base
+                .join(package_url)
+                .map_err(|e| AppError::Execution(format!("Invalid package URL: {}", e)))?;
+            return Ok(url.to_string());
+        }
+        let base = Self::resolve_local_path(metadata_url).unwrap_or_default();
+        let dir = base.parent().unwrap_or_else(|| Path::new(""));
+        Ok(dir.join(package_url).to_string_lossy().to_string())
+    }
+
+    pub async fn update_plugin(
+        &self,
+        id: &str,
+        package_url: String,
+        allow_downgrade: bool,
+    ) -> Result<Plugin> {
+        let _guard = self.lock_plugin(id).await;
         let existing = self.repo.get(id).await?;
-        let bytes = Self::fetch_bytes(&package_url, "package").await?;
+        let bytes = self.fetch_bytes(&package_url, "package").await?;
         let temp_dir = tempfile::Builder::new()
             .prefix("plugin_update_")
             .tempdir()
             .map_err(|e| AppError::Execution(format!("Failed to create temp dir: {}", e)))?;
 
-        Self::extract_zip(&bytes, temp_dir.path(), None)?;
+        Self::extract_zip(Cursor::new(&bytes), temp_dir.path(), None)?;
         let (spec, metadata_dir) = Self::read_metadata_from_dir(temp_dir.path())?;
+        if let Some(warning) = self.check_unknown_fields(&spec)? {
+            tracing::warn!("{}", warning);
+        }
         let PackageMetadata {
             plugin_id,
             name,
@@ -88,6 +439,10 @@ impl PluginService {
             parameters,
             groups,
             metadata,
+            default_timeout_ms,
+            max_timeout_ms,
+            tags,
+            ..
         } = spec;
 
         let plugin_id = Self::normalize_plugin_id(plugin_id, &name)?;
@@ -102,20 +457,170 @@ impl PluginService {
                 "Entry point cannot be empty".to_string(),
             ));
         }
-        let _ = Self::parse_plugin_type(&plugin_type)?;
+        let plugin_type = self.parse_plugin_type(&plugin_type)?;
+        let _ = Self::validate_spec(parameters.as_deref(), metadata.as_ref())?;
         let _ = Self::validate_parameters(parameters)?;
         let _ = Self::validate_groups(groups)?;
         let _ = Self::serialize_metadata(metadata)?;
+        let _ = Self::normalize_tags(tags)?;
         let _ = Self::normalize_min_anthill_version(min_anthill_version)?;
-        let _ = Self::resolve_entry_point(&entry_point, temp_dir.path(), metadata_dir.as_deref())?;
-        Self::ensure_newer_version(&version, &existing.version)?;
+        Self::validate_timeouts(default_timeout_ms, max_timeout_ms)?;
+        let entry_point =
+            Self::resolve_entry_point(&entry_point, temp_dir.path(), metadata_dir.as_deref())?;
+        let _ = self.check_entry_point_type(plugin_type, &temp_dir.path().join(&entry_point))?;
+        Self::ensure_newer_version(&version, &existing.version, allow_downgrade)?;
+
+        // Keep the state dir so plugin data survives the upgrade
+        self.retire_plugin(&existing, false).await?;
+        let plugin = self.install_plugin_from_archive(Cursor::new(bytes)).await?;
+        self.audit(
+            "update",
+            id,
+            serde_json::json!({
+                "from_version": existing.version,
+                "to_version": plugin.version,
+                "package_url": package_url,
+            }),
+        )
+        .await;
+        Ok(plugin)
+    }
+
+    pub async fn validate_package(&self, package_url: String) -> Result<PackageValidation> {
+        let bytes = self.fetch_bytes(&package_url, "package").await?;
+        let (spec, metadata_dir) = Self::read_metadata_from_zip(Cursor::new(&bytes))?;
+        let unknown_fields_warning = self.check_unknown_fields(&spec)?;
+        let PackageMetadata {
+            plugin_id,
+            name,
+            version,
+            min_anthill_version,
+            plugin_type,
+            description,
+            author,
+            entry_point,
+            parameters,
+            groups,
+            metadata,
+            default_timeout_ms,
+            max_timeout_ms,
+            tags,
+            ..
+        } = spec;
+
+        let plugin_id = Self::normalize_plugin_id(plugin_id, &name)?;
+        if entry_point.trim().is_empty() {
+            return Err(AppError::Execution(
+                "Entry point cannot be empty".to_string(),
+            ));
+        }
+        let plugin_type = self.parse_plugin_type(&plugin_type)?;
+        let settings = Self::validate_spec(parameters.as_deref(), metadata.as_ref())?;
+        let _ = Self::validate_parameters(parameters.clone())?;
+        let _ = Self::validate_groups(groups.clone())?;
+        let _ = Self::serialize_metadata(metadata.clone())?;
+        let tags = Self::normalize_tags(tags)?;
+        let min_anthill_version = Self::normalize_min_anthill_version(min_anthill_version)?;
+        Self::validate_timeouts(default_timeout_ms, max_timeout_ms)?;
+
+        let temp_dir = tempfile::Builder::new()
+            .prefix("plugin_validate_")
+            .tempdir()
+            .map_err(|e| AppError::Execution(format!("Failed to create temp dir: {}", e)))?;
+        Self::extract_zip(
+            Cursor::new(&bytes),
+            temp_dir.path(),
+            metadata_dir.as_deref(),
+        )?;
+        let entry_point = Self::resolve_entry_point(&entry_point, temp_dir.path(), None)?;
+        Self::check_command_files(&settings.commands, temp_dir.path())?;
+        let entry_point_warning =
+            self.check_entry_point_type(plugin_type, &temp_dir.path().join(&entry_point))?;
+
+        let mut warnings: Vec<String> = unknown_fields_warning.into_iter().collect();
+        warnings.extend(entry_point_warning);
+        warnings.extend(
+            parameters
+                .iter()
+                .flatten()
+                .filter(|param| param.required == Some(true) && param.default.is_some())
+                .map(|param| {
+                    format!(
+                        "Parameter '{}' is required but has a default; the default is used when it is not provided",
+                        param.name
+                    )
+                }),
+        );
+        let mut python_dependencies = None;
+        if plugin_type == PluginType::Python {
+            python_dependencies =
+                Self::resolve_python_dependencies(temp_dir.path(), None, &entry_point);
+            if python_dependencies.is_none() {
+                warnings.push(
+                    "No pyproject.toml or requirements.txt found; the venv will be created without dependencies"
+                        .to_string(),
+                );
+            }
+        }
+        match self.repo.get(&plugin_id).await {
+            Ok(existing) => warnings.push(format!(
+                "Plugin '{}' is already installed with version {}",
+                plugin_id, existing.version
+            )),
+            Err(AppError::PluginNotFound(_)) => {}
+            Err(err) => return Err(err),
+        }
+
+        Ok(PackageValidation {
+            plugin_id,
+            name,
+            version,
+            min_anthill_version,
+            plugin_type,
+            description,
+            author,
+            entry_point,
+            parameters,
+            groups,
+            metadata,
+            tags,
+            python_dependencies,
+            warnings,
+        })
+    }
+
+    /// Removes the plugin; with `keep_history` its executions stay queryable.
+    pub async fn uninstall_plugin(&self, id: &str, keep_history: bool) -> Result<()> {
+        let _guard = self.lock_plugin(id).await;
+        if keep_history {
+            let plugin = self.repo.get(id).await?;
+            self.retire_plugin(&plugin, true).await?;
+        } else {
+            self.remove_plugin(id, true).await?;
+        }
+        self.audit(
+            "uninstall",
+            id,
+            serde_json::json!({ "keep_history": keep_history }),
+        )
+        .await;
+        Ok(())
+    }
 
-        self.uninstall_plugin(id).await?;
-        self.install_plugin_from_bytes(bytes).await
+    /// Removes the plugin's files but keeps its row as the parent of its
+    /// executions; installing the same id again takes the row over.
+    async fn retire_plugin(&self, plugin: &Plugin, remove_state: bool) -> Result<()> {
+        self.remove_plugin_files(plugin, remove_state)?;
+        self.repo.mark_history_only(&plugin.plugin_id).await
     }
 
-    pub async fn uninstall_plugin(&self, id: &str) -> Result<()> {
+    async fn remove_plugin(&self, id: &str, remove_state: bool) -> Result<()> {
         let plugin = self.repo.get(id).await?;
+        self.remove_plugin_files(&plugin, remove_state)?;
+        self.repo.delete(id).await
+    }
+
+    fn remove_plugin_files(&self, plugin: &Plugin, remove_state: bool) -> Result<()> {
         if !plugin.plugin_path.is_empty() {
             match fs::remove_dir_all(&plugin.plugin_path) {
                 Ok(_) => {}
@@ -123,28 +628,417 @@ impl PluginService {
                 Err(err) => return Err(err.into()),
             }
         }
-        if let Some(venv_path) = &plugin.python_venv_path {
-            if !venv_path.is_empty() {
-                match fs::remove_dir_all(venv_path) {
-                    Ok(_) => {}
-                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
-                    Err(err) => return Err(err.into()),
+        if let Some(venv_path) = &plugin.python_venv_path
+            && !venv_path.is_empty()
+        {
+            match fs::remove_dir_all(venv_path) {
+                Ok(_) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        match fs::remove_dir_all(self.dirs.container_deps_dir()?.join(&plugin.plugin_id)) {
+            Ok(_) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        if remove_state {
+            self.remove_state_dir(&plugin.plugin_id)?;
+        }
+        Ok(())
+    }
+
+    /// Decoded parameter schema of a plugin; empty when it declares none.
+    pub async fn plugin_parameters(&self, id: &str) -> Result<Vec<PluginParameter>> {
+        let plugin = self.repo.get(id).await?;
+        match plugin.parameters.as_deref().map(str::trim) {
+            None | Some("") => Ok(Vec::new()),
+            Some(raw) => serde_json::from_str(raw)
+                .map_err(|e| AppError::Execution(format!("Invalid plugin parameters: {}", e))),
+        }
+    }
+
+    pub async fn plugin_usage(&self, id: &str) -> Result<PluginUsage> {
+        let plugin = self.repo.get(id).await?;
+        tokio::task::spawn_blocking(move || {
+            let plugin_bytes = if plugin.plugin_path.is_empty() {
+                0
+            } else {
+                paths::dir_size(Path::new(&plugin.plugin_path))?
+            };
+            let venv_bytes = match plugin.python_venv_path.as_deref() {
+                Some(venv_path) if !venv_path.is_empty() => paths::dir_size(Path::new(venv_path))?,
+                _ => 0,
+            };
+            Ok(PluginUsage {
+                plugin_bytes,
+                venv_bytes,
+                total_bytes: plugin_bytes + venv_bytes,
+            })
+        })
+        .await
+        .map_err(|e| AppError::Execution(format!("Failed to compute disk usage: {}", e)))?
+    }
+
+    pub async fn plugin_files(&self, id: &str) -> Result<PluginFiles> {
+        let plugin = self.repo.get(id).await?;
+        tokio::task::spawn_blocking(move || {
+            let mut entries = Vec::new();
+            let mut truncated = false;
+            if !plugin.plugin_path.is_empty() {
+                Self::walk_plugin_files(
+                    Path::new(&plugin.plugin_path),
+                    "",
+                    0,
+                    &mut entries,
+                    &mut truncated,
+                )?;
+            }
+            Ok(PluginFiles {
+                plugin_id: plugin.plugin_id,
+                entries,
+                truncated,
+            })
+        })
+        .await
+        .map_err(|e| AppError::Execution(format!("Failed to list plugin files: {}", e)))?
+    }
+
+    /// Reads a UTF-8 text file from the plugin dir; binary and oversized files are rejected.
+    pub async fn read_plugin_file(&self, id: &str, path: &str) -> Result<(String, &'static str)> {
+        let plugin = self.repo.get(id).await?;
+        if path.trim().is_empty() {
+            return Err(AppError::PluginFileNotFound(path.to_string()));
+        }
+        Self::validate_entry_point(path).map_err(|_| {
+            AppError::InvalidRequest(format!(
+                "File path must be relative to the plugin dir without '..': {}",
+                path
+            ))
+        })?;
+        let plugin_dir = Path::new(&plugin.plugin_path);
+        // Canonicalize both sides so a symlink cannot point outside the plugin dir
+        let (Ok(plugin_dir), Ok(file_path)) = (
+            plugin_dir.canonicalize(),
+            plugin_dir.join(path).canonicalize(),
+        ) else {
+            return Err(AppError::PluginFileNotFound(path.to_string()));
+        };
+        if !file_path.starts_with(&plugin_dir) || !file_path.is_file() {
+            return Err(AppError::PluginFileNotFound(path.to_string()));
+        }
+        let size = tokio::fs::metadata(&file_path).await?.len();
+        if size > MAX_FILE_READ_BYTES {
+            return Err(AppError::InvalidRequest(format!(
+                "File '{}' is {} bytes; only files up to {} bytes can be read",
+                path, size, MAX_FILE_READ_BYTES
+            )));
+        }
+        let bytes = tokio::fs::read(&file_path).await?;
+        let content = match String::from_utf8(bytes) {
+            Ok(content) if !content.contains('\0') => content,
+            _ => {
+                return Err(AppError::InvalidRequest(format!(
+                    "File '{}' is not a text file",
+                    path
+                )));
+            }
+        };
+        // Markup a browser would render (HTML, SVG, XML) is served as plain text,
+        // since package contents are untrusted and share the API's origin
+        let content_type = match file_path
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("json") => "application/json",
+            Some("js" | "mjs" | "cjs") => "text/javascript; charset=utf-8",
+            Some("css") => "text/css; charset=utf-8",
+            Some("md") => "text/markdown; charset=utf-8",
+            _ => "text/plain; charset=utf-8",
+        };
+        Ok((content, content_type))
+    }
+
+    // Symlinks are listed but never followed, so the walk cannot leave the plugin dir.
+    fn walk_plugin_files(
+        dir: &Path,
+        prefix: &str,
+        depth: usize,
+        entries: &mut Vec<PluginFileEntry>,
+        truncated: &mut bool,
+    ) -> Result<()> {
+        let mut children = match fs::read_dir(dir) {
+            Ok(children) => children.collect::<std::io::Result<Vec<_>>>()?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        children.sort_by_key(|entry| entry.file_name());
+
+        for child in children {
+            if entries.len() >= MAX_FILE_LISTING_ENTRIES {
+                *truncated = true;
+                return Ok(());
+            }
+            let name = child.file_name().to_string_lossy().into_owned();
+            let path = if prefix.is_empty() {
+                name
+            } else {
+                format!("{}/{}", prefix, name)
+            };
+            let file_type = child.file_type()?;
+            let is_dir = file_type.is_dir();
+            let size = if file_type.is_file() {
+                Some(child.metadata()?.len())
+            } else {
+                None
+            };
+            entries.push(PluginFileEntry {
+                path: path.clone(),
+                is_dir,
+                is_symlink: file_type.is_symlink(),
+                size,
+            });
+            if is_dir {
+                if depth + 1 >= MAX_FILE_LISTING_DEPTH {
+                    *truncated = true;
+                } else {
+                    Self::walk_plugin_files(&child.path(), &path, depth + 1, entries, truncated)?;
                 }
             }
         }
-        self.repo.delete(id).await
+        Ok(())
+    }
+
+    pub async fn soft_delete_plugin(&self, id: &str) -> Result<()> {
+        let _guard = self.lock_plugin(id).await;
+        let plugin = self.repo.get(id).await?;
+        let recycled_dir = self.recycled_dir_for(&plugin.plugin_id)?;
+        if recycled_dir.exists() {
+            fs::remove_dir_all(&recycled_dir)?;
+        }
+        let plugin_path = Path::new(&plugin.plugin_path);
+        if !plugin.plugin_path.is_empty() && plugin_path.exists() {
+            if let Some(parent) = recycled_dir.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(plugin_path, &recycled_dir)?;
+        }
+        self.repo
+            .mark_deleted(id, Utc::now().timestamp_millis())
+            .await?;
+        self.audit(
+            "delete",
+            id,
+            serde_json::json!({ "version": plugin.version }),
+        )
+        .await;
+        Ok(())
+    }
+
+    pub async fn restore_plugin(&self, id: &str) -> Result<Plugin> {
+        let _guard = self.lock_plugin(id).await;
+        let plugin = self.repo.get_deleted(id).await?;
+        if self.is_recycle_expired(&plugin) {
+            self.purge_recycled_plugin(&plugin).await?;
+            return Err(AppError::PluginNotFound(id.to_string()));
+        }
+        if self.repo.get(id).await.is_ok() {
+            return Err(AppError::PluginAlreadyExists(id.to_string()));
+        }
+        self.ensure_plugin_capacity().await?;
+
+        let recycled_dir = self.recycled_dir_for(&plugin.plugin_id)?;
+        if recycled_dir.exists() && !plugin.plugin_path.is_empty() {
+            let plugin_path = Path::new(&plugin.plugin_path);
+            if plugin_path.exists() {
+                return Err(AppError::Execution(format!(
+                    "Cannot restore plugin '{}': {} already exists",
+                    id,
+                    plugin_path.display()
+                )));
+            }
+            if let Some(parent) = plugin_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(&recycled_dir, plugin_path)?;
+        }
+        self.repo.restore(id).await?;
+        let plugin = self.repo.get(id).await?;
+        self.audit(
+            "restore",
+            id,
+            serde_json::json!({ "version": plugin.version }),
+        )
+        .await;
+        Ok(plugin)
+    }
+
+    pub async fn purge_expired_recycled(&self) -> Result<usize> {
+        let cutoff = Utc::now().timestamp_millis() - self.recycle_retention_ms;
+        let expired = self.repo.list_deleted_before(cutoff).await?;
+        let mut purged = 0;
+        for plugin in &expired {
+            let _guard = self.lock_plugin(&plugin.plugin_id).await;
+            // Re-check under the lock in case it was restored meanwhile
+            let Ok(plugin) = self.repo.get_deleted(&plugin.plugin_id).await else {
+                continue;
+            };
+            if self.is_recycle_expired(&plugin) {
+                self.purge_recycled_plugin(&plugin).await?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+
+    fn is_recycle_expired(&self, plugin: &Plugin) -> bool {
+        plugin.deleted_at.is_some_and(|deleted_at| {
+            Utc::now().timestamp_millis() - deleted_at >= self.recycle_retention_ms
+        })
+    }
+
+    async fn purge_recycled_plugin(&self, plugin: &Plugin) -> Result<()> {
+        self.remove_recycled_files(plugin)?;
+        tracing::info!("Purged recycled plugin {}", plugin.plugin_id);
+        self.repo.delete(&plugin.plugin_id).await
+    }
+
+    fn remove_recycled_files(&self, plugin: &Plugin) -> Result<()> {
+        let recycled_dir = self.recycled_dir_for(&plugin.plugin_id)?;
+        match fs::remove_dir_all(&recycled_dir) {
+            Ok(_) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        if let Some(venv_path) = &plugin.python_venv_path
+            && !venv_path.is_empty()
+        {
+            match fs::remove_dir_all(venv_path) {
+                Ok(_) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        self.remove_state_dir(&plugin.plugin_id)
+    }
+
+    /// Deletes and recreates a Python plugin's venv from its stored dependencies.
+    pub async fn rebuild_python_env(&self, id: &str) -> Result<Plugin> {
+        let _guard = self.lock_plugin(id).await;
+        let mut plugin = self.repo.get(id).await?;
+        if plugin.plugin_type != PluginType::Python {
+            return Err(AppError::Execution(format!(
+                "Plugin {} is not a Python plugin",
+                id
+            )));
+        }
+        let dependencies = plugin
+            .python_dependencies
+            .as_deref()
+            .map(serde_json::from_str::<PythonDependencies>)
+            .transpose()
+            .map_err(|e| {
+                AppError::Execution(format!("Invalid stored python dependencies: {}", e))
+            })?;
+        let venv_dir = match plugin.python_venv_path.as_deref() {
+            Some(venv_path) if !venv_path.is_empty() => PathBuf::from(venv_path),
+            _ => self.python_env_dir_for(id)?,
+        };
+
+        if venv_dir.exists() {
+            fs::remove_dir_all(&venv_dir)?;
+        }
+        if let Err(err) = Self::prepare_python_env(
+            self.uv_path.as_deref(),
+            self.offline,
+            &venv_dir,
+            Path::new(&plugin.plugin_path),
+            dependencies.as_ref(),
+        )
+        .await
+        {
+            let _ = fs::remove_dir_all(&venv_dir);
+            return Err(err);
+        }
+
+        plugin.resolved_dependencies = self.freeze_python_env(&venv_dir).await;
+        plugin.python_version =
+            Self::probe_python_version(&Self::python_executable_path(&venv_dir)).await;
+        plugin.python_venv_path = Some(venv_dir.to_string_lossy().to_string());
+        plugin.updated_at = Utc::now().timestamp_millis();
+        self.repo.update(&plugin).await?;
+        tracing::info!("Rebuilt python env for plugin {}", id);
+        Ok(plugin)
+    }
+
+    /// Resolves the plugin's icon file and its content type.
+    pub async fn plugin_icon(&self, id: &str) -> Result<(PathBuf, &'static str)> {
+        let plugin = self.repo.get(id).await?;
+        let Some(icon) = plugin.settings()?.icon.as_deref() else {
+            return Err(AppError::IconNotFound(id.to_string()));
+        };
+        Self::validate_entry_point(icon).map_err(|_| AppError::IconNotFound(id.to_string()))?;
+        let plugin_dir = Path::new(&plugin.plugin_path);
+        // Canonicalize both sides so a symlinked icon cannot escape the plugin dir
+        let (Ok(plugin_dir), Ok(icon_path)) = (
+            plugin_dir.canonicalize(),
+            plugin_dir.join(icon).canonicalize(),
+        ) else {
+            return Err(AppError::IconNotFound(id.to_string()));
+        };
+        if !icon_path.starts_with(&plugin_dir) || !icon_path.is_file() {
+            return Err(AppError::IconNotFound(id.to_string()));
+        }
+        let content_type = match icon_path
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("png") => "image/png",
+            Some("jpg" | "jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
+            Some("svg") => "image/svg+xml",
+            Some("ico") => "image/x-icon",
+            _ => "application/octet-stream",
+        };
+        Ok((icon_path, content_type))
     }
 
     pub async fn enable_plugin(&self, id: &str) -> Result<()> {
-        self.repo.update_enabled(id, true).await
+        self.repo.update_enabled(id, true).await?;
+        self.audit("enable", id, serde_json::json!({})).await;
+        Ok(())
     }
 
     pub async fn disable_plugin(&self, id: &str) -> Result<()> {
-        self.repo.update_enabled(id, false).await
+        self.repo.update_enabled(id, false).await?;
+        self.audit("disable", id, serde_json::json!({})).await;
+        Ok(())
+    }
+
+    async fn ensure_plugin_capacity(&self) -> Result<()> {
+        if self.max_plugins == 0 {
+            return Ok(());
+        }
+        if self.repo.count().await? >= self.max_plugins {
+            return Err(AppError::PluginLimitReached(self.max_plugins));
+        }
+        Ok(())
     }
 
-    async fn install_plugin_from_bytes(&self, bytes: Vec<u8>) -> Result<Plugin> {
-        let (spec, metadata_dir) = Self::read_metadata_from_zip(&bytes)?;
+    async fn install_plugin_from_archive<R>(&self, mut archive: R) -> Result<Plugin>
+    where
+        R: Read + Seek + Send,
+    {
+        let package_sha256 = Self::sha256_hex(&mut archive)?;
+        let (spec, metadata_dir) = Self::read_metadata_from_zip(&mut archive)?;
+        if let Some(warning) = self.check_unknown_fields(&spec)? {
+            tracing::warn!("{}", warning);
+        }
         let PackageMetadata {
             plugin_id,
             name,
@@ -157,6 +1051,10 @@ impl PluginService {
             parameters,
             groups,
             metadata,
+            default_timeout_ms,
+            max_timeout_ms,
+            tags,
+            ..
         } = spec;
 
         let plugin_id = Self::normalize_plugin_id(plugin_id, &name)?;
@@ -165,6 +1063,14 @@ impl PluginService {
                 plugin_id.clone(),
             ));
         }
+        self.ensure_plugin_capacity().await?;
+        // The new install takes over the recycled row, keeping its executions. Its
+        // venv and state share paths with the new install, so move them aside until
+        // the new row is in place; a failed install puts them back
+        let recycled = match self.repo.get_deleted(&plugin_id).await {
+            Ok(recycled) => Some(RecycledRuntime::set_aside(self, &recycled)?),
+            Err(_) => None,
+        };
 
         if entry_point.trim().is_empty() {
             return Err(crate::error::AppError::Execution(
@@ -172,19 +1078,31 @@ impl PluginService {
             ));
         }
 
-        let plugin_type = Self::parse_plugin_type(&plugin_type)?;
+        let plugin_type = self.parse_plugin_type(&plugin_type)?;
+        let settings = Self::validate_spec(parameters.as_deref(), metadata.as_ref())?;
         let parameters_json = Self::validate_parameters(parameters)?;
         let groups_json = Self::validate_groups(groups)?;
         let metadata_json = Self::serialize_metadata(metadata)?;
+        let tags = Self::normalize_tags(tags)?;
+        let tags_json = if tags.is_empty() {
+            None
+        } else {
+            Some(
+                serde_json::to_string(&tags)
+                    .map_err(|e| AppError::Execution(format!("Failed to serialize tags: {}", e)))?,
+            )
+        };
         let min_anthill_version = Self::normalize_min_anthill_version(min_anthill_version)?;
+        let (default_timeout_ms, max_timeout_ms) =
+            Self::validate_timeouts(default_timeout_ms, max_timeout_ms)?;
 
         let internal_id = Uuid::new_v4().to_string();
-        let plugin_dir = Self::plugin_dir_for(&plugin_id)?;
+        let plugin_dir = self.plugin_dir_for(&plugin_id)?;
 
         fs::create_dir_all(&plugin_dir)?;
 
         let strip_prefix = metadata_dir.as_deref();
-        if let Err(err) = Self::extract_zip(&bytes, &plugin_dir, strip_prefix) {
+        if let Err(err) = Self::extract_zip(&mut archive, &plugin_dir, strip_prefix) {
             let _ = fs::remove_dir_all(&plugin_dir);
             return Err(err);
         }
@@ -203,11 +1121,25 @@ impl PluginService {
                     return Err(err);
                 }
             };
+        if let Err(err) = Self::check_command_files(&settings.commands, &plugin_dir) {
+            let _ = fs::remove_dir_all(&plugin_dir);
+            return Err(err);
+        }
+        match self.check_entry_point_type(plugin_type, &plugin_dir.join(&entry_point)) {
+            Ok(Some(warning)) => tracing::warn!("{}", warning),
+            Ok(None) => {}
+            Err(err) => {
+                let _ = fs::remove_dir_all(&plugin_dir);
+                return Err(err);
+            }
+        }
 
         let mut python_venv_path = None;
         let mut python_dependencies_json = None;
+        let mut resolved_dependencies = None;
+        let mut python_version = None;
         if plugin_type == PluginType::Python {
-            let venv_dir = Self::python_env_dir_for(&plugin_id)?;
+            let venv_dir = self.python_env_dir_for(&plugin_id)?;
             let resolved_deps = Self::resolve_python_dependencies(
                 &plugin_dir,
                 metadata_dir.as_deref(),
@@ -226,6 +1158,7 @@ impl PluginService {
             };
             if let Err(err) = Self::prepare_python_env(
                 self.uv_path.as_deref(),
+                self.offline,
                 &venv_dir,
                 &plugin_dir,
                 resolved_deps.as_ref(),
@@ -236,6 +1169,9 @@ impl PluginService {
                 let _ = fs::remove_dir_all(&venv_dir);
                 return Err(err);
             }
+            resolved_dependencies = self.freeze_python_env(&venv_dir).await;
+            python_version =
+                Self::probe_python_version(&Self::python_executable_path(&venv_dir)).await;
             python_venv_path = Some(venv_dir.to_string_lossy().to_string());
         }
 
@@ -259,8 +1195,18 @@ impl PluginService {
             metadata: metadata_json,
             python_venv_path,
             python_dependencies: python_dependencies_json,
+            deleted_at: None,
+            default_timeout_ms,
+            max_timeout_ms,
+            disabled_reason: None,
+            resolved_dependencies,
+            tags: tags_json,
+            python_version,
+            settings: Ok(settings).into(),
         };
 
+        fs::create_dir_all(self.state_dir_for(&plugin_id)?)?;
+
         if let Err(err) = self.repo.create(&plugin).await {
             let _ = fs::remove_dir_all(&plugin.plugin_path);
             if let Some(venv_path) = &plugin.python_venv_path {
@@ -268,16 +1214,82 @@ impl PluginService {
             }
             return Err(err);
         }
+        if let Some(recycled) = recycled
+            && let Err(err) = recycled.discard()
+        {
+            tracing::warn!("Failed to remove recycled copy of {}: {}", plugin_id, err);
+        }
+
+        let version = PluginVersion {
+            plugin_id: plugin.plugin_id.clone(),
+            version: plugin.version.clone(),
+            installed_at: now,
+            package_sha256,
+        };
+        if let Err(err) = self.repo.record_version(&version).await {
+            tracing::warn!(
+                "Failed to record version history for {}: {}",
+                plugin_id,
+                err
+            );
+        }
         Ok(plugin)
     }
 
-    fn plugin_dir_for(plugin_id: &str) -> Result<PathBuf> {
-        let base_dir = paths::plugins_dir()?;
+    pub async fn plugin_history(&self, id: &str) -> Result<Vec<PluginVersion>> {
+        let versions = self.repo.list_versions(id).await?;
+        if versions.is_empty() {
+            // Distinguish an unknown plugin from one installed before history was kept
+            self.repo.get(id).await?;
+        }
+        Ok(versions)
+    }
+
+    fn package_plugin_id<R: Read + Seek>(reader: R) -> Result<String> {
+        let (spec, _) = Self::read_metadata_from_zip(reader)?;
+        Self::normalize_plugin_id(spec.plugin_id, &spec.name)
+    }
+
+    fn sha256_hex<R: Read + Seek>(reader: &mut R) -> Result<String> {
+        reader.rewind()?;
+        let mut hasher = Sha256::new();
+        std::io::copy(reader, &mut hasher)?;
+        reader.rewind()?;
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
+
+    fn plugin_dir_for(&self, plugin_id: &str) -> Result<PathBuf> {
+        let base_dir = self.dirs.plugins_dir()?;
+        Ok(base_dir.join(plugin_id))
+    }
+
+    fn state_dir_for(&self, plugin_id: &str) -> Result<PathBuf> {
+        let base_dir = self.dirs.plugin_state_dir()?;
+        Ok(base_dir.join(plugin_id))
+    }
+
+    fn remove_state_dir(&self, plugin_id: &str) -> Result<()> {
+        match fs::remove_dir_all(self.state_dir_for(plugin_id)?) {
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn recycled_dir_for(&self, plugin_id: &str) -> Result<PathBuf> {
+        let base_dir = self.dirs.recycle_bin_dir()?;
         Ok(base_dir.join(plugin_id))
     }
 
-    fn extract_zip(bytes: &[u8], target_dir: &Path, strip_prefix: Option<&Path>) -> Result<()> {
-        let reader = Cursor::new(bytes);
+    fn extract_zip<R: Read + Seek>(
+        reader: R,
+        target_dir: &Path,
+        strip_prefix: Option<&Path>,
+    ) -> Result<()> {
         let mut archive = zip::ZipArchive::new(reader).map_err(|e| {
             crate::error::AppError::Execution(format!("Invalid zip archive: {}", e))
         })?;
@@ -327,16 +1339,15 @@ impl PluginService {
             }
 
             let mut outfile = fs::File::create(&out_path)?;
-            let mut buffer = Vec::new();
-            file.read_to_end(&mut buffer)?;
-            outfile.write_all(&buffer)?;
+            std::io::copy(&mut file, &mut outfile)?;
         }
 
         Ok(())
     }
 
-    fn read_metadata_from_zip(bytes: &[u8]) -> Result<(PackageMetadata, Option<PathBuf>)> {
-        let reader = Cursor::new(bytes);
+    fn read_metadata_from_zip<R: Read + Seek>(
+        reader: R,
+    ) -> Result<(PackageMetadata, Option<PathBuf>)> {
         let mut archive = zip::ZipArchive::new(reader)
             .map_err(|e| AppError::Execution(format!("Invalid zip archive: {}", e)))?;
 
@@ -375,19 +1386,7 @@ impl PluginService {
         let mut buffer = Vec::new();
         file.read_to_end(&mut buffer)?;
 
-        let payload: PackageMetadataPayload = serde_json::from_slice(&buffer)
-            .map_err(|e| AppError::Execution(format!("Invalid metadata JSON: {}", e)))?;
-        let spec = match payload {
-            PackageMetadataPayload::Single(spec) => spec,
-            PackageMetadataPayload::Multi { install_plugins } => {
-                if install_plugins.len() != 1 {
-                    return Err(AppError::Execution(
-                        "Package metadata must describe exactly one plugin".to_string(),
-                    ));
-                }
-                install_plugins.into_iter().next().unwrap()
-            }
-        };
+        let spec = Self::parse_metadata_spec(&buffer)?;
 
         let metadata_dir = metadata_path
             .as_deref()
@@ -434,10 +1433,96 @@ impl PluginService {
         let metadata_path = matches.remove(0);
         let buffer = fs::read(&metadata_path)
             .map_err(|e| AppError::Execution(format!("Failed to read metadata.json: {}", e)))?;
-        let payload: PackageMetadataPayload = serde_json::from_slice(&buffer)
+        let spec = Self::parse_metadata_spec(&buffer)?;
+
+        let metadata_dir = metadata_path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(root).ok())
+            .map(PathBuf::from)
+            .filter(|dir| !dir.as_os_str().is_empty());
+
+        Ok((spec, metadata_dir))
+    }
+
+    fn check_unknown_fields(&self, spec: &PackageMetadata) -> Result<Option<String>> {
+        if spec.unknown_fields.is_empty() {
+            return Ok(None);
+        }
+        let names = spec
+            .unknown_fields
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let message = format!("Unknown metadata field(s): {}", names);
+        if self.strict_metadata {
+            return Err(AppError::Execution(message));
+        }
+        Ok(Some(message))
+    }
+
+    /// Flags an entry point whose extension and shebang both don't fit the plugin type.
+    fn check_entry_point_type(
+        &self,
+        plugin_type: PluginType,
+        entry_path: &Path,
+    ) -> Result<Option<String>> {
+        let (extensions, interpreter, expected) = match plugin_type {
+            PluginType::Python => (&["py"][..], "python", ".py"),
+            PluginType::JavaScript => (&["js", "mjs", "cjs"][..], "node", ".js, .mjs or .cjs"),
+            PluginType::Shell => return Ok(None),
+        };
+        let extension = entry_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        if extension.is_some_and(|extension| extensions.contains(&extension.as_str())) {
+            return Ok(None);
+        }
+        if Self::read_shebang(entry_path).is_some_and(|line| line.contains(interpreter)) {
+            return Ok(None);
+        }
+
+        let message = format!(
+            "Entry point {} does not look like a {:?} file (expected {} or a #! line naming {})",
+            entry_path.file_name().unwrap_or_default().to_string_lossy(),
+            plugin_type,
+            expected,
+            interpreter
+        );
+        if self.strict_entry_point {
+            return Err(AppError::Execution(message));
+        }
+        Ok(Some(message))
+    }
+
+    fn read_shebang(path: &Path) -> Option<String> {
+        let mut buffer = [0u8; 256];
+        let read = fs::File::open(path).ok()?.read(&mut buffer).ok()?;
+        let head = buffer[..read].strip_prefix(b"#!")?;
+        let line = head.split(|byte| *byte == b'\n').next()?;
+        Some(String::from_utf8_lossy(line).into_owned())
+    }
+
+    fn parse_metadata_spec(buffer: &[u8]) -> Result<PackageMetadata> {
+        const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+        let buffer = buffer.strip_prefix(UTF8_BOM).unwrap_or(buffer);
+        let is_utf16 = buffer.starts_with(&[0xFF, 0xFE]) || buffer.starts_with(&[0xFE, 0xFF]);
+        if is_utf16 || buffer.contains(&0) || std::str::from_utf8(buffer).is_err() {
+            return Err(AppError::Execution(
+                "metadata.json must be UTF-8 (without BOM)".to_string(),
+            ));
+        }
+
+        // Check the schema before the typed parse so newer formats fail with a clear message
+        let raw: serde_json::Value = serde_json::from_slice(buffer)
+            .map_err(|e| AppError::Execution(format!("Invalid metadata JSON: {}", e)))?;
+        Self::ensure_schema_version(&raw)?;
+
+        let payload: PackageMetadataPayload = serde_json::from_value(raw)
             .map_err(|e| AppError::Execution(format!("Invalid metadata JSON: {}", e)))?;
         let spec = match payload {
-            PackageMetadataPayload::Single(spec) => spec,
+            PackageMetadataPayload::Single(spec) => *spec,
             PackageMetadataPayload::Multi { install_plugins } => {
                 if install_plugins.len() != 1 {
                     return Err(AppError::Execution(
@@ -447,17 +1532,40 @@ impl PluginService {
                 install_plugins.into_iter().next().unwrap()
             }
         };
+        Ok(spec)
+    }
 
-        let metadata_dir = metadata_path
-            .parent()
-            .and_then(|parent| parent.strip_prefix(root).ok())
-            .map(PathBuf::from)
-            .filter(|dir| !dir.as_os_str().is_empty());
-
-        Ok((spec, metadata_dir))
+    fn ensure_schema_version(raw: &serde_json::Value) -> Result<()> {
+        let spec = raw
+            .get("install_plugins")
+            .and_then(|plugins| plugins.get(0))
+            .unwrap_or(raw);
+        let schema_version = match spec.get("schema_version") {
+            None => 1,
+            Some(value) => value
+                .as_u64()
+                .filter(|version| *version >= 1)
+                .ok_or_else(|| {
+                    AppError::Execution(format!(
+                        "metadata.json schema_version must be a positive integer, got {}",
+                        value
+                    ))
+                })?,
+        };
+        if schema_version <= u64::from(METADATA_SCHEMA_VERSION) {
+            return Ok(());
+        }
+        let required = match spec.get("min_anthill_version").and_then(|v| v.as_str()) {
+            Some(version) => format!("anthill >= {}", version.trim()),
+            None => "a newer anthill".to_string(),
+        };
+        Err(AppError::Execution(format!(
+            "Package schema {} requires {} (this server supports schema {})",
+            schema_version, required, METADATA_SCHEMA_VERSION
+        )))
     }
 
-    async fn fetch_bytes(url: &str, label: &str) -> Result<Vec<u8>> {
+    async fn fetch_bytes(&self, url: &str, label: &str) -> Result<Vec<u8>> {
         if let Some(path) = Self::resolve_local_path(url) {
             let bytes = fs::read(&path).map_err(|e| {
                 AppError::Execution(format!(
@@ -469,6 +1577,13 @@ impl PluginService {
             })?;
             return Ok(bytes);
         }
+        if self.offline {
+            return Err(AppError::Execution(format!(
+                "Offline mode is enabled; cannot download {} from {}. Use a local path instead",
+                label, url
+            )));
+        }
+        ensure_host_allowed(url, &self.allowed_hosts)?;
 
         let response = reqwest::get(url)
             .await
@@ -505,12 +1620,17 @@ impl PluginService {
         Some(PathBuf::from(url))
     }
 
-    fn parse_plugin_type(raw: &str) -> Result<PluginType> {
-        match raw {
-            "python" => Ok(PluginType::Python),
-            "javascript" | "js" => Ok(PluginType::JavaScript),
-            _ => Err(AppError::InvalidPluginType),
+    fn parse_plugin_type(&self, raw: &str) -> Result<PluginType> {
+        let plugin_type = PluginType::from_name(raw).ok_or(AppError::InvalidPluginType)?;
+        if !self.enabled_plugin_types.is_empty()
+            && !self.enabled_plugin_types.contains(&plugin_type)
+        {
+            return Err(AppError::Execution(format!(
+                "Plugin type {} is disabled on this server",
+                plugin_type.name()
+            )));
         }
+        Ok(plugin_type)
     }
 
     fn validate_entry_point(entry_point: &str) -> Result<()> {
@@ -572,7 +1692,7 @@ impl PluginService {
         Ok(plugin_id.to_string())
     }
 
-    fn ensure_newer_version(candidate: &str, current: &str) -> Result<()> {
+    fn ensure_newer_version(candidate: &str, current: &str, allow_downgrade: bool) -> Result<()> {
         let candidate = candidate.trim();
         if candidate.is_empty() {
             return Err(AppError::Execution(
@@ -583,6 +1703,16 @@ impl PluginService {
         let candidate = Version::parse(candidate).map_err(|e| {
             AppError::Execution(format!("Invalid plugin version '{}': {}", candidate, e))
         })?;
+        if allow_downgrade {
+            if Version::parse(current).is_ok_and(|current| candidate <= current) {
+                tracing::warn!(
+                    "Replacing installed version {} with {} (downgrade allowed)",
+                    current,
+                    candidate
+                );
+            }
+            return Ok(());
+        }
         let current = Version::parse(current).map_err(|e| {
             AppError::Execution(format!(
                 "Invalid installed plugin version '{}': {}",
@@ -659,8 +1789,8 @@ impl PluginService {
         Ok(())
     }
 
-    fn python_env_dir_for(plugin_id: &str) -> Result<PathBuf> {
-        let base_dir = paths::python_envs_dir()?;
+    fn python_env_dir_for(&self, plugin_id: &str) -> Result<PathBuf> {
+        let base_dir = self.dirs.python_envs_dir()?;
         Ok(base_dir.join(plugin_id))
     }
 
@@ -673,10 +1803,10 @@ impl PluginService {
         if let Some(dir) = metadata_dir {
             Self::push_unique_dir(&mut search_dirs, dir.to_path_buf());
         }
-        if let Some(entry_dir) = Path::new(entry_point).parent() {
-            if !entry_dir.as_os_str().is_empty() {
-                Self::push_unique_dir(&mut search_dirs, entry_dir.to_path_buf());
-            }
+        if let Some(entry_dir) = Path::new(entry_point).parent()
+            && !entry_dir.as_os_str().is_empty()
+        {
+            Self::push_unique_dir(&mut search_dirs, entry_dir.to_path_buf());
         }
         Self::push_unique_dir(&mut search_dirs, PathBuf::new());
 
@@ -727,6 +1857,7 @@ impl PluginService {
 
     async fn prepare_python_env(
         uv_path: Option<&Path>,
+        offline: bool,
         venv_dir: &Path,
         plugin_dir: &Path,
         dependencies: Option<&PythonDependencies>,
@@ -736,7 +1867,11 @@ impl PluginService {
         }
 
         let venv_dir_str = venv_dir.to_string_lossy().to_string();
-        Self::run_uv_command(uv_path, &vec!["venv".to_string(), venv_dir_str], None).await?;
+        let mut venv_args = vec!["venv".to_string(), venv_dir_str];
+        if offline {
+            venv_args.push("--offline".to_string());
+        }
+        Self::run_uv_command(uv_path, &venv_args, None).await?;
 
         let python_path = Self::python_executable_path(venv_dir);
         if !python_path.is_file() {
@@ -757,6 +1892,9 @@ impl PluginService {
             "--python".to_string(),
             python_path_str,
         ];
+        if offline {
+            args.push("--offline".to_string());
+        }
         let current_dir = match dependencies {
             PythonDependencies::Requirements { path } => {
                 args.push("-r".to_string());
@@ -772,8 +1910,85 @@ impl PluginService {
             }
         };
 
-        Self::run_uv_command(uv_path, &args, current_dir.as_deref()).await?;
-        Ok(())
+        match Self::run_uv_command(uv_path, &args, current_dir.as_deref()).await {
+            Err(AppError::Execution(message)) if offline => Err(AppError::Execution(format!(
+                "Dependencies could not be satisfied offline; make sure they are in the uv cache ({})",
+                message
+            ))),
+            result => result.map(|_| ()),
+        }
+    }
+
+    /// Installed packages as reported by `uv pip freeze`, stored as a JSON array.
+    /// Failures only warn: the list is informational and must not block installs.
+    async fn freeze_python_env(&self, venv_dir: &Path) -> Option<String> {
+        let python_path = Self::python_executable_path(venv_dir);
+        let args = [
+            "pip".to_string(),
+            "freeze".to_string(),
+            "--python".to_string(),
+            python_path.to_string_lossy().to_string(),
+        ];
+        let stdout = match Self::run_uv_command(self.uv_path.as_deref(), &args, None).await {
+            Ok(stdout) => stdout,
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to list installed packages in {}: {}",
+                    venv_dir.display(),
+                    err
+                );
+                return None;
+            }
+        };
+        let packages = stdout
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect::<Vec<_>>();
+        serde_json::to_string(&packages).ok()
+    }
+
+    /// Compares the venv interpreter's version with the one recorded when it
+    /// was built; `None` for plugins without a venv.
+    pub async fn python_env_status(&self, plugin: &Plugin) -> Option<PythonEnvStatus> {
+        let venv_path = plugin
+            .python_venv_path
+            .as_deref()
+            .filter(|path| !path.is_empty())?;
+        let current_version =
+            Self::probe_python_version(&Self::python_executable_path(Path::new(venv_path))).await;
+        let needs_rebuild = match (&plugin.python_version, &current_version) {
+            (_, None) => true,
+            (Some(recorded), Some(current)) => recorded != current,
+            // Installed before versions were recorded; it still runs
+            (None, Some(_)) => false,
+        };
+        Some(PythonEnvStatus {
+            recorded_version: plugin.python_version.clone(),
+            current_version,
+            needs_rebuild,
+        })
+    }
+
+    /// Runs `python --version`, e.g. `Python 3.12.4` -> `3.12.4`.
+    async fn probe_python_version(python_path: &Path) -> Option<String> {
+        let mut cmd = tokio::process::Command::new(python_path);
+        cmd.arg("--version").kill_on_drop(true);
+        let output = tokio::time::timeout(PYTHON_PROBE_TIMEOUT, cmd.output())
+            .await
+            .ok()?
+            .ok()
+            .filter(|output| output.status.success())?;
+        // Python 2 printed the version to stderr
+        let text = if output.stdout.is_empty() {
+            output.stderr
+        } else {
+            output.stdout
+        };
+        let text = String::from_utf8_lossy(&text);
+        text.trim()
+            .strip_prefix("Python ")
+            .map(|version| version.trim().to_string())
     }
 
     fn python_executable_path(venv_dir: &Path) -> PathBuf {
@@ -788,7 +2003,7 @@ impl PluginService {
         uv_path: Option<&Path>,
         args: &[String],
         current_dir: Option<&Path>,
-    ) -> Result<()> {
+    ) -> Result<String> {
         let mut cmd = match uv_path {
             Some(path) => tokio::process::Command::new(path),
             None => tokio::process::Command::new("uv"),
@@ -797,12 +2012,15 @@ impl PluginService {
         if let Some(dir) = current_dir {
             cmd.current_dir(dir);
         }
-        let output = cmd.output().await.map_err(|e| {
-            crate::error::AppError::Execution(format!("Failed to run uv {}: {}", args.join(" "), e))
+        let output = cmd.output().await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::UvNotFound(
+                uv_path.map_or_else(|| "uv".to_string(), |path| path.display().to_string()),
+            ),
+            _ => AppError::Execution(format!("Failed to run uv {}: {}", args.join(" "), e)),
         })?;
 
         if output.status.success() {
-            return Ok(());
+            return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
         }
 
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -820,6 +2038,208 @@ impl PluginService {
         Err(crate::error::AppError::Execution(message))
     }
 
+    /// Parses and checks the feature settings under `metadata`; shared by install,
+    /// update and validation.
+    fn validate_spec(
+        parameters: Option<&[PluginParameter]>,
+        metadata: Option<&serde_json::Value>,
+    ) -> Result<PluginMetadata> {
+        let settings = PluginMetadata::from_value(metadata).map_err(AppError::Execution)?;
+        Self::validate_parameter_env(parameters, &settings)?;
+        Self::validate_metadata_env(&settings.env)?;
+        if let Some(health_check) = &settings.health_check {
+            Self::validate_health_check(health_check)?;
+        }
+        Self::validate_display(&settings)?;
+        Self::validate_warm_imports(&settings.warm_imports)?;
+        if let Some(rate_limit) = &settings.rate_limit {
+            Self::validate_rate_limit(rate_limit)?;
+        }
+        Self::validate_resource_limits(&settings.resource_limits)?;
+        if let Some(icon) = &settings.icon {
+            Self::validate_plugin_relative_path("metadata.icon", icon)?;
+        }
+        if let Some(env_file) = &settings.env_file {
+            Self::validate_plugin_relative_path("metadata.env_file", env_file)?;
+        }
+        Self::validate_commands(&settings.commands)?;
+        Ok(settings)
+    }
+
+    fn validate_parameter_env(
+        parameters: Option<&[PluginParameter]>,
+        settings: &PluginMetadata,
+    ) -> Result<()> {
+        if !settings.param_env {
+            return Ok(());
+        }
+
+        let mut seen = HashMap::new();
+        for param in parameters.unwrap_or_default() {
+            let Some(env_name) = param.env_var_name() else {
+                return Err(AppError::Execution(format!(
+                    "Parameter '{}' cannot be exported as an env var; use letters, digits and '_' only",
+                    param.name
+                )));
+            };
+            if let Some(other) = seen.insert(env_name.clone(), param.name.as_str()) {
+                return Err(AppError::Execution(format!(
+                    "Parameters '{}' and '{}' both map to env var {}",
+                    other, param.name, env_name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_metadata_env(env: &BTreeMap<String, String>) -> Result<()> {
+        for key in env.keys() {
+            if !crate::models::plugin::is_env_var_name(key) {
+                return Err(AppError::Execution(format!(
+                    "Invalid env var name in metadata.env: {}",
+                    key
+                )));
+            }
+            if key.starts_with("ANTHILL_") {
+                return Err(AppError::Execution(format!(
+                    "metadata.env cannot set reserved variable {}",
+                    key
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Used for `metadata.icon` and `metadata.env_file`.
+    fn validate_plugin_relative_path(field: &str, path: &str) -> Result<()> {
+        if path.trim().is_empty() {
+            return Err(AppError::Execution(format!(
+                "{} must be a non-empty path",
+                field
+            )));
+        }
+        Self::validate_entry_point(path).map_err(|_| {
+            AppError::Execution(format!(
+                "{} must be a relative path inside the plugin: {}",
+                field, path
+            ))
+        })
+    }
+
+    fn validate_rate_limit(rate_limit: &PluginRateLimit) -> Result<()> {
+        if rate_limit.max_executions == 0 {
+            return Err(AppError::Execution(
+                "metadata.rate_limit.max_executions must be greater than 0".to_string(),
+            ));
+        }
+        if rate_limit.window_secs == 0 {
+            return Err(AppError::Execution(
+                "metadata.rate_limit.window_secs must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_resource_limits(limits: &ResourceLimits) -> Result<()> {
+        if limits.max_memory_mb == Some(0) || limits.max_cpu_secs == Some(0) {
+            return Err(AppError::Execution(
+                "metadata.resource_limits values must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_display(settings: &PluginMetadata) -> Result<()> {
+        if settings
+            .category
+            .as_deref()
+            .is_some_and(|category| category.trim().is_empty())
+        {
+            return Err(AppError::Execution(
+                "metadata.category must be a non-empty string".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_warm_imports(modules: &[String]) -> Result<()> {
+        for module in modules {
+            let valid = module.split('.').all(|part| {
+                part.chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                    && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+            if !valid {
+                return Err(AppError::Execution(format!(
+                    "metadata.warm_imports entry '{}' is not a valid module name",
+                    module
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_commands(commands: &BTreeMap<String, PluginCommand>) -> Result<()> {
+        for (name, command) in commands {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(AppError::Execution(format!(
+                    "metadata.commands name '{}' may only contain letters, digits, '_' and '-'",
+                    name
+                )));
+            }
+            if command.entry_point.trim().is_empty() {
+                return Err(AppError::Execution(format!(
+                    "metadata.commands.{}.entry_point cannot be empty",
+                    name
+                )));
+            }
+            Self::validate_entry_point(&command.entry_point)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that every command's entry point exists in the extracted plugin.
+    fn check_command_files(
+        commands: &BTreeMap<String, PluginCommand>,
+        plugin_dir: &Path,
+    ) -> Result<()> {
+        for (name, command) in commands {
+            if !plugin_dir.join(&command.entry_point).is_file() {
+                return Err(AppError::Execution(format!(
+                    "Entry point of command '{}' not found: {}",
+                    name, command.entry_point
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_health_check(health_check: &PluginHealthCheck) -> Result<()> {
+        if let Some(script) = &health_check.script {
+            if script.trim().is_empty() {
+                return Err(AppError::Execution(
+                    "metadata.health_check.script cannot be empty".to_string(),
+                ));
+            }
+            Self::validate_entry_point(script)?;
+        } else if health_check.args.is_empty() {
+            return Err(AppError::Execution(
+                "metadata.health_check needs a script or args".to_string(),
+            ));
+        }
+        if health_check.timeout_ms == Some(0) {
+            return Err(AppError::Execution(
+                "metadata.health_check.timeout_ms must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     fn validate_parameters(parameters: Option<Vec<PluginParameter>>) -> Result<Option<String>> {
         let Some(parameters) = parameters else {
             return Ok(None);
@@ -845,13 +2265,22 @@ impl PluginService {
                     name
                 )));
             }
-            if let Some(default) = &param.default {
-                if !param.param_type.matches(default) {
-                    return Err(crate::error::AppError::Execution(format!(
-                        "Default value for parameter '{}' does not match type {:?}",
-                        name, param.param_type
-                    )));
-                }
+            if let Some(default) = &param.default
+                && !param.param_type.matches(default)
+            {
+                return Err(crate::error::AppError::Execution(format!(
+                    "Default value for parameter '{}' does not match type {:?}",
+                    name, param.param_type
+                )));
+            }
+            if let Some(validation) = &param.validation {
+                Self::validate_parameter_constraints(param, validation)?;
+            }
+            if param.is_secret() && param.choices.is_some() {
+                return Err(crate::error::AppError::Execution(format!(
+                    "Secret parameter '{}' cannot declare choices",
+                    name
+                )));
             }
             if let Some(choices) = &param.choices {
                 if choices.is_empty() {
@@ -902,11 +2331,10 @@ impl PluginService {
                                 }
                                 if let Some(value) =
                                     choice.as_object().and_then(|obj| obj.get("value"))
+                                    && value == item
                                 {
-                                    if value == item {
-                                        item_matches = true;
-                                        break;
-                                    }
+                                    item_matches = true;
+                                    break;
                                 }
                             }
                             if !item_matches {
@@ -923,13 +2351,11 @@ impl PluginService {
                                 default_matches = true;
                                 break;
                             }
-                            if let Some(value) =
-                                choice.as_object().and_then(|obj| obj.get("value"))
+                            if let Some(value) = choice.as_object().and_then(|obj| obj.get("value"))
+                                && value == default
                             {
-                                if value == default {
-                                    default_matches = true;
-                                    break;
-                                }
+                                default_matches = true;
+                                break;
                             }
                         }
                         if !default_matches {
@@ -949,6 +2375,63 @@ impl PluginService {
         Ok(Some(json))
     }
 
+    fn validate_parameter_constraints(
+        param: &PluginParameter,
+        validation: &PluginParameterValidation,
+    ) -> Result<()> {
+        let name = &param.name;
+        if (validation.min.is_some() || validation.max.is_some()) && !param.param_type.is_numeric()
+        {
+            return Err(crate::error::AppError::Execution(format!(
+                "Parameter '{}' min/max only apply to number or integer types",
+                name
+            )));
+        }
+        if let (Some(min), Some(max)) = (validation.min, validation.max)
+            && min > max
+        {
+            return Err(crate::error::AppError::Execution(format!(
+                "Parameter '{}' min {} is greater than max {}",
+                name, min, max
+            )));
+        }
+        if validation.pattern.is_some() && !param.param_type.is_textual() {
+            return Err(crate::error::AppError::Execution(format!(
+                "Parameter '{}' pattern only applies to string types",
+                name
+            )));
+        }
+        let pattern = validation.pattern_regex().transpose().map_err(|e| {
+            crate::error::AppError::Execution(format!(
+                "Invalid pattern for parameter '{}': {}",
+                name, e
+            ))
+        })?;
+
+        let Some(default) = &param.default else {
+            return Ok(());
+        };
+        if let Some(number) = default.as_f64() {
+            let below_min = validation.min.is_some_and(|min| number < min);
+            let above_max = validation.max.is_some_and(|max| number > max);
+            if below_min || above_max {
+                return Err(crate::error::AppError::Execution(format!(
+                    "Default value for parameter '{}' is out of range",
+                    name
+                )));
+            }
+        }
+        if let (Some(pattern), Some(text)) = (pattern, default.as_str())
+            && !pattern.is_match(text)
+        {
+            return Err(crate::error::AppError::Execution(format!(
+                "Default value for parameter '{}' does not match pattern",
+                name
+            )));
+        }
+        Ok(())
+    }
+
     fn validate_groups(groups: Option<Vec<PluginParameterGroup>>) -> Result<Option<String>> {
         let Some(groups) = groups else {
             return Ok(None);
@@ -986,13 +2469,412 @@ impl PluginService {
         Ok(Some(json))
     }
 
+    fn validate_timeouts(
+        default_timeout_ms: Option<u64>,
+        max_timeout_ms: Option<u64>,
+    ) -> Result<(Option<i64>, Option<i64>)> {
+        let to_millis = |field: &str, value: Option<u64>| -> Result<Option<i64>> {
+            match value {
+                Some(0) => Err(AppError::Execution(format!(
+                    "{} must be greater than 0",
+                    field
+                ))),
+                Some(value) => i64::try_from(value)
+                    .map(Some)
+                    .map_err(|_| AppError::Execution(format!("{} is too large", field))),
+                None => Ok(None),
+            }
+        };
+        let default_timeout_ms = to_millis("default_timeout_ms", default_timeout_ms)?;
+        let max_timeout_ms = to_millis("max_timeout_ms", max_timeout_ms)?;
+        if let (Some(default), Some(max)) = (default_timeout_ms, max_timeout_ms)
+            && default > max
+        {
+            return Err(AppError::Execution(format!(
+                "default_timeout_ms ({}) cannot exceed max_timeout_ms ({})",
+                default, max
+            )));
+        }
+        Ok((default_timeout_ms, max_timeout_ms))
+    }
+
+    /// Trims tags and drops case-insensitive duplicates, keeping the first spelling.
+    fn normalize_tags(tags: Option<Vec<String>>) -> Result<Vec<String>> {
+        let mut normalized: Vec<String> = Vec::new();
+        for tag in tags.unwrap_or_default() {
+            let tag = tag.trim();
+            if tag.is_empty() {
+                return Err(AppError::Execution("Tags cannot be empty".to_string()));
+            }
+            if !normalized
+                .iter()
+                .any(|existing| existing.to_lowercase() == tag.to_lowercase())
+            {
+                normalized.push(tag.to_string());
+            }
+        }
+        Ok(normalized)
+    }
+
     fn serialize_metadata(metadata: Option<serde_json::Value>) -> Result<Option<String>> {
         let Some(metadata) = metadata else {
             return Ok(None);
         };
+        if !metadata.is_object() {
+            return Err(AppError::Execution(
+                "metadata must be a JSON object".to_string(),
+            ));
+        }
         let json = serde_json::to_string(&metadata).map_err(|e| {
             crate::error::AppError::Execution(format!("Failed to serialize metadata: {}", e))
         })?;
         Ok(Some(json))
     }
 }
+
+/// Rejects non-HTTP(S) URLs and, when `allowed_hosts` is set, hosts outside it.
+/// An entry also admits its subdomains.
+pub(crate) fn ensure_host_allowed(url: &str, allowed_hosts: &[String]) -> Result<()> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| AppError::Execution(format!("Invalid URL {}: {}", url, e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(AppError::Execution(format!(
+            "URL must use http or https: {}",
+            url
+        )));
+    }
+    if allowed_hosts.is_empty() {
+        return Ok(());
+    }
+    let host = parsed.host_str().unwrap_or_default();
+    if !host_in_allowlist(host, allowed_hosts) {
+        return Err(AppError::Execution(format!(
+            "Host '{}' is not in allowed_hosts",
+            host.to_ascii_lowercase()
+        )));
+    }
+    Ok(())
+}
+
+/// The venv and state dir of a recycled plugin, renamed aside while a new
+/// install of the same id runs. Dropping it without `discard` puts them back.
+struct RecycledRuntime {
+    plugin_id: String,
+    recycled_dir: PathBuf,
+    /// `(original, stashed)` pairs still waiting to be restored.
+    moved: Vec<(PathBuf, PathBuf)>,
+}
+
+impl RecycledRuntime {
+    fn set_aside(service: &PluginService, plugin: &Plugin) -> Result<Self> {
+        let mut runtime = Self {
+            plugin_id: plugin.plugin_id.clone(),
+            recycled_dir: service.recycled_dir_for(&plugin.plugin_id)?,
+            moved: Vec::new(),
+        };
+        let mut dirs = vec![service.state_dir_for(&plugin.plugin_id)?];
+        if let Some(venv_path) = &plugin.python_venv_path
+            && !venv_path.is_empty()
+        {
+            dirs.push(PathBuf::from(venv_path));
+        }
+        for dir in dirs {
+            if !dir.exists() {
+                continue;
+            }
+            let mut stashed = dir.clone().into_os_string();
+            stashed.push(".recycled");
+            let stashed = PathBuf::from(stashed);
+            if stashed.exists() {
+                fs::remove_dir_all(&stashed)?;
+            }
+            // Siblings share a filesystem, so the rename cannot fall back to a copy
+            fs::rename(&dir, &stashed)?;
+            runtime.moved.push((dir, stashed));
+        }
+        Ok(runtime)
+    }
+
+    /// Deletes the recycled files for good once the new install is registered.
+    fn discard(mut self) -> Result<()> {
+        for (_, stashed) in std::mem::take(&mut self.moved) {
+            fs::remove_dir_all(stashed)?;
+        }
+        match fs::remove_dir_all(&self.recycled_dir) {
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl Drop for RecycledRuntime {
+    fn drop(&mut self) {
+        for (original, stashed) in self.moved.drain(..) {
+            // Whatever the failed install left behind gives way to the recycled copy
+            let _ = fs::remove_dir_all(&original);
+            if let Err(err) = fs::rename(&stashed, &original) {
+                tracing::warn!(
+                    "Failed to restore {} for recycled plugin {}: {}",
+                    original.display(),
+                    self.plugin_id,
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// Whether `host` is an `allowed_hosts` entry or a subdomain of one.
+pub(crate) fn host_in_allowlist(host: &str, allowed_hosts: &[String]) -> bool {
+    let host = host.to_ascii_lowercase();
+    allowed_hosts.iter().any(|entry| {
+        let entry = entry.trim().to_ascii_lowercase();
+        host == entry || host.ends_with(&format!(".{}", entry))
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::repository::{PoolSettings, establish_connection};
+    use std::io::Write;
+
+    /// A plugin service over an in-memory database, keeping its files in the
+    /// returned temp dir.
+    async fn service() -> (PluginService, tempfile::TempDir) {
+        service_with_uv(None).await
+    }
+
+    async fn service_with_uv(uv_path: Option<PathBuf>) -> (PluginService, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = establish_connection("sqlite::memory:", PoolSettings::default())
+            .await
+            .unwrap();
+        let service = PluginService::new(PluginRepository::new(pool), uv_path)
+            .with_dirs(paths::Dirs::under(dir.path()));
+        (service, dir)
+    }
+
+    pub(crate) fn package(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in files {
+            writer
+                .start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    pub(crate) fn shell_spec(plugin_id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "plugin_id": plugin_id,
+            "name": plugin_id,
+            "version": "1.0.0",
+            "plugin_type": "shell",
+            "description": "test plugin",
+            "author": "tests",
+            "entry_point": "main.sh",
+        })
+    }
+
+    fn shell_metadata(plugin_id: &str) -> String {
+        shell_spec(plugin_id).to_string()
+    }
+
+    /// Installs a package made of `spec` as metadata.json plus `main.sh`.
+    pub(crate) async fn install_script(
+        service: &PluginService,
+        spec: &serde_json::Value,
+        script: &str,
+    ) -> Result<Plugin> {
+        let metadata = spec.to_string();
+        let bytes = package(&[
+            ("metadata.json", metadata.as_bytes()),
+            ("main.sh", script.as_bytes()),
+        ]);
+        service
+            .install_plugin_from_archive(Cursor::new(bytes))
+            .await
+    }
+
+    async fn install_shell(service: &PluginService, plugin_id: &str) -> Plugin {
+        let metadata = shell_metadata(plugin_id);
+        let bytes = package(&[
+            ("metadata.json", metadata.as_bytes()),
+            ("main.sh", b"echo hello\n"),
+        ]);
+        service
+            .install_plugin_from_archive(Cursor::new(bytes))
+            .await
+            .unwrap()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn python_install_runs_the_configured_uv() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let bin = tempfile::tempdir().unwrap();
+        let log = bin.path().join("uv.log");
+        let uv = bin.path().join("custom-uv");
+        // Records each call and makes `venv` produce an interpreter that reports a version
+        fs::write(
+            &uv,
+            format!(
+                "#!/bin/sh\necho \"$*\" >> '{}'\nif [ \"$1\" = venv ]; then\n  mkdir -p \"$2/bin\"\n  printf '#!/bin/sh\\necho Python 3.12.1\\n' > \"$2/bin/python\"\n  chmod +x \"$2/bin/python\"\nfi\n",
+                log.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&uv, fs::Permissions::from_mode(0o755)).unwrap();
+        let (service, _dir) = service_with_uv(Some(uv)).await;
+
+        let mut spec = shell_spec("custom-uv");
+        spec["plugin_type"] = serde_json::json!("python");
+        spec["entry_point"] = serde_json::json!("main.py");
+        let metadata = spec.to_string();
+        let bytes = package(&[
+            ("metadata.json", metadata.as_bytes()),
+            ("main.py", b"print('hi')\n"),
+        ]);
+        let plugin = service
+            .install_plugin_from_archive(Cursor::new(bytes))
+            .await
+            .unwrap();
+
+        let venv = plugin.python_venv_path.unwrap();
+        let calls = fs::read_to_string(&log).unwrap();
+        let calls: Vec<&str> = calls.lines().collect();
+        assert_eq!(calls[0], format!("venv {}", venv));
+        assert!(calls[1].starts_with("pip freeze --python "), "{:?}", calls);
+        assert_eq!(plugin.python_version.as_deref(), Some("3.12.1"));
+    }
+
+    #[tokio::test]
+    async fn strict_metadata_reports_a_misspelled_field() {
+        let mut spec = shell_spec("typo-plugin");
+        spec["enty_point"] = spec["entry_point"].clone();
+
+        let (lenient, _dir) = service().await;
+        let parsed = PluginService::parse_metadata_spec(spec.to_string().as_bytes()).unwrap();
+        assert_eq!(
+            lenient.check_unknown_fields(&parsed).unwrap().as_deref(),
+            Some("Unknown metadata field(s): enty_point")
+        );
+
+        let (strict, _dir) = service().await;
+        let strict = strict.with_strict_metadata(true);
+        let err = install_script(&strict, &spec, "echo hi\n")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("enty_point"), "{}", err);
+        assert!(matches!(
+            strict.get_plugin("typo-plugin").await,
+            Err(AppError::PluginNotFound(_))
+        ));
+    }
+
+    fn utf16le(text: &str, bom: bool) -> Vec<u8> {
+        let mut bytes = if bom { vec![0xFF, 0xFE] } else { Vec::new() };
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        bytes
+    }
+
+    #[tokio::test]
+    async fn metadata_with_a_utf8_bom_installs() {
+        let (service, _dir) = service().await;
+        let mut metadata = b"\xEF\xBB\xBF".to_vec();
+        metadata.extend(shell_metadata("bom-plugin").as_bytes());
+        let bytes = package(&[("metadata.json", &metadata), ("main.sh", b"echo hi\n")]);
+
+        let plugin = service
+            .install_plugin_from_archive(Cursor::new(bytes))
+            .await
+            .unwrap();
+        assert_eq!(plugin.plugin_id, "bom-plugin");
+    }
+
+    #[test]
+    fn utf16_metadata_is_rejected_clearly() {
+        let metadata = shell_metadata("utf16-plugin");
+        for bytes in [utf16le(&metadata, true), utf16le(&metadata, false)] {
+            let err = PluginService::parse_metadata_spec(&bytes).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Execution error: metadata.json must be UTF-8 (without BOM)"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn soft_deleted_plugins_can_be_restored() {
+        let (service, _dir) = service().await;
+        let plugin = install_shell(&service, "soft-restore").await;
+        let plugin_path = PathBuf::from(&plugin.plugin_path);
+        let recycled = service.recycled_dir_for("soft-restore").unwrap();
+
+        service.soft_delete_plugin("soft-restore").await.unwrap();
+        assert!(matches!(
+            service.get_plugin("soft-restore").await,
+            Err(AppError::PluginNotFound(_))
+        ));
+        assert!(!plugin_path.exists());
+        assert!(recycled.join("main.sh").exists());
+
+        let restored = service.restore_plugin("soft-restore").await.unwrap();
+        assert_eq!(restored.version, "1.0.0");
+        assert!(plugin_path.join("main.sh").exists());
+        assert!(!recycled.exists());
+        service.get_plugin("soft-restore").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_reinstall_keeps_the_recycled_copy() {
+        let (service, _dir) = service().await;
+        install_shell(&service, "soft-reinstall").await;
+        let state = service.state_dir_for("soft-reinstall").unwrap();
+        fs::write(state.join("counter"), "3").unwrap();
+        service.soft_delete_plugin("soft-reinstall").await.unwrap();
+        let recycled = service.recycled_dir_for("soft-reinstall").unwrap();
+
+        let metadata = shell_metadata("soft-reinstall");
+        let missing_entry = package(&[("metadata.json", metadata.as_bytes())]);
+        service
+            .install_plugin_from_archive(Cursor::new(missing_entry))
+            .await
+            .unwrap_err();
+        assert!(recycled.join("main.sh").exists());
+        assert_eq!(fs::read_to_string(state.join("counter")).unwrap(), "3");
+
+        let restored = service.restore_plugin("soft-reinstall").await.unwrap();
+        assert!(Path::new(&restored.plugin_path).join("main.sh").exists());
+        service.soft_delete_plugin("soft-reinstall").await.unwrap();
+
+        install_shell(&service, "soft-reinstall").await;
+        assert!(!recycled.exists());
+        assert!(!state.join("counter").exists());
+    }
+
+    #[tokio::test]
+    async fn recycled_plugins_are_purged_after_the_retention_window() {
+        let (service, _dir) = service().await;
+        let service = service.with_recycle_retention_ms(60_000);
+        install_shell(&service, "soft-purge").await;
+        service.soft_delete_plugin("soft-purge").await.unwrap();
+        let recycled = service.recycled_dir_for("soft-purge").unwrap();
+
+        // Still inside the window
+        assert_eq!(service.purge_expired_recycled().await.unwrap(), 0);
+        assert!(recycled.exists());
+
+        let service = service.with_recycle_retention_ms(0);
+        assert_eq!(service.purge_expired_recycled().await.unwrap(), 1);
+        assert!(!recycled.exists());
+        assert!(matches!(
+            service.restore_plugin("soft-purge").await,
+            Err(AppError::PluginNotFound(_))
+        ));
+    }
+}
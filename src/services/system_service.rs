@@ -0,0 +1,100 @@
+use crate::error::{AppError, Result};
+use crate::paths;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Debug, Serialize)]
+pub struct SystemUsage {
+    pub plugins_bytes: u64,
+    pub python_envs_bytes: u64,
+    pub work_dir_bytes: u64,
+    pub total_bytes: u64,
+    pub plugin_count: u64,
+    pub python_env_count: u64,
+    pub execution_dir_count: u64,
+}
+
+/// The resolved config plus the directories it leads to.
+#[derive(Debug, Serialize)]
+pub struct EffectiveConfig {
+    #[serde(flatten)]
+    pub config: Map<String, Value>,
+    pub paths: ResolvedPaths,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolvedPaths {
+    pub install_root: PathBuf,
+    pub conf_dir: PathBuf,
+    pub data_dir: PathBuf,
+    pub plugins_dir: PathBuf,
+    pub work_dir: PathBuf,
+    pub python_envs_dir: PathBuf,
+    pub recycle_bin_dir: PathBuf,
+    pub plugin_state_dir: PathBuf,
+}
+
+#[derive(Clone)]
+pub struct SystemService {
+    config: Arc<Map<String, Value>>,
+    dirs: paths::Dirs,
+}
+
+impl SystemService {
+    /// `config` holds the settings safe to report; `effective_config` returns it as-is.
+    pub fn new(config: Map<String, Value>) -> Self {
+        Self {
+            config: Arc::new(config),
+            dirs: paths::Dirs::default(),
+        }
+    }
+
+    pub fn with_dirs(mut self, dirs: paths::Dirs) -> Self {
+        self.dirs = dirs;
+        self
+    }
+
+    /// The running server's config with credentials redacted.
+    pub fn effective_config(&self) -> Result<EffectiveConfig> {
+        Ok(EffectiveConfig {
+            config: (*self.config).clone(),
+            paths: ResolvedPaths {
+                install_root: paths::install_root()?,
+                conf_dir: paths::conf_dir()?,
+                data_dir: self.dirs.data_dir()?,
+                plugins_dir: self.dirs.plugins_dir()?,
+                work_dir: self.dirs.work_dir()?,
+                python_envs_dir: self.dirs.python_envs_dir()?,
+                recycle_bin_dir: self.dirs.recycle_bin_dir()?,
+                plugin_state_dir: self.dirs.plugin_state_dir()?,
+            },
+        })
+    }
+
+    pub async fn usage(&self) -> Result<SystemUsage> {
+        let dirs = self.dirs.clone();
+        tokio::task::spawn_blocking(move || {
+            let plugins_dir = dirs.plugins_dir()?;
+            let python_envs_dir = dirs.python_envs_dir()?;
+            let work_dir = dirs.work_dir()?;
+
+            let plugins_bytes = paths::dir_size(&plugins_dir)?;
+            let python_envs_bytes = paths::dir_size(&python_envs_dir)?;
+            let work_dir_bytes = paths::dir_size(&work_dir)?;
+
+            Ok(SystemUsage {
+                plugins_bytes,
+                python_envs_bytes,
+                work_dir_bytes,
+                total_bytes: plugins_bytes + python_envs_bytes + work_dir_bytes,
+                plugin_count: paths::count_entries(&plugins_dir)?,
+                python_env_count: paths::count_entries(&python_envs_dir)?,
+                execution_dir_count: paths::count_entries(&work_dir)?,
+            })
+        })
+        .await
+        .map_err(|e| AppError::Execution(format!("Failed to compute disk usage: {}", e)))?
+    }
+}
@@ -1,6 +1,11 @@
 use crate::error::{AppError, Result};
+use crate::models::{Plugin, PluginType};
 use crate::paths;
+use crate::shutdown::Shutdown;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use chrono::Utc;
+use ed25519_dalek::{Signature, VerifyingKey};
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -11,6 +16,8 @@ use uuid::Uuid;
 const UPDATE_PENDING_FILE: &str = ".update_pending.json";
 const UPDATE_STAGING_DIR: &str = ".update_staging";
 const PRESERVE_DIRS: [&str; 4] = ["data", "plugins", "work_dir", "conf"];
+const GITHUB_API_URL: &str = "https://api.github.com";
+pub const DEFAULT_ASSET_PATTERN: &str = "anthill-{version}-{os}-{arch}.zip";
 
 #[derive(Debug, Serialize, Deserialize)]
 struct PendingUpdate {
@@ -19,6 +26,18 @@ struct PendingUpdate {
     package_version: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct UpdateStatus {
     pub restart_required: bool,
@@ -26,15 +45,100 @@ pub struct UpdateStatus {
     pub package_version: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct RestartStatus {
+    pub update_applied: bool,
+    pub current_version: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PendingStatus {
+    pub current_version: String,
+    pub pending: bool,
+    pub staged_version: Option<String>,
+    pub staged_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PluginImpact {
+    pub plugin_id: String,
+    pub name: String,
+    pub incompatible: bool,
+    pub venv_rebuild: bool,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateImpact {
+    pub current_version: String,
+    pub package_version: String,
+    pub newer: bool,
+    pub plugins: Vec<PluginImpact>,
+}
+
 #[derive(Clone)]
-pub struct UpdateService;
+pub struct UpdateService {
+    public_key: Option<VerifyingKey>,
+    asset_pattern: String,
+    preserve_dirs: Vec<String>,
+    shutdown: Option<Shutdown>,
+}
+
+impl Default for UpdateService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl UpdateService {
     pub fn new() -> Self {
-        Self
+        Self {
+            public_key: None,
+            asset_pattern: DEFAULT_ASSET_PATTERN.to_string(),
+            preserve_dirs: PRESERVE_DIRS.iter().map(|dir| dir.to_string()).collect(),
+            shutdown: None,
+        }
+    }
+
+    /// Lets [`UpdateService::restart`] stop the server; `main` relaunches it.
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = Some(shutdown);
+        self
     }
 
-    pub async fn stage_update(&self, package_url: String) -> Result<UpdateStatus> {
+    /// Adds install root directories that updates must keep, on top of the defaults.
+    pub fn with_preserve_dirs(mut self, extra_dirs: Vec<String>) -> Self {
+        for dir in extra_dirs {
+            if !self.preserve_dirs.contains(&dir) {
+                self.preserve_dirs.push(dir);
+            }
+        }
+        self
+    }
+
+    /// Asset name template for GitHub releases; supports `{version}`, `{os}` and `{arch}`.
+    pub fn with_asset_pattern(mut self, asset_pattern: String) -> Self {
+        self.asset_pattern = asset_pattern;
+        self
+    }
+
+    /// Requires staged packages to carry an ed25519 signature from this base64 public key.
+    pub fn with_public_key(mut self, public_key: &str) -> Result<Self> {
+        let bytes: [u8; 32] = decode_base64(public_key, "update public key")?
+            .try_into()
+            .map_err(|_| AppError::Execution("Update public key must be 32 bytes".to_string()))?;
+        let key = VerifyingKey::from_bytes(&bytes)
+            .map_err(|e| AppError::Execution(format!("Invalid update public key: {}", e)))?;
+        self.public_key = Some(key);
+        Ok(self)
+    }
+
+    pub async fn stage_update(
+        &self,
+        package_url: String,
+        signature: Option<String>,
+        signature_url: Option<String>,
+    ) -> Result<UpdateStatus> {
         let install_root = paths::install_root()?;
         let pending_path = pending_update_path(&install_root);
         if pending_path.exists() {
@@ -44,6 +148,8 @@ impl UpdateService {
         }
 
         let bytes = fetch_bytes(&package_url, "update package").await?;
+        self.verify_signature(&bytes, signature, signature_url)
+            .await?;
 
         let extract_dir = tempfile::Builder::new()
             .prefix("update_extract_")
@@ -83,22 +189,254 @@ impl UpdateService {
         })
     }
 
-    pub fn apply_pending_update() -> Result<Option<PathBuf>> {
+    /// Resolves the download URL of the release asset matching this platform.
+    pub async fn resolve_release_url(&self, repo: &str, tag: Option<&str>) -> Result<String> {
+        let valid_repo = repo.split_once('/').is_some_and(|(owner, name)| {
+            !owner.is_empty() && !name.is_empty() && !name.contains('/')
+        });
+        if !valid_repo {
+            return Err(AppError::Execution(format!(
+                "Invalid repository '{}', expected owner/name",
+                repo
+            )));
+        }
+
+        let url = match tag.map(str::trim) {
+            None | Some("") | Some("latest") => {
+                format!("{}/repos/{}/releases/latest", GITHUB_API_URL, repo)
+            }
+            Some(tag) => format!("{}/repos/{}/releases/tags/{}", GITHUB_API_URL, repo, tag),
+        };
+        let response = reqwest::Client::new()
+            .get(&url)
+            .header(reqwest::header::USER_AGENT, "anthill")
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AppError::Execution(format!("Failed to query release: {}", e)))?;
+        let body = response
+            .text()
+            .await
+            .map_err(|e| AppError::Execution(format!("Failed to read release: {}", e)))?;
+        let release: GithubRelease = serde_json::from_str(&body)
+            .map_err(|e| AppError::Execution(format!("Invalid release response: {}", e)))?;
+
+        let version = release.tag_name.trim_start_matches('v');
+        let asset_name = self
+            .asset_pattern
+            .replace("{version}", version)
+            .replace("{os}", platform_os())
+            .replace("{arch}", std::env::consts::ARCH);
+        release
+            .assets
+            .into_iter()
+            .find(|asset| asset.name == asset_name)
+            .map(|asset| asset.browser_download_url)
+            .ok_or_else(|| {
+                AppError::Execution(format!(
+                    "Release {} has no asset named {}",
+                    release.tag_name, asset_name
+                ))
+            })
+    }
+
+    async fn verify_signature(
+        &self,
+        package: &[u8],
+        signature: Option<String>,
+        signature_url: Option<String>,
+    ) -> Result<()> {
+        let Some(public_key) = &self.public_key else {
+            if signature.is_some() || signature_url.is_some() {
+                tracing::warn!("Ignoring update signature: no update public key is configured");
+            }
+            return Ok(());
+        };
+
+        let signature = match (signature, signature_url) {
+            (Some(signature), _) => decode_base64(&signature, "update signature")?,
+            (None, Some(url)) => {
+                let bytes = fetch_bytes(&url, "update signature").await?;
+                if bytes.len() == Signature::BYTE_SIZE {
+                    bytes
+                } else {
+                    decode_base64(&String::from_utf8_lossy(&bytes), "update signature")?
+                }
+            }
+            (None, None) => {
+                return Err(AppError::Execution(
+                    "Update package must be signed".to_string(),
+                ));
+            }
+        };
+        let signature = Signature::from_slice(&signature)
+            .map_err(|e| AppError::Execution(format!("Invalid update signature: {}", e)))?;
+
+        public_key
+            .verify_strict(package, &signature)
+            .map_err(|_| AppError::Execution("Update signature verification failed".to_string()))
+    }
+
+    pub async fn check_impact(
+        &self,
+        package_url: String,
+        plugins: &[Plugin],
+    ) -> Result<UpdateImpact> {
+        let install_root = paths::install_root()?;
+        let bytes = fetch_bytes(&package_url, "update package").await?;
+
+        let extract_dir = tempfile::Builder::new()
+            .prefix("update_impact_")
+            .tempdir()
+            .map_err(|e| {
+                AppError::Execution(format!("Failed to create update extract dir: {}", e))
+            })?;
+        extract_zip(&bytes, extract_dir.path())?;
+        let update_root = detect_update_root(extract_dir.path())?;
+        let package_version = read_update_version(&update_root)?;
+        let candidate = Version::parse(&package_version).map_err(|e| {
+            AppError::Execution(format!(
+                "Invalid update version '{}': {}",
+                package_version, e
+            ))
+        })?;
+        let newer = ensure_newer_version(&package_version).is_ok();
+        let replaced = replaced_entries(&update_root, &install_root, &self.preserve_dirs)?;
+
+        let mut impacts = Vec::new();
+        for plugin in plugins {
+            let mut reasons = Vec::new();
+
+            let incompatible = match plugin
+                .min_anthill_version
+                .as_deref()
+                .and_then(|raw| Version::parse(raw).ok())
+            {
+                Some(required) if candidate < required => {
+                    reasons.push(format!(
+                        "Requires anthill >= {}, update provides {}",
+                        required, candidate
+                    ));
+                    true
+                }
+                _ => false,
+            };
+
+            let venv_rebuild = match plugin.python_venv_path.as_deref() {
+                Some(venv_path)
+                    if plugin.plugin_type == PluginType::Python && !venv_path.is_empty() =>
+                {
+                    match venv_rebuild_reason(Path::new(venv_path), &install_root, &replaced) {
+                        Some(reason) => {
+                            reasons.push(reason);
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                _ => false,
+            };
+
+            if incompatible || venv_rebuild {
+                impacts.push(PluginImpact {
+                    plugin_id: plugin.plugin_id.clone(),
+                    name: plugin.name.clone(),
+                    incompatible,
+                    venv_rebuild,
+                    reasons,
+                });
+            }
+        }
+
+        Ok(UpdateImpact {
+            current_version: current_version_string(),
+            package_version,
+            newer,
+            plugins: impacts,
+        })
+    }
+
+    pub fn pending_status(&self) -> Result<PendingStatus> {
+        let install_root = paths::install_root()?;
+        let pending = read_pending_update(&pending_update_path(&install_root))?;
+
+        Ok(PendingStatus {
+            current_version: current_version_string(),
+            pending: pending.is_some(),
+            staged_version: pending
+                .as_ref()
+                .and_then(|pending| pending.package_version.clone()),
+            staged_at: pending.map(|pending| pending.created_at),
+        })
+    }
+
+    pub fn cancel_pending(&self) -> Result<()> {
         let install_root = paths::install_root()?;
         let pending_path = pending_update_path(&install_root);
-        if !pending_path.is_file() {
-            return Ok(None);
+        let Some(pending) = read_pending_update(&pending_path)? else {
+            return Err(AppError::Execution("No update is pending".to_string()));
+        };
+
+        let staged_path = PathBuf::from(&pending.staged_path);
+        // Only ever delete inside the staging dir, never elsewhere under install root
+        if !staged_path.starts_with(update_staging_root(&install_root))
+            || staged_path
+                .components()
+                .any(|component| matches!(component, std::path::Component::ParentDir))
+        {
+            return Err(AppError::Execution(
+                "Staged update is outside install root".to_string(),
+            ));
         }
 
-        let content = fs::read_to_string(&pending_path).map_err(|e| {
+        if staged_path.exists() {
+            fs::remove_dir_all(&staged_path).map_err(|e| {
+                AppError::Execution(format!(
+                    "Failed to remove staged update {}: {}",
+                    staged_path.display(),
+                    e
+                ))
+            })?;
+        }
+        fs::remove_file(&pending_path).map_err(|e| {
             AppError::Execution(format!(
-                "Failed to read update metadata {}: {}",
+                "Failed to remove update metadata {}: {}",
                 pending_path.display(),
                 e
             ))
         })?;
-        let pending: PendingUpdate = serde_json::from_str(&content)
-            .map_err(|e| AppError::Execution(format!("Invalid update metadata: {}", e)))?;
+
+        tracing::info!(
+            "Cancelled pending update {}",
+            pending.package_version.as_deref().unwrap_or("unknown")
+        );
+        Ok(())
+    }
+
+    /// Applies a pending update and stops the server gracefully, so the
+    /// response still goes out and running executions record their result.
+    pub fn restart(&self) -> Result<RestartStatus> {
+        let Some(shutdown) = &self.shutdown else {
+            return Err(AppError::Execution(
+                "Restart is not available in this process".to_string(),
+            ));
+        };
+        let applied = self.apply_pending_update()?;
+        shutdown.restart();
+
+        Ok(RestartStatus {
+            update_applied: applied.is_some(),
+            current_version: current_version_string(),
+        })
+    }
+
+    pub fn apply_pending_update(&self) -> Result<Option<PathBuf>> {
+        let install_root = paths::install_root()?;
+        let pending_path = pending_update_path(&install_root);
+        let Some(pending) = read_pending_update(&pending_path)? else {
+            return Ok(None);
+        };
 
         let staged_path = PathBuf::from(&pending.staged_path);
         if !staged_path.is_dir() {
@@ -113,7 +451,7 @@ impl UpdateService {
             ));
         }
 
-        apply_update_from_staged(&staged_path, &install_root)?;
+        apply_update_from_staged(&staged_path, &install_root, &self.preserve_dirs)?;
         fs::remove_file(&pending_path).map_err(|e| {
             AppError::Execution(format!(
                 "Failed to remove update metadata {}: {}",
@@ -140,6 +478,68 @@ fn pending_update_path(install_root: &Path) -> PathBuf {
     install_root.join(UPDATE_PENDING_FILE)
 }
 
+/// Top-level install root entries that applying the update would overwrite.
+fn replaced_entries(
+    update_root: &Path,
+    install_root: &Path,
+    preserve_dirs: &[String],
+) -> Result<Vec<String>> {
+    let mut replaced = Vec::new();
+    for entry in fs::read_dir(update_root)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if preserve_dirs.contains(&name) && install_root.join(&name).exists() {
+            continue;
+        }
+        replaced.push(name);
+    }
+    Ok(replaced)
+}
+
+fn venv_rebuild_reason(
+    venv_dir: &Path,
+    install_root: &Path,
+    replaced: &[String],
+) -> Option<String> {
+    let config_path = venv_dir.join("pyvenv.cfg");
+    let Ok(content) = fs::read_to_string(&config_path) else {
+        return Some(format!("Venv config missing at {}", config_path.display()));
+    };
+    let home = content.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "home").then(|| PathBuf::from(value.trim()))
+    })?;
+
+    let top_level = home
+        .strip_prefix(install_root)
+        .ok()
+        .and_then(|relative| relative.components().next())
+        .map(|component| component.as_os_str().to_string_lossy().to_string())?;
+    if replaced.contains(&top_level) {
+        return Some(format!(
+            "Venv interpreter at {} is replaced by the update",
+            home.display()
+        ));
+    }
+    None
+}
+
+fn read_pending_update(pending_path: &Path) -> Result<Option<PendingUpdate>> {
+    if !pending_path.is_file() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(pending_path).map_err(|e| {
+        AppError::Execution(format!(
+            "Failed to read update metadata {}: {}",
+            pending_path.display(),
+            e
+        ))
+    })?;
+    let pending = serde_json::from_str(&content)
+        .map_err(|e| AppError::Execution(format!("Invalid update metadata: {}", e)))?;
+    Ok(Some(pending))
+}
+
 fn update_staging_root(install_root: &Path) -> PathBuf {
     install_root.join(UPDATE_STAGING_DIR)
 }
@@ -299,6 +699,20 @@ fn ensure_newer_version(package_version: &str) -> Result<()> {
     Ok(())
 }
 
+fn decode_base64(raw: &str, label: &str) -> Result<Vec<u8>> {
+    BASE64
+        .decode(raw.trim())
+        .map_err(|e| AppError::Execution(format!("Invalid {} encoding: {}", label, e)))
+}
+
+/// OS name as used in bundle names by `scripts/package_bundle.sh`.
+fn platform_os() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    }
+}
+
 fn current_version_string() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
@@ -342,7 +756,11 @@ fn extract_zip(bytes: &[u8], target_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-fn apply_update_from_staged(staged_root: &Path, install_root: &Path) -> Result<()> {
+fn apply_update_from_staged(
+    staged_root: &Path,
+    install_root: &Path,
+    preserve_dirs: &[String],
+) -> Result<()> {
     let entries = fs::read_dir(staged_root).map_err(|e| {
         AppError::Execution(format!(
             "Failed to read staged update {}: {}",
@@ -361,7 +779,7 @@ fn apply_update_from_staged(staged_root: &Path, install_root: &Path) -> Result<(
         })?;
         let name = entry.file_name();
         let name_str = name.to_string_lossy();
-        if PRESERVE_DIRS.contains(&name_str.as_ref()) {
+        if preserve_dirs.iter().any(|dir| dir == name_str.as_ref()) {
             let dest = install_root.join(&name);
             if dest.exists() {
                 continue;
@@ -483,3 +901,133 @@ fn ensure_executable(path: &Path) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::plugin_repository::tests::plugin;
+    use crate::services::plugin_service::tests::package;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    #[tokio::test]
+    async fn check_impact_flags_incompatible_plugins_and_stale_venvs() {
+        let dir = tempfile::tempdir().unwrap();
+        let package_path = dir.path().join("anthill.zip");
+        fs::write(
+            &package_path,
+            package(&[("VERSION", b"99.0.0"), ("bin/anthill", b"binary")]),
+        )
+        .unwrap();
+
+        let venv = dir.path().join("venv");
+        fs::create_dir_all(&venv).unwrap();
+        let interpreter = paths::install_root().unwrap().join("bin");
+        fs::write(
+            venv.join("pyvenv.cfg"),
+            format!("home = {}\n", interpreter.display()),
+        )
+        .unwrap();
+
+        let plugins = vec![
+            Plugin {
+                plugin_id: "too-new".to_string(),
+                min_anthill_version: Some("100.0.0".to_string()),
+                ..plugin("p1")
+            },
+            Plugin {
+                plugin_id: "compatible".to_string(),
+                min_anthill_version: Some("1.0.0".to_string()),
+                ..plugin("p2")
+            },
+            Plugin {
+                plugin_id: "python".to_string(),
+                plugin_type: PluginType::Python,
+                python_venv_path: Some(venv.to_string_lossy().to_string()),
+                ..plugin("p3")
+            },
+        ];
+
+        let impact = UpdateService::new()
+            .check_impact(package_path.to_string_lossy().to_string(), &plugins)
+            .await
+            .unwrap();
+
+        assert_eq!(impact.package_version, "99.0.0");
+        assert!(impact.newer);
+        let flagged: Vec<_> = impact
+            .plugins
+            .iter()
+            .map(|p| p.plugin_id.as_str())
+            .collect();
+        assert_eq!(flagged, ["too-new", "python"]);
+
+        let too_new = &impact.plugins[0];
+        assert!(too_new.incompatible && !too_new.venv_rebuild);
+        assert_eq!(
+            too_new.reasons,
+            ["Requires anthill >= 100.0.0, update provides 99.0.0"]
+        );
+
+        let python = &impact.plugins[1];
+        assert!(python.venv_rebuild && !python.incompatible);
+        assert!(python.reasons[0].contains("replaced by the update"));
+    }
+
+    #[tokio::test]
+    async fn verify_signature_requires_a_valid_signature_once_a_key_is_set() {
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = BASE64.encode(signing_key.verifying_key().to_bytes());
+        let service = UpdateService::new().with_public_key(&public_key).unwrap();
+        let package = b"update package";
+        let signature = signing_key.sign(package).to_bytes();
+
+        service
+            .verify_signature(package, Some(BASE64.encode(signature)), None)
+            .await
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let signature_path = dir.path().join("anthill.zip.sig");
+        fs::write(&signature_path, signature).unwrap();
+        service
+            .verify_signature(
+                package,
+                None,
+                Some(signature_path.to_string_lossy().to_string()),
+            )
+            .await
+            .unwrap();
+
+        let forged = SigningKey::from_bytes(&[8; 32]).sign(package).to_bytes();
+        let err = service
+            .verify_signature(package, Some(BASE64.encode(forged)), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("verification failed"), "{err}");
+
+        let err = service
+            .verify_signature(b"tampered package", Some(BASE64.encode(signature)), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("verification failed"), "{err}");
+
+        let err = service
+            .verify_signature(package, None, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("must be signed"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn verify_signature_is_optional_without_a_key() {
+        let service = UpdateService::new();
+        service
+            .verify_signature(b"update package", None, None)
+            .await
+            .unwrap();
+        service
+            .verify_signature(b"update package", Some("not base64".to_string()), None)
+            .await
+            .unwrap();
+    }
+}
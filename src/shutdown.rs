@@ -0,0 +1,70 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// Stops the server gracefully; `main` relaunches the binary afterwards when a
+/// restart was asked for. Shared by the restart endpoint and the Windows tray.
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+    restart: Arc<AtomicBool>,
+}
+
+impl Shutdown {
+    /// The receiver completes once a stop or restart is requested.
+    pub fn new() -> (Self, oneshot::Receiver<()>) {
+        let (sender, receiver) = oneshot::channel();
+        let shutdown = Self {
+            sender: Arc::new(Mutex::new(Some(sender))),
+            restart: Arc::new(AtomicBool::new(false)),
+        };
+        (shutdown, receiver)
+    }
+
+    pub fn stop(&self) {
+        if let Some(sender) = self.sender.lock().unwrap().take() {
+            let _ = sender.send(());
+        }
+    }
+
+    pub fn restart(&self) {
+        self.restart.store(true, Ordering::Relaxed);
+        self.stop();
+    }
+
+    pub fn restart_requested(&self) -> bool {
+        self.restart.load(Ordering::Relaxed)
+    }
+}
+
+/// Replaces the process with a fresh `exe` on Unix; elsewhere starts it and
+/// returns so the caller can exit.
+pub fn relaunch(exe: &Path) -> std::io::Result<()> {
+    let mut command = std::process::Command::new(exe);
+    command.args(std::env::args_os().skip(1));
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        Err(command.exec())
+    }
+    #[cfg(not(unix))]
+    command.spawn().map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn restart_stops_once_and_is_remembered() {
+        let (shutdown, stopped) = Shutdown::new();
+        assert!(!shutdown.restart_requested());
+
+        shutdown.clone().restart();
+        shutdown.stop();
+
+        stopped.await.unwrap();
+        assert!(shutdown.restart_requested());
+    }
+}
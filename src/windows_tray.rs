@@ -1,23 +1,69 @@
-use tray_icon::menu::{Menu, MenuEvent, MenuItem};
+use crate::shutdown::Shutdown;
+use std::io::Write;
+use std::os::windows::process::CommandExt;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
 use tray_icon::{Icon, TrayIconBuilder};
+use windows_sys::Win32::System::Threading::GetCurrentThreadId;
 use windows_sys::Win32::UI::WindowsAndMessaging::{
-    DispatchMessageW, GetMessageW, MSG, PostQuitMessage, TranslateMessage,
+    DispatchMessageW, GetMessageW, MSG, PostQuitMessage, PostThreadMessageW, SetTimer,
+    TranslateMessage, WM_QUIT, WM_TIMER,
 };
 
-pub fn run_tray_loop(shutdown_tx: tokio::sync::oneshot::Sender<()>) -> anyhow::Result<()> {
+/// Counts shown in the tooltip, refreshed by the server runtime.
+pub static PLUGIN_COUNT: AtomicUsize = AtomicUsize::new(0);
+pub static RUNNING_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Thread running the tray loop, so [`quit`] can end it from elsewhere.
+static TRAY_THREAD_ID: AtomicU32 = AtomicU32::new(0);
+
+const TOOLTIP_REFRESH_MS: u32 = 5_000;
+
+/// Hides the console window that `explorer`/`clip` would otherwise flash.
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// URL a local browser can reach the server at; wildcard binds map to loopback.
+pub fn base_url(host: &str, port: u16) -> String {
+    let host = match host {
+        "0.0.0.0" | "" => "127.0.0.1",
+        "::" | "[::]" => "[::1]",
+        host => host,
+    };
+    if host.contains(':') && !host.starts_with('[') {
+        format!("http://[{}]:{}", host, port)
+    } else {
+        format!("http://{}:{}", host, port)
+    }
+}
+
+pub fn run_tray_loop(base_url: String, shutdown: Shutdown) -> anyhow::Result<()> {
     let menu = Menu::new();
+    let open_item = MenuItem::new("Open dashboard", true, None);
+    let copy_item = MenuItem::new("Copy API URL", true, None);
+    let restart_item = MenuItem::new("Restart", true, None);
     let exit_item = MenuItem::new("Exit", true, None);
+    menu.append(&open_item)?;
+    menu.append(&copy_item)?;
+    menu.append(&PredefinedMenuItem::separator())?;
+    menu.append(&restart_item)?;
     menu.append(&exit_item)?;
 
     let icon = build_tray_icon()?;
-    let _tray_icon = TrayIconBuilder::new()
+    let tray_icon = TrayIconBuilder::new()
         .with_menu(Box::new(menu))
-        .with_tooltip("anthill")
+        .with_tooltip(status_tooltip())
         .with_icon(icon)
         .build()?;
+    // A thread timer posts WM_TIMER to this loop's queue
+    unsafe {
+        SetTimer(std::ptr::null_mut(), 0, TOOLTIP_REFRESH_MS, None);
+    }
 
+    let open_id = open_item.id().clone();
+    let copy_id = copy_item.id().clone();
+    let restart_id = restart_item.id().clone();
     let exit_id = exit_item.id().clone();
-    let mut shutdown_tx = Some(shutdown_tx);
+    TRAY_THREAD_ID.store(unsafe { GetCurrentThreadId() }, Ordering::Relaxed);
 
     loop {
         let mut msg = std::mem::MaybeUninit::<MSG>::zeroed();
@@ -30,15 +76,29 @@ pub fn run_tray_loop(shutdown_tx: tokio::sync::oneshot::Sender<()>) -> anyhow::R
         }
 
         let msg = unsafe { msg.assume_init() };
+        if msg.message == WM_TIMER {
+            let _ = tray_icon.set_tooltip(Some(status_tooltip()));
+        }
         unsafe {
             TranslateMessage(&msg);
             DispatchMessageW(&msg);
         }
 
         while let Ok(event) = MenuEvent::receiver().try_recv() {
-            if event.id == exit_id {
-                if let Some(tx) = shutdown_tx.take() {
-                    let _ = tx.send(());
+            if event.id == open_id {
+                if let Err(err) = open_in_browser(&base_url) {
+                    tracing::warn!("Failed to open {}: {}", base_url, err);
+                }
+            } else if event.id == copy_id {
+                let api_url = format!("{}/api", base_url);
+                if let Err(err) = copy_to_clipboard(&api_url) {
+                    tracing::warn!("Failed to copy {} to the clipboard: {}", api_url, err);
+                }
+            } else if event.id == exit_id || event.id == restart_id {
+                if event.id == restart_id {
+                    shutdown.restart();
+                } else {
+                    shutdown.stop();
                 }
                 unsafe {
                     PostQuitMessage(0);
@@ -50,6 +110,43 @@ pub fn run_tray_loop(shutdown_tx: tokio::sync::oneshot::Sender<()>) -> anyhow::R
     Ok(())
 }
 
+/// Ends the tray loop from another thread, e.g. after a restart over the API.
+pub fn quit() {
+    let thread_id = TRAY_THREAD_ID.load(Ordering::Relaxed);
+    if thread_id != 0 {
+        unsafe {
+            PostThreadMessageW(thread_id, WM_QUIT, 0, 0);
+        }
+    }
+}
+
+fn status_tooltip() -> String {
+    format!(
+        "anthill: {} plugins, {} running",
+        PLUGIN_COUNT.load(Ordering::Relaxed),
+        RUNNING_COUNT.load(Ordering::Relaxed)
+    )
+}
+
+fn open_in_browser(url: &str) -> std::io::Result<()> {
+    std::process::Command::new("explorer")
+        .arg(url)
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn()
+        .map(|_| ())
+}
+
+fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    let mut child = std::process::Command::new("clip")
+        .stdin(std::process::Stdio::piped())
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    child.wait().map(|_| ())
+}
+
 fn build_tray_icon() -> anyhow::Result<Icon> {
     const SIZE: u32 = 32;
     let mut rgba = vec![0u8; (SIZE * SIZE * 4) as usize];